@@ -0,0 +1,101 @@
+//! examples/generic-resources.rs
+
+#![no_main]
+#![no_std]
+#![deny(warnings)]
+#![deny(unsafe_code)]
+#![deny(missing_docs)]
+#![allow(unexpected_cfgs)]
+
+use panic_semihosting as _;
+
+/// Board support, each board selects its own status LED
+pub mod board {
+    /// A LED that can be toggled
+    pub trait Led {
+        /// Toggles the LED and returns its new state
+        fn toggle(&mut self) -> bool;
+    }
+
+    /// A LED connected to pin `N`
+    pub struct Pin<const N: u8> {
+        on: bool,
+    }
+
+    impl<const N: u8> Pin<N> {
+        /// Takes the pin
+        pub const fn new() -> Self {
+            Pin { on: false }
+        }
+    }
+
+    impl<const N: u8> Led for Pin<N> {
+        fn toggle(&mut self) -> bool {
+            self.on = !self.on;
+            self.on
+        }
+    }
+
+    /// The status LED of board B
+    #[cfg(feature = "board_b")]
+    pub type StatusLed = Pin<7>;
+
+    /// The status LED of board A
+    #[cfg(not(feature = "board_b"))]
+    pub type StatusLed = Pin<5>;
+
+    /// Number of samples buffered by board B
+    #[cfg(feature = "board_b")]
+    pub const SAMPLES: usize = 8;
+
+    /// Number of samples buffered by board A
+    #[cfg(not(feature = "board_b"))]
+    pub const SAMPLES: usize = 4;
+}
+
+#[rtic::app(device = lm3s6965, dispatchers = [SSI0])]
+mod app {
+    use crate::board::{self, Led};
+    use cortex_m_semihosting::{debug, hprintln};
+    use heapless::Vec;
+
+    // The arguments in the return type of `init` select the concrete resource types, the bounds
+    // are checked against them
+    #[shared]
+    struct Shared<L: Led> {
+        led: L,
+    }
+
+    // Without an argument in the return type of `init`, the default is used. A default can use
+    // the parameters declared before it
+    #[local]
+    struct Local<const N: usize = { board::SAMPLES }, S = Vec<u8, N>> {
+        samples: S,
+    }
+
+    #[init]
+    fn init(_: init::Context) -> (Shared<board::StatusLed>, Local) {
+        blink::spawn().ok();
+
+        (
+            Shared {
+                led: board::StatusLed::new(),
+            },
+            Local {
+                samples: Vec::new(),
+            },
+        )
+    }
+
+    #[task(shared = [led], local = [samples])]
+    async fn blink(mut cx: blink::Context) {
+        while cx.local.samples.push(0).is_ok() {
+            let on = cx.shared.led.lock(|led| led.toggle());
+            hprintln!("led on: {}", on);
+        }
+
+        hprintln!("{} samples", cx.local.samples.len());
+
+        debug::exit(debug::EXIT_SUCCESS); // Exit QEMU simulator
+    }
+}
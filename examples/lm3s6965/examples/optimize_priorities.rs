@@ -0,0 +1,53 @@
+//! examples/optimize_priorities.rs
+
+#![no_main]
+#![no_std]
+#![deny(warnings)]
+#![deny(unsafe_code)]
+#![deny(missing_docs)]
+
+use panic_semihosting as _;
+
+// The priorities 10, 20 and 30 are compressed to 1, 2 and 3
+#[rtic::app(device = lm3s6965, dispatchers = [SSI0, QEI0, UART1], optimize_priorities = true)]
+mod app {
+    use cortex_m_semihosting::{debug, hprintln};
+
+    #[shared]
+    struct Shared {}
+
+    #[local]
+    struct Local {}
+
+    #[init]
+    fn init(_: init::Context) -> (Shared, Local) {
+        for (declared, used) in PRIORITY_MAP {
+            hprintln!("priority {} -> {}", declared, used);
+        }
+
+        low::spawn().ok();
+
+        (Shared {}, Local {})
+    }
+
+    #[task(priority = 10)]
+    async fn low(_: low::Context) {
+        hprintln!("low");
+        mid::spawn().ok();
+        hprintln!("low done");
+
+        debug::exit(debug::EXIT_SUCCESS); // Exit QEMU simulator
+    }
+
+    #[task(priority = 20)]
+    async fn mid(_: mid::Context) {
+        hprintln!("mid");
+        high::spawn().ok();
+        hprintln!("mid done");
+    }
+
+    #[task(priority = 30)]
+    async fn high(_: high::Context) {
+        hprintln!("high");
+    }
+}
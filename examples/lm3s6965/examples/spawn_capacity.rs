@@ -0,0 +1,63 @@
+//! examples/spawn_capacity.rs
+
+#![no_main]
+#![no_std]
+#![deny(warnings)]
+#![deny(unsafe_code)]
+#![deny(missing_docs)]
+
+use panic_semihosting as _;
+
+#[rtic::app(device = lm3s6965, dispatchers = [SSI0])]
+mod app {
+    use core::{future::poll_fn, task::Poll};
+    use cortex_m_semihosting::{debug, hprintln};
+
+    #[shared]
+    struct Shared {}
+
+    #[local]
+    struct Local {}
+
+    #[init]
+    fn init(_: init::Context) -> (Shared, Local) {
+        for id in 0..3 {
+            worker::spawn(id).unwrap();
+        }
+        assert!(worker::spawn(3).is_err()); // All instances of `worker` are busy
+
+        (Shared {}, Local {})
+    }
+
+    #[idle]
+    fn idle(_: idle::Context) -> ! {
+        hprintln!("idle");
+        debug::exit(debug::EXIT_SUCCESS); // Exit QEMU simulator
+
+        loop {
+            cortex_m::asm::nop();
+        }
+    }
+
+    // Up to 3 instances of `worker` can run at the same time
+    #[task(capacity = 3, priority = 1)]
+    async fn worker(_: worker::Context, id: u32) {
+        hprintln!("worker {} started", id);
+        yield_now().await;
+        hprintln!("worker {} done", id);
+    }
+
+    async fn yield_now() {
+        let mut yielded = false;
+        poll_fn(|cx| {
+            if yielded {
+                Poll::Ready(())
+            } else {
+                yielded = true;
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
+        })
+        .await
+    }
+}
@@ -0,0 +1,57 @@
+//! examples/spawn_queue.rs
+
+#![no_main]
+#![no_std]
+#![deny(warnings)]
+#![deny(unsafe_code)]
+#![deny(missing_docs)]
+
+use panic_semihosting as _;
+
+#[rtic::app(device = lm3s6965, dispatchers = [SSI0, QEI0])]
+mod app {
+    use cortex_m_semihosting::{debug, hprintln};
+
+    #[shared]
+    struct Shared {}
+
+    #[local]
+    struct Local {}
+
+    #[init]
+    fn init(_: init::Context) -> (Shared, Local) {
+        worker::spawn(0).unwrap();
+        worker::spawn(1).unwrap(); // `worker` is busy, the request is queued
+        worker::spawn(2).unwrap();
+        assert!(worker::spawn(3).is_err()); // The queue is full
+
+        producer::spawn().unwrap();
+
+        (Shared {}, Local {})
+    }
+
+    #[idle]
+    fn idle(_: idle::Context) -> ! {
+        hprintln!("idle");
+        debug::exit(debug::EXIT_SUCCESS); // Exit QEMU simulator
+
+        loop {
+            cortex_m::asm::nop();
+        }
+    }
+
+    #[task(priority = 2)]
+    async fn producer(_: producer::Context) {
+        for id in 3..5 {
+            // Waits until there is space in the queue
+            worker::spawn_wait(id).await;
+            hprintln!("spawned {}", id);
+        }
+    }
+
+    // Up to 2 spawn requests are queued while `worker` is running
+    #[task(queue = 2, priority = 1)]
+    async fn worker(_: worker::Context, id: u32) {
+        hprintln!("worker {}", id);
+    }
+}
@@ -0,0 +1,69 @@
+//! examples/task_error.rs
+
+#![no_main]
+#![no_std]
+#![deny(warnings)]
+#![deny(unsafe_code)]
+#![deny(missing_docs)]
+
+use panic_semihosting as _;
+
+/// Errors of the sensor task.
+#[derive(Debug)]
+pub enum SensorError {
+    /// The sensor did not respond.
+    Timeout,
+}
+
+#[rtic::app(device = lm3s6965, dispatchers = [SSI0], on_task_error = report)]
+mod app {
+    use super::SensorError;
+    use cortex_m_semihosting::{debug, hprintln};
+
+    #[shared]
+    struct Shared {}
+
+    #[local]
+    struct Local {}
+
+    #[init]
+    fn init(_: init::Context) -> (Shared, Local) {
+        supervisor::spawn().unwrap();
+
+        (Shared {}, Local {})
+    }
+
+    // Called every time a task returns `Err`
+    fn report(task: &'static str, error: &dyn core::fmt::Debug) {
+        hprintln!("{} failed: {:?}", task, error);
+    }
+
+    #[task(priority = 1)]
+    async fn supervisor(_: supervisor::Context) {
+        let result = sensor::spawn().unwrap().join().await.unwrap();
+        hprintln!("sensor returned {:?}", result);
+
+        let value = measure::spawn().unwrap().join().await.unwrap();
+        hprintln!("measured {}", value);
+
+        debug::exit(debug::EXIT_SUCCESS); // Exit QEMU simulator
+    }
+
+    // Restarted up to 2 times after returning `Err`
+    #[task(priority = 1, restart = 2, local = [attempts: u32 = 0])]
+    async fn sensor(cx: sensor::Context) -> Result<(), SensorError> {
+        *cx.local.attempts += 1;
+        hprintln!("attempt {}", cx.local.attempts);
+
+        if *cx.local.attempts < 3 {
+            Err(SensorError::Timeout)
+        } else {
+            Ok(())
+        }
+    }
+
+    #[task(priority = 1)]
+    async fn measure(_: measure::Context) -> u32 {
+        42
+    }
+}
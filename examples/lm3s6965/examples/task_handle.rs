@@ -0,0 +1,70 @@
+//! examples/task_handle.rs
+
+#![no_main]
+#![no_std]
+#![deny(warnings)]
+#![deny(unsafe_code)]
+#![deny(missing_docs)]
+
+use panic_semihosting as _;
+
+#[rtic::app(device = lm3s6965, dispatchers = [SSI0])]
+mod app {
+    use core::{future::poll_fn, task::Poll};
+    use cortex_m_semihosting::{debug, hprintln};
+
+    #[shared]
+    struct Shared {}
+
+    #[local]
+    struct Local {}
+
+    #[init]
+    fn init(_: init::Context) -> (Shared, Local) {
+        supervisor::spawn().unwrap();
+
+        (Shared {}, Local {})
+    }
+
+    #[task(priority = 1)]
+    async fn supervisor(_: supervisor::Context) {
+        // Wait for the worker to finish
+        let handle = worker::spawn(2).unwrap();
+        hprintln!("worker running: {}", handle.is_running());
+        handle.join().await.unwrap();
+        hprintln!("worker joined");
+
+        // Restart the worker, and stop it again
+        let handle = worker::spawn(u32::MAX).unwrap();
+        yield_now().await;
+        handle.cancel();
+        if handle.join().await.is_err() {
+            hprintln!("worker cancelled");
+        }
+
+        debug::exit(debug::EXIT_SUCCESS); // Exit QEMU simulator
+    }
+
+    #[task(priority = 1)]
+    async fn worker(_: worker::Context, steps: u32) {
+        hprintln!("worker started");
+        for _ in 0..steps {
+            yield_now().await;
+        }
+        hprintln!("worker done");
+    }
+
+    async fn yield_now() {
+        let mut yielded = false;
+        poll_fn(|cx| {
+            if yielded {
+                Poll::Ready(())
+            } else {
+                yielded = true;
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
+        })
+        .await
+    }
+}
@@ -0,0 +1,52 @@
+//! examples/task_modules/main.rs
+
+#![no_main]
+#![no_std]
+#![deny(warnings)]
+#![deny(unsafe_code)]
+#![deny(missing_docs)]
+
+use panic_semihosting as _;
+
+mod sensors;
+
+// The tasks and resources of `sensors::tasks` are included into the app
+#[rtic::app(
+    device = lm3s6965,
+    dispatchers = [SSI0, QEI0],
+    task_modules = [crate::sensors::tasks],
+)]
+mod app {
+    use cortex_m_semihosting::{debug, hprintln};
+
+    #[shared]
+    struct Shared {
+        samples: u32,
+    }
+
+    #[local]
+    struct Local {}
+
+    #[init]
+    fn init(_: init::Context) -> (Shared, Local) {
+        sample::spawn(3).ok();
+
+        // The resources declared in the task module are initialized here as well
+        (
+            Shared {
+                samples: 0,
+                reading: 0,
+            },
+            Local { scale: 10 },
+        )
+    }
+
+    #[task(priority = 1, shared = [samples, reading])]
+    async fn report(mut cx: report::Context) {
+        let samples = cx.shared.samples.lock(|samples| *samples);
+        let reading = cx.shared.reading.lock(|reading| *reading);
+        hprintln!("report: {} samples, last reading {}", samples, reading);
+
+        debug::exit(debug::EXIT_SUCCESS); // Exit QEMU simulator
+    }
+}
@@ -0,0 +1,32 @@
+//! examples/task_modules/sensors.rs
+
+/// Tasks sampling the sensor
+#[rtic::task_module]
+pub mod tasks {
+    use cortex_m_semihosting::hprintln;
+
+    // Added to the `#[shared]` struct of the app
+    #[shared]
+    struct Shared {
+        reading: u32,
+    }
+
+    // Added to the `#[local]` struct of the app
+    #[local]
+    struct Local {
+        scale: u32,
+    }
+
+    #[task(priority = 2, shared = [samples, reading], local = [scale])]
+    async fn sample(mut cx: sample::Context, n: u32) {
+        for i in 1..=n {
+            let value = i * *cx.local.scale;
+            hprintln!("sample: {}", value);
+
+            cx.shared.reading.lock(|reading| *reading = value);
+            cx.shared.samples.lock(|samples| *samples += 1);
+        }
+
+        report::spawn().ok();
+    }
+}
@@ -8,5 +8,7 @@
 extern crate std;
 
 pub mod dropper;
+#[cfg(feature = "lock-free")]
+pub mod lock_free_queue;
 pub mod wait_queue;
 pub mod waker_registration;
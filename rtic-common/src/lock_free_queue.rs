@@ -0,0 +1,329 @@
+//! A bounded, lock-free queue.
+
+use core::{cell::UnsafeCell, mem::MaybeUninit};
+use portable_atomic::{AtomicUsize, Ordering};
+
+/// A bounded, lock-free FIFO queue, based on Dmitry Vyukov's bounded MPMC queue.
+///
+/// Producers and consumers claim a slot with a compare-and-swap on the enqueue or dequeue
+/// position, and each slot carries a stamp telling whether it holds a value, so no critical
+/// section is needed on targets with compare-and-swap instructions (e.g. `LDREX`/`STREX`). On
+/// other targets `portable-atomic` implements the atomics with critical sections.
+///
+/// A value is only visible to the consumers once its producer has finished writing it. If a
+/// producer is preempted between claiming and writing its slot, the values pushed after it are
+/// not visible either until it resumes.
+pub struct LockFreeQueue<T, const N: usize> {
+    slots: [Slot<T>; N],
+    enqueue: AtomicUsize,
+    dequeue: AtomicUsize,
+}
+
+struct Slot<T> {
+    /// Equal to the position of the slot when it is free, one more when it holds a value
+    stamp: AtomicUsize,
+    value: UnsafeCell<MaybeUninit<T>>,
+}
+
+impl<T> Slot<T> {
+    const fn new() -> Self {
+        Self {
+            stamp: AtomicUsize::new(0),
+            value: UnsafeCell::new(MaybeUninit::uninit()),
+        }
+    }
+}
+
+unsafe impl<T: Send, const N: usize> Sync for LockFreeQueue<T, N> {}
+
+impl<T, const N: usize> Default for LockFreeQueue<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> LockFreeQueue<T, N> {
+    /// Positions are a slot index in the low bits and a lap count in the high bits, so a slot
+    /// freed for the next lap can not be mistaken for a full one, even for `N == 1`.
+    const LAP: usize = (N + 1).next_power_of_two();
+
+    /// Create a new, empty queue.
+    pub const fn new() -> Self {
+        assert!(N > 0, "a queue needs space for at least one value");
+
+        let mut slots = [const { Slot::new() }; N];
+        let mut i = 0;
+
+        while i < N {
+            slots[i].stamp = AtomicUsize::new(i);
+            i += 1;
+        }
+
+        Self {
+            slots,
+            enqueue: AtomicUsize::new(0),
+            dequeue: AtomicUsize::new(0),
+        }
+    }
+
+    /// The position following `pos`.
+    const fn next(pos: usize) -> usize {
+        if (pos & (Self::LAP - 1)) + 1 < N {
+            pos + 1
+        } else {
+            (pos & !(Self::LAP - 1)).wrapping_add(Self::LAP)
+        }
+    }
+
+    fn slot(&self, pos: usize) -> &Slot<T> {
+        &self.slots[pos & (Self::LAP - 1)]
+    }
+
+    /// Push a value to the back of the queue. Returns the value if the queue is full.
+    pub fn push(&self, value: T) -> Result<(), T> {
+        let mut pos = self.enqueue.load(Ordering::Relaxed);
+
+        loop {
+            let slot = self.slot(pos);
+            let stamp = slot.stamp.load(Ordering::Acquire);
+
+            if stamp == pos {
+                match self.enqueue.compare_exchange_weak(
+                    pos,
+                    Self::next(pos),
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => {
+                        // SAFETY: The slot was claimed by the compare-and-swap, no other producer
+                        // writes it and no consumer reads it before the stamp is published.
+                        unsafe { (*slot.value.get()).write(value) };
+                        slot.stamp.store(pos.wrapping_add(1), Ordering::Release);
+                        return Ok(());
+                    }
+                    Err(current) => pos = current,
+                }
+            } else if stamp.wrapping_add(Self::LAP) == pos.wrapping_add(1) {
+                // The slot still holds the value pushed one lap ago
+                return Err(value);
+            } else {
+                // Another producer claimed the slot
+                pos = self.enqueue.load(Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Pop the value at the front of the queue.
+    pub fn pop(&self) -> Option<T> {
+        let mut pos = self.dequeue.load(Ordering::Relaxed);
+
+        loop {
+            let slot = self.slot(pos);
+            let stamp = slot.stamp.load(Ordering::Acquire);
+
+            if stamp == pos.wrapping_add(1) {
+                match self.dequeue.compare_exchange_weak(
+                    pos,
+                    Self::next(pos),
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => {
+                        // SAFETY: The slot was claimed by the compare-and-swap and holds a
+                        // published value, no other consumer reads it.
+                        let value = unsafe { (*slot.value.get()).assume_init_read() };
+
+                        // Free the slot for the producers of the next lap
+                        slot.stamp
+                            .store(pos.wrapping_add(Self::LAP), Ordering::Release);
+                        return Some(value);
+                    }
+                    Err(current) => pos = current,
+                }
+            } else if stamp == pos {
+                // The slot is free, or its producer has not written it yet
+                return None;
+            } else {
+                // Another consumer claimed the slot
+                pos = self.dequeue.load(Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Hand the values to `f` in order, stopping at the first one `f` returns, which is kept at
+    /// the front of the queue. `f` is called at most `N` times, values pushed meanwhile may be
+    /// left for the next call.
+    ///
+    /// # Safety
+    ///
+    /// There must be a single consumer: `drain` must not run concurrently with itself or `pop`.
+    pub unsafe fn drain(&self, mut f: impl FnMut(T) -> Result<(), T>) {
+        for _ in 0..N {
+            let pos = self.dequeue.load(Ordering::Relaxed);
+            let slot = self.slot(pos);
+
+            if slot.stamp.load(Ordering::Acquire) != pos.wrapping_add(1) {
+                break;
+            }
+
+            // SAFETY: The slot holds a published value and there is no other consumer.
+            let value = (*slot.value.get()).assume_init_read();
+
+            if let Err(value) = f(value) {
+                // The slot is still owned by the consumer, put the value back
+                (*slot.value.get()).write(value);
+                break;
+            }
+
+            self.dequeue.store(Self::next(pos), Ordering::Relaxed);
+            slot.stamp
+                .store(pos.wrapping_add(Self::LAP), Ordering::Release);
+        }
+    }
+
+    /// Check if the queue is empty.
+    pub fn is_empty(&self) -> bool {
+        let pos = self.dequeue.load(Ordering::Relaxed);
+        self.slot(pos).stamp.load(Ordering::Acquire) != pos.wrapping_add(1)
+    }
+
+    /// Check if the queue is full.
+    pub fn is_full(&self) -> bool {
+        let pos = self.enqueue.load(Ordering::Relaxed);
+        self.slot(pos)
+            .stamp
+            .load(Ordering::Acquire)
+            .wrapping_add(Self::LAP)
+            == pos.wrapping_add(1)
+    }
+}
+
+impl<T, const N: usize> Drop for LockFreeQueue<T, N> {
+    fn drop(&mut self) {
+        while self.pop().is_some() {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{thread, vec::Vec};
+
+    #[test]
+    fn push_until_full_and_pop_in_order() {
+        let queue: LockFreeQueue<u32, 3> = LockFreeQueue::new();
+
+        assert!(queue.is_empty());
+        assert_eq!(queue.push(1), Ok(()));
+        assert_eq!(queue.push(2), Ok(()));
+        assert_eq!(queue.push(3), Ok(()));
+        assert!(queue.is_full());
+        assert_eq!(queue.push(4), Err(4));
+
+        assert_eq!(queue.pop(), Some(1));
+        assert!(!queue.is_full());
+        assert_eq!(queue.push(4), Ok(()));
+        assert_eq!(queue.pop(), Some(2));
+        assert_eq!(queue.pop(), Some(3));
+        assert_eq!(queue.pop(), Some(4));
+        assert_eq!(queue.pop(), None);
+
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn single_slot() {
+        let queue: LockFreeQueue<u32, 1> = LockFreeQueue::new();
+
+        for i in 0..3 {
+            assert_eq!(queue.push(i), Ok(()));
+            assert!(queue.is_full());
+            assert_eq!(queue.push(i + 1), Err(i + 1));
+            assert_eq!(queue.pop(), Some(i));
+            assert!(queue.is_empty());
+        }
+    }
+
+    #[test]
+    fn drain_keeps_the_refused_value() {
+        let queue: LockFreeQueue<u32, 4> = LockFreeQueue::new();
+
+        for i in 0..4 {
+            queue.push(i).unwrap();
+        }
+
+        let mut taken = Vec::new();
+        unsafe {
+            queue.drain(|v| {
+                if v < 2 {
+                    taken.push(v);
+                    Ok(())
+                } else {
+                    Err(v)
+                }
+            })
+        };
+
+        assert_eq!(taken, [0, 1]);
+        assert!(!queue.is_empty());
+        assert_eq!(queue.pop(), Some(2));
+        assert_eq!(queue.pop(), Some(3));
+    }
+
+    #[test]
+    fn concurrent_producers_and_consumers() {
+        const THREADS: usize = 4;
+        const PER_THREAD: usize = 10_000;
+
+        let queue: LockFreeQueue<(usize, usize), 8> = LockFreeQueue::new();
+        let received: Vec<Vec<(usize, usize)>> = thread::scope(|s| {
+            for p in 0..THREADS {
+                let queue = &queue;
+                s.spawn(move || {
+                    for i in 0..PER_THREAD {
+                        let mut value = (p, i);
+                        while let Err(v) = queue.push(value) {
+                            value = v;
+                            thread::yield_now();
+                        }
+                    }
+                });
+            }
+
+            let consumers: Vec<_> = (0..THREADS)
+                .map(|_| {
+                    let queue = &queue;
+                    s.spawn(move || {
+                        let mut received = Vec::new();
+                        while received.len() < PER_THREAD {
+                            match queue.pop() {
+                                Some(value) => received.push(value),
+                                None => thread::yield_now(),
+                            }
+                        }
+                        received
+                    })
+                })
+                .collect();
+
+            consumers.into_iter().map(|c| c.join().unwrap()).collect()
+        });
+
+        // Each consumer sees the values of a producer in order, and none are lost
+        let mut all = Vec::new();
+        for values in &received {
+            for p in 0..THREADS {
+                let from_p: Vec<_> = values.iter().filter(|(q, _)| *q == p).collect();
+                assert!(from_p.windows(2).all(|w| w[0].1 < w[1].1));
+            }
+            all.extend_from_slice(values);
+        }
+
+        all.sort();
+        let expected: Vec<_> = (0..THREADS)
+            .flat_map(|p| (0..PER_THREAD).map(move |i| (p, i)))
+            .collect();
+        assert_eq!(all, expected);
+        assert!(queue.is_empty());
+    }
+}
@@ -2,6 +2,8 @@
 
 use core::cell::UnsafeCell;
 use core::task::Waker;
+#[cfg(feature = "lock-free")]
+use portable_atomic::{AtomicU8, Ordering};
 
 /// A critical section based waker handler.
 pub struct CriticalSectionWakerRegistration {
@@ -70,3 +72,165 @@ impl Default for CriticalSectionWakerRegistration {
         Self::new()
     }
 }
+
+/// No waker is being registered or woken.
+#[cfg(feature = "lock-free")]
+const WAITING: u8 = 0;
+/// A waker is being registered.
+#[cfg(feature = "lock-free")]
+const REGISTERING: u8 = 1 << 0;
+/// The waker is being taken to wake it.
+#[cfg(feature = "lock-free")]
+const WAKING: u8 = 1 << 1;
+
+/// A lock-free waker handler, which never takes a critical section on targets with
+/// compare-and-swap instructions.
+///
+/// `register` and `wake` can preempt each other: a `wake` during `register` is delivered to the
+/// new waker by `register` once it is done, and a `register` during `wake` wakes the new waker
+/// right away.
+#[cfg(feature = "lock-free")]
+pub struct AtomicWakerRegistration {
+    state: AtomicU8,
+    waker: UnsafeCell<Option<Waker>>,
+}
+
+#[cfg(feature = "lock-free")]
+unsafe impl Send for AtomicWakerRegistration {}
+#[cfg(feature = "lock-free")]
+unsafe impl Sync for AtomicWakerRegistration {}
+
+#[cfg(feature = "lock-free")]
+impl AtomicWakerRegistration {
+    /// Create a new waker registration.
+    pub const fn new() -> Self {
+        Self {
+            state: AtomicU8::new(WAITING),
+            waker: UnsafeCell::new(None),
+        }
+    }
+
+    /// Register a waker.
+    /// This will overwrite the previous waker if there was one, which is woken if it does not
+    /// wake the same task.
+    pub fn register(&self, new_waker: &Waker) {
+        match self
+            .state
+            .compare_exchange(WAITING, REGISTERING, Ordering::Acquire, Ordering::Acquire)
+            .unwrap_or_else(|state| state)
+        {
+            WAITING => {
+                // SAFETY: The `REGISTERING` bit gives exclusive access to the waker.
+                let self_waker = unsafe { &mut *self.waker.get() };
+
+                let old_waker = match self_waker {
+                    Some(w) if w.will_wake(new_waker) => None,
+                    _ => self_waker.replace(new_waker.clone()),
+                };
+
+                if let Err(state) = self.state.compare_exchange(
+                    REGISTERING,
+                    WAITING,
+                    Ordering::AcqRel,
+                    Ordering::Acquire,
+                ) {
+                    // `wake` ran meanwhile and left the waker to us
+                    debug_assert_eq!(state, REGISTERING | WAKING);
+
+                    // SAFETY: The `REGISTERING` bit is still set.
+                    let waker = unsafe { (*self.waker.get()).take() };
+                    self.state.store(WAITING, Ordering::Release);
+
+                    if let Some(waker) = waker {
+                        waker.wake();
+                    }
+                }
+
+                // See `CriticalSectionWakerRegistration::register`
+                if let Some(old_waker) = old_waker {
+                    old_waker.wake();
+                }
+            }
+            WAKING => {
+                // `wake` is taking the previous waker, the new one may have missed the event
+                new_waker.wake_by_ref();
+            }
+            _ => {
+                // Another `register` is running, registering from two tasks concurrently is
+                // not supported
+            }
+        }
+    }
+
+    /// Wake the waker.
+    pub fn wake(&self) {
+        if self.state.fetch_or(WAKING, Ordering::AcqRel) == WAITING {
+            // SAFETY: The `WAKING` bit gives exclusive access to the waker.
+            let waker = unsafe { (*self.waker.get()).take() };
+            self.state.fetch_and(!WAKING, Ordering::Release);
+
+            if let Some(waker) = waker {
+                waker.wake();
+            }
+        }
+    }
+}
+
+#[cfg(feature = "lock-free")]
+impl Default for AtomicWakerRegistration {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(all(test, feature = "lock-free"))]
+mod tests {
+    use super::*;
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        task::Wake,
+    };
+
+    struct Counter(AtomicUsize);
+
+    impl Wake for Counter {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    fn counter() -> (Arc<Counter>, Waker) {
+        let counter = Arc::new(Counter(AtomicUsize::new(0)));
+        (counter.clone(), Waker::from(counter))
+    }
+
+    #[test]
+    fn atomic_registration_wakes_once() {
+        let registration = AtomicWakerRegistration::new();
+        let (count, waker) = counter();
+
+        registration.wake();
+        registration.register(&waker);
+        registration.wake();
+        registration.wake();
+
+        assert_eq!(count.0.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn atomic_registration_wakes_replaced_waker() {
+        let registration = AtomicWakerRegistration::new();
+        let (first, first_waker) = counter();
+        let (second, second_waker) = counter();
+
+        registration.register(&first_waker);
+        registration.register(&second_waker);
+        registration.wake();
+
+        assert_eq!(first.0.load(Ordering::Relaxed), 1);
+        assert_eq!(second.0.load(Ordering::Relaxed), 1);
+    }
+}
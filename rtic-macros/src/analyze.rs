@@ -1,11 +1,12 @@
 use core::ops;
 use std::collections::{BTreeMap, BTreeSet};
 
+use crate::preprocess;
 use crate::syntax::{
     analyze::{self, Priority},
     ast::{App, Dispatcher},
 };
-use syn::Ident;
+use syn::{parse, Ident};
 
 /// Extend the upstream `Analysis` struct with our field
 pub struct Analysis {
@@ -23,7 +24,7 @@ impl ops::Deref for Analysis {
 }
 
 // Assign an interrupt to each priority level
-pub fn app(analysis: analyze::Analysis, app: &App) -> Analysis {
+pub fn app(analysis: analyze::Analysis, app: &App) -> parse::Result<Analysis> {
     let mut available_dispatchers = app.args.dispatchers.clone();
 
     // the set of priorities (each priority only once)
@@ -39,10 +40,9 @@ pub fn app(analysis: analyze::Analysis, app: &App) -> Analysis {
         .iter()
         // 0 prio tasks are run in main
         .filter(|prio| **prio > 0);
-    assert!(
-        available_dispatchers.len() >= nonzero_priorities.clone().count(),
-        "The number of dispatchers must be equal to or greater than the number of distinct task priorities."
-    );
+
+    check_dispatchers(app, nonzero_priorities.clone().copied().collect())?;
+
     let interrupts: BTreeMap<Priority, _> = nonzero_priorities
         .copied()
         .rev()
@@ -51,22 +51,68 @@ pub fn app(analysis: analyze::Analysis, app: &App) -> Analysis {
                 p,
                 available_dispatchers
                     .pop()
-                    // EXPECT: covered by above assertion
+                    // EXPECT: covered by `check_dispatchers`
                     .expect("UNREACHABLE"),
             )
         })
         .collect();
 
+    // The inter-processor interrupt only spawns tasks, it can run at the priority of its tasks
+    let ipi_task = preprocess::ipi_task_ident();
     let max_async_prio = app
         .hardware_tasks
         .iter()
+        .filter(|(name, _)| **name != ipi_task)
         .map(|(_, task)| task.args.priority)
         .min()
         .map(|v| v - 1); // One less than the smallest HW task
 
-    Analysis {
+    Ok(Analysis {
         parent: analysis,
         interrupts,
         max_async_prio,
+    })
+}
+
+// Check that there is a dispatcher for each priority level of the software tasks
+fn check_dispatchers(app: &App, priorities: Vec<Priority>) -> parse::Result<()> {
+    let need = priorities.len();
+    let given = app.args.dispatchers.len();
+
+    if need <= given {
+        return Ok(());
     }
+
+    // Dispatchers are assigned from the highest priority down, so the lowest levels miss out
+    let mut errors = priorities[..need - given].iter().map(|priority| {
+        let (name, task) = app
+            .software_tasks
+            .iter()
+            .find(|(_, task)| task.args.priority == *priority)
+            .expect("UNREACHABLE");
+
+        // The `priority` argument is what has to change, a task without one has priority 0
+        parse::Error::new(
+            task.args.priority_span.unwrap_or_else(|| name.span()),
+            format!(
+                "not enough interrupts to dispatch all software tasks (need: {need}; given: {given}); \
+                    add an unused interrupt to `dispatchers = [..]` for priority {priority}"
+            ),
+        )
+    });
+
+    // EXPECT: `need > given` so at least one level is missing a dispatcher
+    let mut error = errors.next().expect("UNREACHABLE");
+    errors.for_each(|e| error.combine(e));
+
+    if let Some((last, _)) = app.args.dispatchers.iter().last() {
+        error.combine(parse::Error::new(
+            last.span(),
+            format!(
+                "only {given} dispatcher(s) listed here, but the software tasks use {need} priority levels"
+            ),
+        ));
+    }
+
+    Err(error)
 }
@@ -2,16 +2,20 @@ use proc_macro2::TokenStream as TokenStream2;
 use quote::quote;
 
 use crate::analyze::Analysis;
+use crate::codegen::bindings::{Backend, Selected};
 use crate::syntax::ast::App;
 
 pub mod bindings;
+pub mod util;
 
 mod assertions;
 mod async_dispatchers;
+mod executors;
 mod extra_mods;
 mod hardware_tasks;
 mod idle;
 mod init;
+mod lints;
 mod local_resources;
 mod local_resources_struct;
 mod module;
@@ -20,7 +24,8 @@ mod pre_init;
 mod shared_resources;
 mod shared_resources_struct;
 mod software_tasks;
-mod util;
+mod stack;
+mod timing;
 
 mod main;
 
@@ -39,6 +44,10 @@ pub fn app(app: &App, analysis: &Analysis) -> TokenStream2 {
     let hardware_tasks_codegen = hardware_tasks::codegen(app, analysis);
     let software_tasks_codegen = software_tasks::codegen(app, analysis);
     let async_dispatchers_codegen = async_dispatchers::codegen(app, analysis);
+    let lints = lints::codegen(app, analysis);
+    let executors = executors::codegen(app);
+    let timing = timing::codegen(app);
+    let stack = stack::codegen(app, analysis);
 
     let user_imports = &app.user_imports;
     let user_code = &app.user_code;
@@ -46,7 +55,24 @@ pub fn app(app: &App, analysis: &Analysis) -> TokenStream2 {
     let device = &app.args.device;
 
     let rt_err = util::rt_err_ident();
-    let async_limit = bindings::async_prio_limit(app, analysis);
+    // The limit is a global symbol, it is provided by the application of the first core
+    let async_limit = if app.args.core_id == 0 {
+        Selected::async_prio_limit(app, analysis)
+    } else {
+        vec![]
+    };
+
+    let priority_map = if app.args.optimize_priorities {
+        let (declared, used): (Vec<u8>, Vec<u8>) = app.priority_map.iter().unzip();
+
+        Some(quote!(
+            /// The declared task priorities and the priorities they were compressed to by
+            /// `optimize_priorities`, as `(declared, used)` pairs
+            pub const PRIORITY_MAP: &[(u8, u8)] = &[#((#declared, #used)),*];
+        ))
+    } else {
+        None
+    };
 
     quote!(
         /// The RTIC application module
@@ -56,6 +82,8 @@ pub fn app(app: &App, analysis: &Analysis) -> TokenStream2 {
 
             #(#async_limit)*
 
+            #priority_map
+
             #(#user_imports)*
 
             #(#user_code)*
@@ -75,6 +103,14 @@ pub fn app(app: &App, analysis: &Analysis) -> TokenStream2 {
 
             #async_dispatchers_codegen
 
+            #executors
+
+            #timing
+
+            #stack
+
+            #(#lints)*
+
             #main
         }
     )
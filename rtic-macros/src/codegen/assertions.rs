@@ -1,7 +1,8 @@
 use proc_macro2::TokenStream as TokenStream2;
-use quote::quote;
+use quote::{quote, quote_spanned};
+use syn::spanned::Spanned;
 
-use super::bindings::extra_assertions;
+use super::bindings::{Backend, Selected};
 use crate::analyze::Analysis;
 use crate::syntax::ast::App;
 
@@ -17,7 +18,17 @@ pub fn codegen(app: &App, analysis: &Analysis) -> Vec<TokenStream2> {
         stmts.push(quote!(rtic::export::assert_sync::<#ty>();));
     }
 
-    stmts.append(&mut extra_assertions(app, analysis));
+    // Resources accessed from other cores must be safe to use concurrently
+    for res in app
+        .shared_resources
+        .values()
+        .filter(|res| res.properties.cross_core)
+    {
+        let ty = &res.ty;
+        stmts.push(quote_spanned!(ty.span()=> rtic::export::assert_cross_core::<#ty>();));
+    }
+
+    stmts.append(&mut Selected::extra_assertions(app, analysis));
 
     stmts
 }
@@ -2,8 +2,8 @@ use crate::syntax::ast::App;
 use crate::{
     analyze::Analysis,
     codegen::{
-        bindings::{async_entry, handler_config, interrupt_entry, interrupt_exit, interrupt_mod},
-        util,
+        bindings::{Backend, Selected},
+        stack, timing, util,
     },
 };
 use proc_macro2::TokenStream as TokenStream2;
@@ -14,16 +14,47 @@ pub fn codegen(app: &App, analysis: &Analysis) -> TokenStream2 {
     let mut items = vec![];
 
     let interrupts = &analysis.interrupts;
+    let export = Selected::export_path();
 
-    // Generate executor definition and priority in global scope
-    for (name, _) in app.software_tasks.iter() {
+    // Generate executor definition and priority in global scope, one executor per instance
+    for (name, task) in app.software_tasks.iter() {
         let exec_name = util::internal_task_ident(name, "EXEC");
+        let capacity = task.args.capacity as usize;
 
         items.push(quote!(
             #[allow(non_upper_case_globals)]
-            static #exec_name: rtic::export::executor::AsyncTaskExecutorPtr =
-                rtic::export::executor::AsyncTaskExecutorPtr::new();
+            static #exec_name: [rtic::export::executor::AsyncTaskExecutorPtr; #capacity] = {
+                const PTR: rtic::export::executor::AsyncTaskExecutorPtr =
+                    rtic::export::executor::AsyncTaskExecutorPtr::new();
+                [PTR; #capacity]
+            };
         ));
+
+        // Requests that are waiting for a free instance
+        if let Some(queue) = task.args.queue {
+            let queue_name = util::internal_task_ident(name, "SPAWN_QUEUE");
+            let queue = queue as usize;
+            let (_, _, _, input_ty) = util::regroup_inputs(&task.inputs);
+
+            items.push(quote!(
+                #[allow(non_upper_case_globals)]
+                static #queue_name: rtic::export::spawn_queue::SpawnQueue<#input_ty, #queue> =
+                    rtic::export::spawn_queue::SpawnQueue::new();
+            ));
+        }
+
+        // Requests from the other cores, delivered by the inter-processor interrupt
+        if let Some(mailbox) = task.args.mailbox {
+            let mailbox_name = util::internal_task_ident(name, "MAILBOX");
+            let mailbox = mailbox as usize;
+            let (_, _, _, input_ty) = util::regroup_inputs(&task.inputs);
+
+            items.push(quote!(
+                #[allow(non_upper_case_globals)]
+                static #mailbox_name: rtic::export::mailbox::Mailbox<#input_ty, #mailbox> =
+                    rtic::export::mailbox::Mailbox::new();
+            ));
+        }
     }
 
     for (&level, channel) in &analysis.channels {
@@ -36,9 +67,7 @@ pub fn codegen(app: &App, analysis: &Analysis) -> TokenStream2 {
         };
 
         let pend_interrupt = if level > 0 {
-            let int_mod = interrupt_mod(app);
-
-            quote!(rtic::export::pend(#int_mod::#dispatcher_name);)
+            Selected::pend_interrupt(app, &dispatcher_name)
         } else {
             // For 0 priority tasks we don't need to pend anything
             quote!()
@@ -48,28 +77,66 @@ pub fn codegen(app: &App, analysis: &Analysis) -> TokenStream2 {
             let exec_name = util::internal_task_ident(name, "EXEC");
             let from_ptr_n_args =
                 util::from_ptr_n_args_ident(app.software_tasks[name].inputs.len());
+            let entry = util::task_entry_ident(app, name);
 
             // TODO: Fix cfg
             // let task = &app.software_tasks[name];
             // let cfgs = &task.cfgs;
 
-            stmts.push(quote!(
-                let exec = rtic::export::executor::AsyncTaskExecutor::#from_ptr_n_args(#name, &#exec_name);
-                exec.poll(|| {
-                    let exec = rtic::export::executor::AsyncTaskExecutor::#from_ptr_n_args(#name, &#exec_name);
-                    exec.set_pending();
-                    #pend_interrupt
-                });
-            ));
+            // Each instance needs its own waker, so the polling is unrolled
+            for i in 0..app.software_tasks[name].args.capacity as usize {
+                let poll = timing::measure_task(
+                    name,
+                    quote!(
+                        exec.poll(|| {
+                            let exec = rtic::export::executor::AsyncTaskExecutor::#from_ptr_n_args(#entry, &#exec_name[#i]);
+                            exec.set_pending();
+                            #pend_interrupt
+                        })
+                    ),
+                );
+
+                stmts.push(quote!(
+                    let exec = rtic::export::executor::AsyncTaskExecutor::#from_ptr_n_args(#entry, &#exec_name[#i]);
+                    #poll;
+                ));
+            }
+
+            // Hand queued requests to the instances that became free
+            if app.software_tasks[name].args.queue.is_some() {
+                let queue_name = util::internal_task_ident(name, "SPAWN_QUEUE");
+                let internal_try_spawn_ident = util::internal_task_ident(name, "try_spawn");
+
+                stmts.push(quote!(
+                    #queue_name.refill(#internal_try_spawn_ident);
+                ));
+            }
+
+            // Requests that found all instances busy are delivered again by the
+            // inter-processor interrupt
+            if app.software_tasks[name].args.mailbox.is_some() {
+                let mailbox_name = util::internal_task_ident(name, "MAILBOX");
+                // EXPECT: checked in `preprocess`
+                let pend_ipi =
+                    Selected::pend_interrupt(app, app.args.ipi.as_ref().expect("UNREACHABLE"));
+
+                stmts.push(quote!(
+                    if !#mailbox_name.is_empty() {
+                        #pend_ipi
+                    }
+                ));
+            }
         }
 
         if level > 0 {
             let doc = format!("Interrupt handler to dispatch async tasks at priority {level}");
             let attribute = &interrupts.get(&level).expect("UNREACHABLE").1.attrs;
-            let entry_stmts = interrupt_entry(app, analysis);
-            let exit_stmts = interrupt_exit(app, analysis);
-            let async_entry_stmts = async_entry(app, analysis, dispatcher_name.clone());
-            let config = handler_config(app, analysis, dispatcher_name.clone());
+            let entry_stmts = Selected::interrupt_entry(app, analysis);
+            let exit_stmts = Selected::interrupt_exit(app, analysis);
+            let async_entry_stmts = Selected::async_entry(app, analysis, dispatcher_name.clone());
+            let config = Selected::handler_config(app, analysis, dispatcher_name.clone());
+            let stack_enter = stack::enter(app);
+            let stack_exit = stack::exit(app, level);
             items.push(quote!(
                 #[allow(non_snake_case)]
                 #[doc = #doc]
@@ -79,14 +146,16 @@ pub fn codegen(app: &App, analysis: &Analysis) -> TokenStream2 {
                 unsafe fn #dispatcher_name() {
                     #(#entry_stmts)*
                     #(#async_entry_stmts)*
+                    #stack_enter
 
                     /// The priority of this interrupt handler
                     const PRIORITY: u8 = #level;
 
-                    rtic::export::run(PRIORITY, || {
+                    #export::run(PRIORITY, || {
                         #(#stmts)*
                     });
 
+                    #stack_exit
                     #(#exit_stmts)*
                 }
             ));
@@ -4,29 +4,208 @@
     feature = "test-template",
     feature = "riscv-esp32c3",
     feature = "riscv-slic",
+    feature = "riscv-clic",
+    feature = "riscv-plic",
+    feature = "external-backend",
 )))]
 compile_error!("No backend selected");
 
-#[cfg(any(feature = "cortex-m-source-masking", feature = "cortex-m-basepri"))]
-pub use cortex::*;
+#[cfg(all(
+    feature = "stack-usage",
+    not(any(feature = "cortex-m-basepri", feature = "cortex-m-source-masking"))
+))]
+compile_error!(
+    "`stack-usage` needs the memory layout of `cortex-m-rt`, only the Cortex-M backends have it"
+);
 
-#[cfg(feature = "test-template")]
-pub use template::*;
+#[cfg(all(feature = "measure-timing", not(feature = "cortex-m-basepri")))]
+compile_error!("`measure-timing` needs the DWT cycle counter of the `cortex-m-basepri` backend");
+
+use crate::{
+    analyze::Analysis as CodegenAnalysis,
+    syntax::{analyze::Analysis as SyntaxAnalysis, ast::App},
+};
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse, Attribute, Ident};
+
+/// Revision of the unstable backend API, bumped whenever the `Backend` trait or the types passed to
+/// it change
+#[cfg_attr(not(feature = "external-backend"), allow(dead_code))]
+pub const BACKEND_API: u32 = 1;
+
+/// The contract between the target independent code generation and a backend
+///
+/// Everything that depends on the target, like the interrupt controller, goes through this trait.
+/// The generated code is checked against the contract by the conformance suite in `tests/ui.rs`,
+/// `template.rs` is the reference implementation to start a new backend from.
+///
+/// Backends outside of this crate are included from `$RTIC_EXTERNAL_BACKEND/codegen.rs`, which
+/// must define an `ExternalBackend` type implementing this trait, and
+/// `$RTIC_EXTERNAL_BACKEND/syntax.rs`, which must define the `BackendArgs` of the backend, when
+/// the `external-backend` feature is enabled.
+///
+/// This trait and the `App` and `Analysis` types it is given are internal to `rtic-macros`, they
+/// are **not** a stable API and change in any release, including patch releases. An external
+/// backend declares the [`BACKEND_API`] revision it was written against and fails to build when it
+/// no longer matches.
+pub trait Backend {
+    /// Path of the runtime module the generated code calls into
+    ///
+    /// The module must provide `interrupt::{disable, enable}`, `pend(interrupt)`,
+    /// `run(priority, f)` and, when `core = true`, `Peripherals`.
+    fn export_path() -> TokenStream2 {
+        quote!(rtic::export)
+    }
+
+    /// Name of the interrupt enumeration of the device crate
+    fn interrupt_ident() -> Ident;
+
+    /// Path of the interrupt enumeration used to pend dispatchers and hardware tasks
+    fn interrupt_mod(app: &App) -> TokenStream2 {
+        let device = &app.args.device;
+        let interrupt = Self::interrupt_ident();
+        quote!(#device::#interrupt)
+    }
+
+    /// Statement pending the dispatcher or interrupt `name` of the interrupt enumeration
+    fn pend_interrupt(app: &App, name: &Ident) -> TokenStream2 {
+        let export = Self::export_path();
+        let int_mod = Self::interrupt_mod(app);
+        quote!(#export::pend(#int_mod::#name);)
+    }
+
+    /// Implements `rtic::Mutex` for the proxy of the shared resource `name`
+    ///
+    /// The proxy is `shared_resources::name` if `resources_prefix` is set, and `name` otherwise.
+    /// `lock` must run its closure on `ptr` at the priority `ceiling`.
+    #[allow(clippy::too_many_arguments)]
+    fn impl_mutex(
+        app: &App,
+        analysis: &CodegenAnalysis,
+        cfgs: &[Attribute],
+        resources_prefix: bool,
+        name: &Ident,
+        ty: &TokenStream2,
+        ceiling: u8,
+        ptr: &TokenStream2,
+    ) -> TokenStream2;
+
+    /// Compile time assertions, as statements in `main`
+    fn extra_assertions(_app: &App, _analysis: &SyntaxAnalysis) -> Vec<TokenStream2> {
+        vec![]
+    }
+
+    /// Changes the app before it is checked and analyzed, e.g. to add dispatchers
+    fn pre_init_preprocessing(_app: &mut App, _analysis: &SyntaxAnalysis) -> parse::Result<()> {
+        Ok(())
+    }
+
+    /// Statements checking the device before `init`, e.g. that the dispatchers exist
+    fn pre_init_checks(_app: &App, _analysis: &SyntaxAnalysis) -> Vec<TokenStream2> {
+        vec![]
+    }
+
+    /// Statements setting the priorities of the used interrupts and unmasking them before `init`
+    fn pre_init_enable_interrupts(app: &App, analysis: &CodegenAnalysis) -> Vec<TokenStream2>;
+
+    /// Target specific checks of the app
+    fn architecture_specific_analysis(_app: &App, _analysis: &SyntaxAnalysis) -> parse::Result<()> {
+        Ok(())
+    }
+
+    /// Statements at the start of every interrupt handler
+    fn interrupt_entry(_app: &App, _analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
+        vec![]
+    }
+
+    /// Statements at the end of every interrupt handler
+    fn interrupt_exit(_app: &App, _analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
+        vec![]
+    }
+
+    /// Statements checking for a stack overflow after the executors are allocated
+    fn check_stack_overflow_before_init(
+        _app: &App,
+        _analysis: &CodegenAnalysis,
+    ) -> Vec<TokenStream2> {
+        vec![]
+    }
+
+    /// Statements at the start of the handler of the dispatcher `dispatcher_name`
+    fn async_entry(
+        _app: &App,
+        _analysis: &CodegenAnalysis,
+        _dispatcher_name: Ident,
+    ) -> Vec<TokenStream2> {
+        vec![]
+    }
+
+    /// Items defining the `RTIC_ASYNC_MAX_LOGICAL_PRIO` limit for async HAL drivers
+    fn async_prio_limit(_app: &App, _analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
+        vec![]
+    }
+
+    /// Attributes of the interrupt handler `dispatcher_name`
+    fn handler_config(
+        _app: &App,
+        _analysis: &CodegenAnalysis,
+        _dispatcher_name: Ident,
+    ) -> Vec<TokenStream2> {
+        vec![]
+    }
+
+    /// Items added to `main`, e.g. support code generated by the runtime crate
+    fn extra_modules(_app: &App, _analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
+        vec![]
+    }
+}
+
+#[cfg(any(feature = "cortex-m-source-masking", feature = "cortex-m-basepri"))]
+pub type Selected = cortex::Cortex;
 
 #[cfg(any(feature = "cortex-m-source-masking", feature = "cortex-m-basepri"))]
 mod cortex;
 
+#[cfg(feature = "test-template")]
+pub type Selected = template::Template;
+
 #[cfg(feature = "test-template")]
 mod template;
 
 #[cfg(feature = "riscv-esp32c3")]
-pub use esp32c3::*;
+pub type Selected = esp32c3::Esp32c3;
 
 #[cfg(feature = "riscv-esp32c3")]
 mod esp32c3;
 
 #[cfg(feature = "riscv-slic")]
-pub use riscv_slic::*;
+pub type Selected = riscv_slic::RiscvSlic;
 
 #[cfg(feature = "riscv-slic")]
 mod riscv_slic;
+
+#[cfg(feature = "riscv-clic")]
+pub type Selected = riscv_clic::RiscvClic;
+
+#[cfg(feature = "riscv-clic")]
+mod riscv_clic;
+
+#[cfg(feature = "riscv-plic")]
+pub type Selected = riscv_plic::RiscvPlic;
+
+#[cfg(feature = "riscv-plic")]
+mod riscv_plic;
+
+#[cfg(feature = "external-backend")]
+pub type Selected = external::ExternalBackend;
+
+#[cfg(feature = "external-backend")]
+mod external {
+    include!(concat!(env!("RTIC_EXTERNAL_BACKEND"), "/codegen.rs"));
+
+    const _: () = assert!(
+        BACKEND_API == super::BACKEND_API,
+        "the external backend was written for another revision of the unstable backend API",
+    );
+}
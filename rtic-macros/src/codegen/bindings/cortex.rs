@@ -4,14 +4,19 @@ use crate::{
     syntax::{analyze::Analysis as SyntaxAnalysis, ast::App},
 };
 use proc_macro2::{Span, TokenStream as TokenStream2};
-use quote::quote;
-use std::collections::HashSet;
+use quote::{quote, quote_spanned};
 use syn::{parse, Attribute, Ident};
 
+use super::Backend;
+
 #[cfg(feature = "cortex-m-basepri")]
-pub use basepri::*;
+use basepri as mask;
 #[cfg(feature = "cortex-m-source-masking")]
-pub use source_masking::*;
+use source_masking as mask;
+
+/// Cortex-M backend, with `BASEPRI` based locks for ARMv7-M and ARMv8-M mainline, and interrupt
+/// source masking for ARMv6-M and ARMv8-M baseline
+pub struct Cortex;
 
 /// Whether `name` is an exception with configurable priority
 fn is_exception(name: &Ident) -> bool {
@@ -30,44 +35,9 @@ fn is_exception(name: &Ident) -> bool {
     )
 }
 
-pub fn interrupt_ident() -> Ident {
-    let span = Span::call_site();
-    Ident::new("interrupt", span)
-}
-
-pub fn interrupt_mod(app: &App) -> TokenStream2 {
-    let device = &app.args.device;
-    let interrupt = interrupt_ident();
-    quote!(#device::#interrupt)
-}
-
-pub fn check_stack_overflow_before_init(
-    _app: &App,
-    _analysis: &CodegenAnalysis,
-) -> Vec<TokenStream2> {
-    vec![quote!(
-        // Check for stack overflow using symbols from `cortex-m-rt`.
-        extern "C" {
-            pub static _stack_start: u32;
-            pub static __ebss: u32;
-        }
-
-        let stack_start = &_stack_start as *const _ as u32;
-        let ebss = &__ebss as *const _ as u32;
-
-        if stack_start > ebss {
-            // No flip-link usage, check the MSP for overflow.
-            if rtic::export::msp::read() <= ebss {
-                panic!("Stack overflow after allocating executors");
-            }
-        }
-    )]
-}
-
 #[cfg(feature = "cortex-m-source-masking")]
 mod source_masking {
     use super::*;
-    use std::collections::HashMap;
 
     /// Generates a `Mutex` implementation
     #[allow(clippy::too_many_arguments)]
@@ -87,14 +57,14 @@ mod source_masking {
             quote!(#name)
         };
 
-        // Computing mapping of used interrupts to masks
+        // Computing mapping of used interrupts to their priorities
         let interrupt_ids = analysis.interrupts.iter().map(|(p, (id, _))| (p, id));
 
-        let mut prio_to_masks = HashMap::new();
         let device = &app.args.device;
         // let mut uses_exceptions_with_resources = false;
 
         let mut mask_ids = Vec::new();
+        let mut prio_ids = Vec::new();
 
         for (&priority, name) in interrupt_ids.chain(app.hardware_tasks.values().flat_map(|task| {
             if !is_exception(&task.args.binds) {
@@ -103,27 +73,12 @@ mod source_masking {
                 None
             }
         })) {
-            let v: &mut Vec<_> = prio_to_masks.entry(priority - 1).or_default();
-            v.push(quote!(#device::Interrupt::#name as u32));
+            prio_ids.push(quote!((#priority, #device::Interrupt::#name as u32)));
             mask_ids.push(quote!(#device::Interrupt::#name as u32));
         }
 
-        // Call rtic::export::create_mask([Mask; N]), where the array is the list of shifts
-
-        let mut mask_arr = Vec::new();
-        // NOTE: 0..3 assumes max 4 priority levels according to M0, M23 spec
-        for i in 0..3 {
-            let v = if let Some(v) = prio_to_masks.get(&i) {
-                v.clone()
-            } else {
-                Vec::new()
-            };
-
-            mask_arr.push(quote!(
-                rtic::export::create_mask([#(#v),*])
-            ));
-        }
-
+        // One mask per priority level below the highest one of the device, which is checked at
+        // compile time against `NVIC_PRIO_BITS` and the number of NVIC registers
         quote!(
             #(#cfgs)*
             impl<'a> rtic::Mutex for #path<'a> {
@@ -134,7 +89,9 @@ mod source_masking {
                     /// Priority ceiling
                     const CEILING: u8 = #ceiling;
                     const N_CHUNKS: usize = rtic::export::compute_mask_chunks([#(#mask_ids),*]);
-                    const MASKS: [rtic::export::Mask<N_CHUNKS>; 3] = [#(#mask_arr),*];
+                    const N_LEVELS: usize = rtic::export::mask_levels(#device::NVIC_PRIO_BITS);
+                    const MASKS: [rtic::export::Mask<N_CHUNKS>; N_LEVELS] =
+                        rtic::export::create_masks([#(#prio_ids),*]);
 
                     unsafe {
                         rtic::export::lock(
@@ -148,15 +105,12 @@ mod source_masking {
             }
         )
     }
-
-    pub fn extra_assertions(_: &App, _: &SyntaxAnalysis) -> Vec<TokenStream2> {
-        vec![]
-    }
 }
 
 #[cfg(feature = "cortex-m-basepri")]
 mod basepri {
     use super::*;
+    use crate::codegen::timing;
 
     /// Generates a `Mutex` implementation
     #[allow(clippy::too_many_arguments)]
@@ -177,6 +131,7 @@ mod basepri {
         };
 
         let device = &app.args.device;
+        let f = timing::measure_lock(name, quote!(f));
         quote!(
             #(#cfgs)*
             impl<'a> rtic::Mutex for #path<'a> {
@@ -192,206 +147,198 @@ mod basepri {
                             #ptr,
                             CEILING,
                             #device::NVIC_PRIO_BITS,
-                            f,
+                            #f,
                         )
                     }
                 }
             }
         )
     }
+}
 
-    pub fn extra_assertions(_: &App, _: &SyntaxAnalysis) -> Vec<TokenStream2> {
-        vec![]
+impl Backend for Cortex {
+    fn interrupt_ident() -> Ident {
+        let span = Span::call_site();
+        Ident::new("interrupt", span)
     }
-}
 
-pub fn pre_init_preprocessing(_app: &mut App, _analysis: &SyntaxAnalysis) -> parse::Result<()> {
-    Ok(())
-}
+    #[allow(clippy::too_many_arguments)]
+    fn impl_mutex(
+        app: &App,
+        analysis: &CodegenAnalysis,
+        cfgs: &[Attribute],
+        resources_prefix: bool,
+        name: &Ident,
+        ty: &TokenStream2,
+        ceiling: u8,
+        ptr: &TokenStream2,
+    ) -> TokenStream2 {
+        mask::impl_mutex(
+            app,
+            analysis,
+            cfgs,
+            resources_prefix,
+            name,
+            ty,
+            ceiling,
+            ptr,
+        )
+    }
 
-pub fn pre_init_checks(app: &App, _: &SyntaxAnalysis) -> Vec<TokenStream2> {
-    let mut stmts = vec![];
+    fn check_stack_overflow_before_init(
+        _app: &App,
+        _analysis: &CodegenAnalysis,
+    ) -> Vec<TokenStream2> {
+        vec![quote!(
+            // Check for stack overflow using symbols from `cortex-m-rt`.
+            extern "C" {
+                pub static _stack_start: u32;
+                pub static __ebss: u32;
+            }
 
-    // check that all dispatchers exists in the `Interrupt` enumeration regardless of whether
-    // they are used or not
-    let interrupt = interrupt_ident();
-    let rt_err = util::rt_err_ident();
+            let stack_start = &_stack_start as *const _ as u32;
+            let ebss = &__ebss as *const _ as u32;
 
-    for name in app.args.dispatchers.keys() {
-        stmts.push(quote!(let _ = #rt_err::#interrupt::#name;));
+            if stack_start > ebss {
+                // No flip-link usage, check the MSP for overflow.
+                if rtic::export::msp::read() <= ebss {
+                    panic!("Stack overflow after allocating executors");
+                }
+            }
+        )]
     }
 
-    stmts
-}
+    fn pre_init_checks(app: &App, _: &SyntaxAnalysis) -> Vec<TokenStream2> {
+        let mut stmts = vec![];
 
-pub fn pre_init_enable_interrupts(app: &App, analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
-    let mut stmts = vec![];
+        // check that all dispatchers exists in the `Interrupt` enumeration regardless of whether
+        // they are used or not
+        let interrupt = Self::interrupt_ident();
+        let rt_err = util::rt_err_ident();
 
-    let interrupt = interrupt_ident();
-    let rt_err = util::rt_err_ident();
-    let device = &app.args.device;
-    let nvic_prio_bits = quote!(#device::NVIC_PRIO_BITS);
-    let interrupt_ids = analysis.interrupts.iter().map(|(p, (id, _))| (p, id));
-
-    // Unmask interrupts and set their priorities
-    for (&priority, name) in interrupt_ids.chain(app.hardware_tasks.values().filter_map(|task| {
-        if is_exception(&task.args.binds) {
-            // We do exceptions in another pass
-            None
-        } else {
-            Some((&task.args.priority, &task.args.binds))
+        for name in app.args.dispatchers.keys() {
+            stmts.push(quote!(let _ = #rt_err::#interrupt::#name;));
         }
-    })) {
-        let es = format!(
-            "Maximum priority used by interrupt vector '{name}' is more than supported by hardware"
-        );
-        // Compile time assert that this priority is supported by the device
-        stmts.push(quote!(
-            const _: () =  if (1 << #nvic_prio_bits) < #priority as usize { ::core::panic!(#es); };
-        ));
-
-        stmts.push(quote!(
-            core.NVIC.set_priority(
-                #rt_err::#interrupt::#name,
-                rtic::export::cortex_logical2hw(#priority, #nvic_prio_bits),
-            );
-        ));
 
-        // NOTE unmask the interrupt *after* setting its priority: changing the priority of a pended
-        // interrupt is implementation defined
-        stmts.push(quote!(rtic::export::NVIC::unmask(#rt_err::#interrupt::#name);));
+        stmts
     }
 
-    // Set exception priorities
-    for (name, priority) in app.hardware_tasks.values().filter_map(|task| {
-        if is_exception(&task.args.binds) {
-            Some((&task.args.binds, task.args.priority))
-        } else {
-            None
-        }
-    }) {
-        let es = format!(
-            "Maximum priority used by interrupt vector '{name}' is more than supported by hardware"
-        );
-        // Compile time assert that this priority is supported by the device
-        stmts.push(quote!(
-            const _: () =  if (1 << #nvic_prio_bits) < #priority as usize { ::core::panic!(#es); };
-        ));
-
-        stmts.push(quote!(core.SCB.set_priority(
-            rtic::export::SystemHandler::#name,
-            rtic::export::cortex_logical2hw(#priority, #nvic_prio_bits),
-        );));
-    }
+    fn pre_init_enable_interrupts(app: &App, analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
+        let mut stmts = vec![];
 
-    stmts
-}
-
-pub fn architecture_specific_analysis(app: &App, _: &SyntaxAnalysis) -> parse::Result<()> {
-    // Check that external (device-specific) interrupts are not named after known (Cortex-M)
-    // exceptions
-    for name in app.args.dispatchers.keys() {
-        let name_s = name.to_string();
-
-        match &*name_s {
-            "NonMaskableInt" | "HardFault" | "MemoryManagement" | "BusFault" | "UsageFault"
-            | "SecureFault" | "SVCall" | "DebugMonitor" | "PendSV" | "SysTick" => {
-                return Err(parse::Error::new(
-                    name.span(),
-                    "Cortex-M exceptions can't be used as `extern` interrupts",
-                ));
-            }
+        let interrupt = Self::interrupt_ident();
+        let rt_err = util::rt_err_ident();
+        let device = &app.args.device;
+        let nvic_prio_bits = quote!(#device::NVIC_PRIO_BITS);
+        let interrupt_ids = analysis.interrupts.iter().map(|(p, (id, _))| (p, id));
 
-            _ => {}
-        }
-    }
+        // Unmask interrupts and set their priorities
+        for (&priority, name) in
+            interrupt_ids.chain(app.hardware_tasks.values().filter_map(|task| {
+                if is_exception(&task.args.binds) {
+                    // We do exceptions in another pass
+                    None
+                } else {
+                    Some((&task.args.priority, &task.args.binds))
+                }
+            }))
+        {
+            let es = format!(
+                "Maximum priority used by interrupt vector '{name}' is more than supported by hardware"
+            );
+            // Compile time assert that this priority is supported by the device
+            stmts.push(quote_spanned!(name.span()=>
+                const _: () =  if (1 << #nvic_prio_bits) < #priority as usize { ::core::panic!(#es); };
+            ));
 
-    // Check that there are enough external interrupts to dispatch the software tasks and the timer
-    // queue handler
-    let mut first = None;
-    let priorities = app
-        .software_tasks
-        .iter()
-        .map(|(name, task)| {
-            first = Some(name);
-            task.args.priority
-        })
-        .filter(|prio| *prio > 0)
-        .collect::<HashSet<_>>();
-
-    let need = priorities.len();
-    let given = app.args.dispatchers.len();
-    if need > given {
-        let s = {
-            format!(
-                "not enough interrupts to dispatch \
-                    all software tasks (need: {need}; given: {given})"
-            )
-        };
+            stmts.push(quote!(
+                core.NVIC.set_priority(
+                    #rt_err::#interrupt::#name,
+                    rtic::export::cortex_logical2hw(#priority, #nvic_prio_bits),
+                );
+            ));
 
-        // If not enough tasks and first still is None, may cause
-        // "custom attribute panicked" due to unwrap on None
-        return Err(parse::Error::new(first.unwrap().span(), s));
-    }
+            // NOTE unmask the interrupt *after* setting its priority: changing the priority of a pended
+            // interrupt is implementation defined
+            stmts.push(quote!(rtic::export::NVIC::unmask(#rt_err::#interrupt::#name);));
+        }
 
-    // Check that all exceptions are valid; only exceptions with configurable priorities are
-    // accepted
-    for (name, task) in &app.hardware_tasks {
-        let name_s = task.args.binds.to_string();
-        match &*name_s {
-            "NonMaskableInt" | "HardFault" => {
-                return Err(parse::Error::new(
-                    name.span(),
-                    "only exceptions with configurable priority can be used as hardware tasks",
-                ));
+        // Set exception priorities
+        for (name, priority) in app.hardware_tasks.values().filter_map(|task| {
+            if is_exception(&task.args.binds) {
+                Some((&task.args.binds, task.args.priority))
+            } else {
+                None
             }
+        }) {
+            let es = format!(
+                "Maximum priority used by interrupt vector '{name}' is more than supported by hardware"
+            );
+            // Compile time assert that this priority is supported by the device
+            stmts.push(quote_spanned!(name.span()=>
+                const _: () =  if (1 << #nvic_prio_bits) < #priority as usize { ::core::panic!(#es); };
+            ));
 
-            _ => {}
+            stmts.push(quote!(core.SCB.set_priority(
+                rtic::export::SystemHandler::#name,
+                rtic::export::cortex_logical2hw(#priority, #nvic_prio_bits),
+            );));
         }
+
+        stmts
     }
 
-    Ok(())
-}
+    fn architecture_specific_analysis(app: &App, _: &SyntaxAnalysis) -> parse::Result<()> {
+        // Check that external (device-specific) interrupts are not named after known (Cortex-M)
+        // exceptions
+        for name in app.args.dispatchers.keys() {
+            let name_s = name.to_string();
+
+            match &*name_s {
+                "NonMaskableInt" | "HardFault" | "MemoryManagement" | "BusFault" | "UsageFault"
+                | "SecureFault" | "SVCall" | "DebugMonitor" | "PendSV" | "SysTick" => {
+                    return Err(parse::Error::new(
+                        name.span(),
+                        "Cortex-M exceptions can't be used as `extern` interrupts",
+                    ));
+                }
 
-pub fn interrupt_entry(_app: &App, _analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
-    vec![]
-}
+                _ => {}
+            }
+        }
 
-pub fn interrupt_exit(_app: &App, _analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
-    vec![]
-}
+        // Check that all exceptions are valid; only exceptions with configurable priorities are
+        // accepted
+        for (name, task) in &app.hardware_tasks {
+            let name_s = task.args.binds.to_string();
+            match &*name_s {
+                "NonMaskableInt" | "HardFault" => {
+                    return Err(parse::Error::new(
+                        name.span(),
+                        "only exceptions with configurable priority can be used as hardware tasks",
+                    ));
+                }
 
-pub fn async_entry(
-    _app: &App,
-    _analysis: &CodegenAnalysis,
-    _dispatcher_name: Ident,
-) -> Vec<TokenStream2> {
-    vec![]
-}
+                _ => {}
+            }
+        }
 
-pub fn async_prio_limit(app: &App, analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
-    let max = if let Some(max) = analysis.max_async_prio {
-        quote!(#max)
-    } else {
-        // No limit
-        let device = &app.args.device;
-        quote!(1 << #device::NVIC_PRIO_BITS)
-    };
-
-    vec![quote!(
-        /// Holds the maximum priority level for use by async HAL drivers.
-        #[no_mangle]
-        static RTIC_ASYNC_MAX_LOGICAL_PRIO: u8 = #max;
-    )]
-}
-pub fn handler_config(
-    _app: &App,
-    _analysis: &CodegenAnalysis,
-    _dispatcher_name: Ident,
-) -> Vec<TokenStream2> {
-    vec![]
-}
+        Ok(())
+    }
 
-pub fn extra_modules(_app: &App, _analysis: &SyntaxAnalysis) -> Vec<TokenStream2> {
-    vec![]
+    fn async_prio_limit(app: &App, analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
+        let max = if let Some(max) = analysis.max_async_prio {
+            quote!(#max)
+        } else {
+            // No limit
+            let device = &app.args.device;
+            quote!(1 << #device::NVIC_PRIO_BITS)
+        };
+
+        vec![quote!(
+            /// Holds the maximum priority level for use by async HAL drivers.
+            #[no_mangle]
+            static RTIC_ASYNC_MAX_LOGICAL_PRIO: u8 = #max;
+        )]
+    }
 }
@@ -1,20 +1,20 @@
-#[cfg(feature = "riscv-esp32c3")]
-pub use esp32c3::*;
-
-#[cfg(feature = "riscv-esp32c3")]
-mod esp32c3 {
-    use crate::{
-        analyze::Analysis as CodegenAnalysis,
-        codegen::util,
-        syntax::{analyze::Analysis as SyntaxAnalysis, ast::App},
-    };
-    use proc_macro2::{Span, TokenStream as TokenStream2};
-    use quote::quote;
-    use std::collections::HashSet;
-    use syn::{parse, Attribute, Ident};
+use crate::{
+    analyze::Analysis as CodegenAnalysis,
+    codegen::util,
+    syntax::{analyze::Analysis as SyntaxAnalysis, ast::App},
+};
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{quote, quote_spanned};
+use syn::{parse, Attribute, Ident};
 
+use super::Backend;
+
+/// ESP32-C3 backend, using the interrupt priority threshold of the interrupt matrix for locks
+pub struct Esp32c3;
+
+impl Backend for Esp32c3 {
     #[allow(clippy::too_many_arguments)]
-    pub fn impl_mutex(
+    fn impl_mutex(
         _app: &App,
         _analysis: &CodegenAnalysis,
         cfgs: &[Attribute],
@@ -50,26 +50,12 @@ mod esp32c3 {
         )
     }
 
-    pub fn interrupt_ident() -> Ident {
+    fn interrupt_ident() -> Ident {
         let span = Span::call_site();
         Ident::new("Interrupt", span)
     }
 
-    pub fn interrupt_mod(app: &App) -> TokenStream2 {
-        let device = &app.args.device;
-        let interrupt = interrupt_ident();
-        quote!(#device::#interrupt)
-    }
-
-    pub fn extra_assertions(_: &App, _: &SyntaxAnalysis) -> Vec<TokenStream2> {
-        vec![]
-    }
-
-    pub fn pre_init_preprocessing(_app: &mut App, _analysis: &SyntaxAnalysis) -> parse::Result<()> {
-        Ok(())
-    }
-
-    pub fn pre_init_checks(app: &App, _: &SyntaxAnalysis) -> Vec<TokenStream2> {
+    fn pre_init_checks(app: &App, _: &SyntaxAnalysis) -> Vec<TokenStream2> {
         let mut stmts = vec![];
         // check that all dispatchers exists in the `Interrupt` enumeration regardless of whether
         // they are used or not
@@ -80,7 +66,7 @@ mod esp32c3 {
         }
         stmts
     }
-    pub fn pre_init_enable_interrupts(app: &App, analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
+    fn pre_init_enable_interrupts(app: &App, analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
         let mut stmts = vec![];
         let mut curr_cpu_id: u8 = 1; //cpu interrupt id 0 is reserved
         let rt_err = util::rt_err_ident();
@@ -96,7 +82,7 @@ mod esp32c3 {
                 "Maximum priority used by interrupt vector '{name}' is more than supported by hardware"
             );
             // Compile time assert that this priority is supported by the device
-            stmts.push(quote!(
+            stmts.push(quote_spanned!(name.span()=>
                 const _: () =  if (#max_prio) <= #priority as usize { ::core::panic!(#es); };
             ));
             stmts.push(quote!(
@@ -111,10 +97,7 @@ mod esp32c3 {
         stmts
     }
 
-    pub fn architecture_specific_analysis(
-        app: &App,
-        _analysis: &SyntaxAnalysis,
-    ) -> parse::Result<()> {
+    fn architecture_specific_analysis(app: &App, _analysis: &SyntaxAnalysis) -> parse::Result<()> {
         //check if the dispatchers are supported
         for name in app.args.dispatchers.keys() {
             let name_s = name.to_string();
@@ -130,45 +113,10 @@ mod esp32c3 {
             }
         }
 
-        // Check that there are enough external interrupts to dispatch the software tasks and the timer
-        // queue handler
-        let mut first = None;
-        let priorities = app
-            .software_tasks
-            .iter()
-            .map(|(name, task)| {
-                first = Some(name);
-                task.args.priority
-            })
-            .filter(|prio| *prio > 0)
-            .collect::<HashSet<_>>();
-
-        let need = priorities.len();
-        let given = app.args.dispatchers.len();
-        if need > given {
-            let s = {
-                format!(
-                    "not enough interrupts to dispatch \
-                        all software tasks (need: {need}; given: {given})"
-                )
-            };
-
-            // If not enough tasks and first still is None, may cause
-            // "custom attribute panicked" due to unwrap on None
-            return Err(parse::Error::new(first.unwrap().span(), s));
-        }
         Ok(())
     }
 
-    pub fn interrupt_entry(_app: &App, _analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
-        vec![]
-    }
-
-    pub fn interrupt_exit(_app: &App, _analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
-        vec![]
-    }
-
-    pub fn check_stack_overflow_before_init(
+    fn check_stack_overflow_before_init(
         _app: &App,
         _analysis: &CodegenAnalysis,
     ) -> Vec<TokenStream2> {
@@ -191,7 +139,7 @@ mod esp32c3 {
         )]
     }
 
-    pub fn async_entry(
+    fn async_entry(
         _app: &App,
         _analysis: &CodegenAnalysis,
         dispatcher_name: Ident,
@@ -203,7 +151,7 @@ mod esp32c3 {
         stmts
     }
 
-    pub fn async_prio_limit(app: &App, analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
+    fn async_prio_limit(app: &App, analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
         let max = if let Some(max) = analysis.max_async_prio {
             quote!(#max)
         } else {
@@ -218,7 +166,7 @@ mod esp32c3 {
             static RTIC_ASYNC_MAX_LOGICAL_PRIO: u8 = #max;
         )]
     }
-    pub fn handler_config(
+    fn handler_config(
         app: &App,
         analysis: &CodegenAnalysis,
         dispatcher_name: Ident,
@@ -241,8 +189,4 @@ mod esp32c3 {
 
         stmts
     }
-
-    pub fn extra_modules(_app: &App, _analysis: &SyntaxAnalysis) -> Vec<TokenStream2> {
-        vec![]
-    }
 }
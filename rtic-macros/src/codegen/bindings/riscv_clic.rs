@@ -0,0 +1,224 @@
+use crate::{
+    analyze::Analysis as CodegenAnalysis,
+    codegen::util,
+    syntax::{analyze::Analysis as SyntaxAnalysis, ast::App, backend::BackendArgs},
+};
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::quote;
+use syn::{parse, Attribute, Ident};
+
+use super::Backend;
+
+/// RISC-V backend for the core-local interrupt controller (CLIC)
+///
+/// Every priority is a CLIC interrupt level, the hardware preempts lower levels and locks raise
+/// the `mintthresh` threshold to the level of the ceiling.
+pub struct RiscvClic;
+
+fn args(app: &App) -> &BackendArgs {
+    app.args
+        .backend
+        .as_ref()
+        .expect("checked by `architecture_specific_analysis`")
+}
+
+/// The `clicintctl` value of the logical priority `priority`
+///
+/// The level is stored in the upper `level_bits` bits, the unused lower bits read as ones.
+fn level(app: &App, priority: u8) -> u8 {
+    let shift = 8 - args(app).level_bits;
+    let fill = ((1u16 << shift) - 1) as u8;
+
+    ((u16::from(priority) << shift) as u8) | fill
+}
+
+fn max_priority(app: &App) -> u8 {
+    ((1u16 << args(app).level_bits) - 1) as u8
+}
+
+impl Backend for RiscvClic {
+    fn interrupt_ident() -> Ident {
+        let span = Span::call_site();
+        Ident::new("Interrupt", span)
+    }
+
+    fn pend_interrupt(app: &App, name: &Ident) -> TokenStream2 {
+        let int_mod = Self::interrupt_mod(app);
+        quote!(rtic::export::pend(#int_mod::#name as u16);)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn impl_mutex(
+        app: &App,
+        _analysis: &CodegenAnalysis,
+        cfgs: &[Attribute],
+        resources_prefix: bool,
+        name: &Ident,
+        ty: &TokenStream2,
+        ceiling: u8,
+        ptr: &TokenStream2,
+    ) -> TokenStream2 {
+        let path = if resources_prefix {
+            quote!(shared_resources::#name)
+        } else {
+            quote!(#name)
+        };
+        let level = level(app, ceiling);
+
+        quote!(
+            #(#cfgs)*
+            impl<'a> rtic::Mutex for #path<'a> {
+                type T = #ty;
+
+                #[inline(always)]
+                fn lock<RTIC_INTERNAL_R>(&mut self, f: impl FnOnce(&mut #ty) -> RTIC_INTERNAL_R) -> RTIC_INTERNAL_R {
+                    /// Priority ceiling, as a CLIC level
+                    const CEILING: u8 = #level;
+
+                    unsafe {
+                        rtic::export::lock(
+                            #ptr,
+                            CEILING,
+                            f,
+                        )
+                    }
+                }
+            }
+        )
+    }
+
+    fn pre_init_preprocessing(app: &mut App, _analysis: &SyntaxAnalysis) -> parse::Result<()> {
+        app.args.core = false; // there are no core peripherals to hand out on RISC-V
+        Ok(())
+    }
+
+    fn pre_init_checks(app: &App, _: &SyntaxAnalysis) -> Vec<TokenStream2> {
+        let mut stmts = vec![];
+
+        // check that all dispatchers exists in the `Interrupt` enumeration regardless of whether
+        // they are used or not
+        let rt_err = util::rt_err_ident();
+
+        for name in app.args.dispatchers.keys() {
+            stmts.push(quote!(let _ = #rt_err::Interrupt::#name;));
+        }
+
+        stmts
+    }
+
+    fn pre_init_enable_interrupts(app: &App, analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
+        let mut stmts = vec![];
+
+        let rt_err = util::rt_err_ident();
+        let BackendArgs { base, level_bits } = args(app);
+
+        // Use `level_bits` of `clicintctl` for the level and let all levels through
+        stmts.push(quote!(rtic::export::clic_init(#base, #level_bits);));
+
+        // Dispatchers are pended by software, so they are edge triggered
+        for (&priority, (name, _)) in &analysis.interrupts {
+            let level = level(app, priority);
+
+            stmts.push(quote!(
+                rtic::export::enable(#rt_err::Interrupt::#name as u16, #level, true);
+            ));
+        }
+
+        // Hardware tasks keep the trigger configured for their interrupt source
+        for task in app.hardware_tasks.values() {
+            let name = &task.args.binds;
+            let level = level(app, task.args.priority);
+
+            stmts.push(quote!(
+                rtic::export::enable(#rt_err::Interrupt::#name as u16, #level, false);
+            ));
+        }
+
+        stmts
+    }
+
+    fn architecture_specific_analysis(app: &App, _analysis: &SyntaxAnalysis) -> parse::Result<()> {
+        if app.args.backend.is_none() {
+            return Err(parse::Error::new(
+                Span::call_site(),
+                "CLIC requires backend-specific configuration: `backend = { base = .., level_bits = .. }`",
+            ));
+        }
+
+        // Every priority must map to a level of the CLIC
+        let max = max_priority(app);
+        let priorities = app
+            .hardware_tasks
+            .iter()
+            .map(|(name, task)| (name, task.args.priority))
+            .chain(
+                app.software_tasks
+                    .iter()
+                    .map(|(name, task)| (name, task.args.priority)),
+            );
+
+        for (name, priority) in priorities {
+            if priority > max {
+                return Err(parse::Error::new(
+                    name.span(),
+                    format!(
+                        "this priority is not supported by a CLIC with {} level bits (maximum: {max})",
+                        args(app).level_bits
+                    ),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn check_stack_overflow_before_init(
+        _app: &App,
+        _analysis: &CodegenAnalysis,
+    ) -> Vec<TokenStream2> {
+        vec![quote!(
+            // Check for stack overflow using symbols from `riscv-rt`.
+            extern "C" {
+                pub static _stack_start: u32;
+                pub static _ebss: u32;
+            }
+
+            let stack_start = &_stack_start as *const _ as u32;
+            let ebss = &_ebss as *const _ as u32;
+
+            if stack_start > ebss {
+                // No flip-link usage, check the SP for overflow.
+                if rtic::export::read_sp() <= ebss {
+                    panic!("Stack overflow after allocating executors");
+                }
+            }
+        )]
+    }
+
+    fn async_entry(
+        app: &App,
+        _analysis: &CodegenAnalysis,
+        dispatcher_name: Ident,
+    ) -> Vec<TokenStream2> {
+        // The pending bit of edge triggered interrupts is only cleared by the hardware in vectored
+        // mode, clear it so the dispatcher does not run again
+        let int_mod = Self::interrupt_mod(app);
+        vec![quote!(rtic::export::unpend(#int_mod::#dispatcher_name as u16);)]
+    }
+
+    fn async_prio_limit(app: &App, analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
+        let max = if let Some(max) = analysis.max_async_prio {
+            quote!(#max)
+        } else {
+            // No limit
+            let max = max_priority(app);
+            quote!(#max)
+        };
+
+        vec![quote!(
+            /// Holds the maximum priority level for use by async HAL drivers.
+            #[no_mangle]
+            static RTIC_ASYNC_MAX_LOGICAL_PRIO: u8 = #max;
+        )]
+    }
+}
@@ -0,0 +1,240 @@
+use crate::{
+    analyze::Analysis as CodegenAnalysis,
+    codegen::util,
+    syntax::{analyze::Analysis as SyntaxAnalysis, ast::App, backend::BackendArgs},
+};
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::quote;
+use syn::{parse, Attribute, Ident};
+
+use super::Backend;
+
+/// RISC-V backend for the platform-level interrupt controller (PLIC)
+///
+/// Every priority is a PLIC source priority, the threshold of the hart context is raised to the
+/// priority of the running task and to the ceiling of a lock. All sources share the machine
+/// external interrupt, its handler claims the source and calls the handler of the task.
+pub struct RiscvPlic;
+
+fn args(app: &App) -> &BackendArgs {
+    app.args
+        .backend
+        .as_ref()
+        .expect("checked by `architecture_specific_analysis`")
+}
+
+impl Backend for RiscvPlic {
+    fn interrupt_ident() -> Ident {
+        let span = Span::call_site();
+        Ident::new("Interrupt", span)
+    }
+
+    /// The pending bits of the PLIC are read-only, the device specific `pend` function raises a
+    /// request of the source instead
+    fn pend_interrupt(app: &App, name: &Ident) -> TokenStream2 {
+        let int_mod = Self::interrupt_mod(app);
+        let pend = &args(app).pend;
+        quote!(#pend(#int_mod::#name as u16);)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn impl_mutex(
+        _app: &App,
+        _analysis: &CodegenAnalysis,
+        cfgs: &[Attribute],
+        resources_prefix: bool,
+        name: &Ident,
+        ty: &TokenStream2,
+        ceiling: u8,
+        ptr: &TokenStream2,
+    ) -> TokenStream2 {
+        let path = if resources_prefix {
+            quote!(shared_resources::#name)
+        } else {
+            quote!(#name)
+        };
+
+        quote!(
+            #(#cfgs)*
+            impl<'a> rtic::Mutex for #path<'a> {
+                type T = #ty;
+
+                #[inline(always)]
+                fn lock<RTIC_INTERNAL_R>(&mut self, f: impl FnOnce(&mut #ty) -> RTIC_INTERNAL_R) -> RTIC_INTERNAL_R {
+                    /// Priority ceiling
+                    const CEILING: u8 = #ceiling;
+
+                    unsafe {
+                        rtic::export::lock(
+                            #ptr,
+                            CEILING,
+                            f,
+                        )
+                    }
+                }
+            }
+        )
+    }
+
+    fn pre_init_preprocessing(app: &mut App, _analysis: &SyntaxAnalysis) -> parse::Result<()> {
+        app.args.core = false; // there are no core peripherals to hand out on RISC-V
+        Ok(())
+    }
+
+    fn pre_init_checks(app: &App, _: &SyntaxAnalysis) -> Vec<TokenStream2> {
+        let mut stmts = vec![];
+
+        // check that all dispatchers exists in the `Interrupt` enumeration regardless of whether
+        // they are used or not
+        let rt_err = util::rt_err_ident();
+
+        for name in app.args.dispatchers.keys() {
+            stmts.push(quote!(let _ = #rt_err::Interrupt::#name;));
+        }
+
+        stmts
+    }
+
+    fn pre_init_enable_interrupts(app: &App, analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
+        let mut stmts = vec![];
+
+        let rt_err = util::rt_err_ident();
+        let BackendArgs { base, context, .. } = args(app);
+
+        // Use the context of the hart and let all priorities through
+        stmts.push(quote!(rtic::export::plic_init(#base, #context);));
+
+        for (&priority, (name, _)) in &analysis.interrupts {
+            stmts.push(quote!(
+                rtic::export::enable(#rt_err::Interrupt::#name as u16, #priority);
+            ));
+        }
+
+        for task in app.hardware_tasks.values() {
+            let name = &task.args.binds;
+            let priority = task.args.priority;
+
+            stmts.push(quote!(
+                rtic::export::enable(#rt_err::Interrupt::#name as u16, #priority);
+            ));
+        }
+
+        stmts
+    }
+
+    fn architecture_specific_analysis(app: &App, _analysis: &SyntaxAnalysis) -> parse::Result<()> {
+        if app.args.backend.is_none() {
+            return Err(parse::Error::new(
+                Span::call_site(),
+                "PLIC requires backend-specific configuration: `backend = { base = .., context = .., max_priority = .., pend = .. }`",
+            ));
+        }
+
+        // Every priority must be a priority of the PLIC
+        let max = args(app).max_priority;
+        let priorities = app
+            .hardware_tasks
+            .iter()
+            .map(|(name, task)| (name, task.args.priority))
+            .chain(
+                app.software_tasks
+                    .iter()
+                    .map(|(name, task)| (name, task.args.priority)),
+            );
+
+        for (name, priority) in priorities {
+            if priority > max {
+                return Err(parse::Error::new(
+                    name.span(),
+                    format!("this priority is not supported by the PLIC (maximum: {max})"),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn check_stack_overflow_before_init(
+        _app: &App,
+        _analysis: &CodegenAnalysis,
+    ) -> Vec<TokenStream2> {
+        vec![quote!(
+            // Check for stack overflow using symbols from `riscv-rt`.
+            extern "C" {
+                pub static _stack_start: u32;
+                pub static _ebss: u32;
+            }
+
+            let stack_start = &_stack_start as *const _ as u32;
+            let ebss = &_ebss as *const _ as u32;
+
+            if stack_start > ebss {
+                // No flip-link usage, check the SP for overflow.
+                if rtic::export::read_sp() <= ebss {
+                    panic!("Stack overflow after allocating executors");
+                }
+            }
+        )]
+    }
+
+    fn async_prio_limit(app: &App, analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
+        let max = if let Some(max) = analysis.max_async_prio {
+            quote!(#max)
+        } else {
+            // No limit
+            let max = args(app).max_priority;
+            quote!(#max)
+        };
+
+        vec![quote!(
+            /// Holds the maximum priority level for use by async HAL drivers.
+            #[no_mangle]
+            static RTIC_ASYNC_MAX_LOGICAL_PRIO: u8 = #max;
+        )]
+    }
+
+    /// The PLIC signals all sources with the machine external interrupt, its handler calls the
+    /// handler of the claimed source
+    fn extra_modules(app: &App, analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
+        let int_mod = Self::interrupt_mod(app);
+
+        let hardware_tasks = app.hardware_tasks.values().map(|task| {
+            let name = &task.args.binds;
+            let cfgs = &task.cfgs;
+
+            quote!(
+                #(#cfgs)*
+                if source == #int_mod::#name as u16 {
+                    return #name();
+                }
+            )
+        });
+
+        let dispatchers = analysis.interrupts.values().map(|(name, _)| {
+            quote!(
+                if source == #int_mod::#name as u16 {
+                    return #name();
+                }
+            )
+        });
+
+        let handlers: Vec<_> = hardware_tasks.chain(dispatchers).collect();
+        let source = if handlers.is_empty() {
+            quote!(_)
+        } else {
+            quote!(source)
+        };
+
+        vec![quote!(
+            /// Handler of the machine external interrupt, dispatches the claimed PLIC source
+            #[doc(hidden)]
+            #[no_mangle]
+            #[allow(non_snake_case)]
+            unsafe extern "C" fn MachineExternal() {
+                rtic::export::dispatch(|#source| {
+                    #(#handlers)*
+                });
+            }
+        )]
+    }
+}
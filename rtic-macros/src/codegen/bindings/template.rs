@@ -1,90 +1,73 @@
-use crate::{
-    analyze::Analysis as CodegenAnalysis,
-    syntax::{analyze::Analysis as SyntaxAnalysis, ast::App},
-};
+//! Reference backend, the starting point for a new target
+//!
+//! Only the required methods of [`Backend`] and the limit for async HAL drivers are implemented,
+//! every other hook keeps its default.
+//! The generated code expects a runtime module at `rtic::export` with the functions listed on
+//! [`Backend::export_path`] and a `lock(ptr, ceiling, f)` function.
+
+use crate::{analyze::Analysis as CodegenAnalysis, syntax::ast::App};
 use proc_macro2::{Span, TokenStream as TokenStream2};
 use quote::quote;
-use syn::{parse, Attribute, Ident};
-
-pub fn interrupt_ident() -> Ident {
-    let span = Span::call_site();
-    Ident::new("interrupt", span)
-}
-
-pub fn interrupt_mod(app: &App) -> TokenStream2 {
-    let device = &app.args.device;
-    let interrupt = interrupt_ident();
-    quote!(#device::#interrupt)
-}
-
-pub fn impl_mutex(
-    app: &App,
-    analysis: &CodegenAnalysis,
-    cfgs: &[Attribute],
-    resources_prefix: bool,
-    name: &Ident,
-    ty: &TokenStream2,
-    ceiling: u8,
-    ptr: &TokenStream2,
-) -> TokenStream2 {
-    quote!()
-}
-
-pub fn extra_assertions(app: &App, analysis: &SyntaxAnalysis) -> Vec<TokenStream2> {
-    vec![]
-}
-
-pub fn pre_init_preprocessing(app: &mut App, analysis: &SyntaxAnalysis) -> parse::Result<()> {
-    Ok(())
-}
+use syn::{Attribute, Ident};
 
-pub fn pre_init_checks(app: &App, analysis: &SyntaxAnalysis) -> Vec<TokenStream2> {
-    vec![]
-}
+use super::Backend;
 
-pub fn pre_init_enable_interrupts(app: &App, analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
-    vec![]
-}
+/// Backend without an interrupt controller, used to test the target independent code generation
+pub struct Template;
 
-pub fn architecture_specific_analysis(app: &App, analysis: &SyntaxAnalysis) -> parse::Result<()> {
-    Ok(())
-}
+impl Backend for Template {
+    fn interrupt_ident() -> Ident {
+        let span = Span::call_site();
+        Ident::new("interrupt", span)
+    }
 
-pub fn interrupt_entry(app: &App, analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
-    vec![]
-}
+    fn impl_mutex(
+        _app: &App,
+        _analysis: &CodegenAnalysis,
+        cfgs: &[Attribute],
+        resources_prefix: bool,
+        name: &Ident,
+        ty: &TokenStream2,
+        ceiling: u8,
+        ptr: &TokenStream2,
+    ) -> TokenStream2 {
+        let path = if resources_prefix {
+            quote!(shared_resources::#name)
+        } else {
+            quote!(#name)
+        };
 
-pub fn interrupt_exit(app: &App, analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
-    vec![]
-}
+        quote!(
+            #(#cfgs)*
+            impl<'a> rtic::Mutex for #path<'a> {
+                type T = #ty;
 
-pub fn check_stack_overflow_before_init(
-    _app: &App,
-    _analysis: &CodegenAnalysis,
-) -> Vec<TokenStream2> {
-    vec![]
-}
+                #[inline(always)]
+                fn lock<RTIC_INTERNAL_R>(&mut self, f: impl FnOnce(&mut #ty) -> RTIC_INTERNAL_R) -> RTIC_INTERNAL_R {
+                    /// Priority ceiling
+                    const CEILING: u8 = #ceiling;
 
-pub fn async_entry(
-    app: &App,
-    analysis: &CodegenAnalysis,
-    dispatcher_name: Ident,
-) -> Vec<TokenStream2> {
-    vec![]
-}
+                    unsafe { rtic::export::lock(#ptr, CEILING, f) }
+                }
+            }
+        )
+    }
 
-pub fn async_prio_limit(app: &App, analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
-    vec![]
-}
+    fn pre_init_enable_interrupts(_app: &App, _analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
+        vec![]
+    }
 
-pub fn handler_config(
-    app: &App,
-    analysis: &CodegenAnalysis,
-    dispatcher_name: Ident,
-) -> Vec<TokenStream2> {
-    vec![]
-}
+    fn async_prio_limit(_app: &App, analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
+        let max = if let Some(max) = analysis.max_async_prio {
+            quote!(#max)
+        } else {
+            quote!(u8::MAX) // No limit
+        };
 
-pub fn extra_modules(app: &App, analysis: &SyntaxAnalysis) -> Vec<TokenStream2> {
-    vec![]
+        vec![quote!(
+            /// Holds the maximum priority level for use by async HAL drivers.
+            #[no_mangle]
+            static RTIC_ASYNC_MAX_LOGICAL_PRIO: u8 = #max;
+        )]
+    }
 }
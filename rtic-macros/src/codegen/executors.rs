@@ -0,0 +1,60 @@
+use crate::codegen::util;
+use crate::syntax::ast::App;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{quote, quote_spanned};
+
+/// Generates the `executors` module listing the executors of the software tasks, and the
+/// checks of the `max_size` of their futures
+pub fn codegen(app: &App) -> TokenStream2 {
+    let mut checks = vec![];
+    let mut tasks = vec![];
+
+    for (name, task) in &app.software_tasks {
+        let cfgs = &task.cfgs;
+        let entry = util::task_entry_ident(app, name);
+        let future_size = util::future_size_n_args_ident(task.inputs.len());
+        let executor_layout = util::executor_layout_n_args_ident(task.inputs.len());
+        let span = name.span();
+        let name = name.to_string();
+        let priority = task.args.priority;
+        let capacity = task.args.capacity;
+
+        tasks.push(quote!(
+            #(#cfgs)*
+            rtic::ExecutorInfo {
+                name: #name,
+                priority: #priority,
+                capacity: #capacity,
+                future_size: rtic::export::executor::AsyncTaskExecutor::#future_size(&super::#entry),
+                executor_size: rtic::export::executor::AsyncTaskExecutor::#executor_layout(&super::#entry).size(),
+            }
+        ));
+
+        if let Some(max_size) = task.args.max_size {
+            // Reported on the name of the task
+            checks.push(quote_spanned!(span=>
+                #(#cfgs)*
+                const _: () = {
+                    let size = rtic::export::executor::AsyncTaskExecutor::#future_size(&#entry);
+                    if size > #max_size {
+                        ::core::panic!(
+                            "{}",
+                            rtic::export::executor::MaxSizeExceeded::new(#name, size, #max_size)
+                                .as_str()
+                        );
+                    }
+                };
+            ));
+        }
+    }
+
+    quote!(
+        #(#checks)*
+
+        /// The executors of the software tasks, see `rtic::executors`
+        pub mod executors {
+            /// The executors of each software task
+            pub const TASKS: &[rtic::ExecutorInfo] = &[#(#tasks),*];
+        }
+    )
+}
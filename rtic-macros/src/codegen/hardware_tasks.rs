@@ -2,8 +2,8 @@ use crate::syntax::{ast::App, Context};
 use crate::{
     analyze::Analysis,
     codegen::{
-        bindings::{interrupt_entry, interrupt_exit, handler_config},
-        local_resources_struct, module, shared_resources_struct,
+        bindings::{Backend, Selected},
+        local_resources_struct, module, shared_resources_struct, stack, timing,
     },
 };
 use proc_macro2::TokenStream as TokenStream2;
@@ -20,9 +20,13 @@ pub fn codegen(app: &App, analysis: &Analysis) -> TokenStream2 {
         let priority = task.args.priority;
         let cfgs = &task.cfgs;
         let attrs = &task.attrs;
-        let entry_stmts = interrupt_entry(app, analysis);
-        let exit_stmts = interrupt_exit(app, analysis);
-        let config = handler_config(app, analysis, symbol.clone());
+        let entry_stmts = Selected::interrupt_entry(app, analysis);
+        let exit_stmts = Selected::interrupt_exit(app, analysis);
+        let config = Selected::handler_config(app, analysis, symbol.clone());
+        let export = Selected::export_path();
+        let run = timing::measure_task(name, quote!(#name(#name::Context::new())));
+        let stack_enter = stack::enter(app);
+        let stack_exit = stack::exit(app, priority);
 
         mod_app.push(quote!(
             #[allow(non_snake_case)]
@@ -32,15 +36,15 @@ pub fn codegen(app: &App, analysis: &Analysis) -> TokenStream2 {
             #(#config)*
             unsafe fn #symbol() {
                 #(#entry_stmts)*
+                #stack_enter
 
                 const PRIORITY: u8 = #priority;
 
-                rtic::export::run(PRIORITY, || {
-                    #name(
-                        #name::Context::new()
-                    )
+                #export::run(PRIORITY, || {
+                    #run
                 });
 
+                #stack_exit
                 #(#exit_stmts)*
             }
         ));
@@ -1,5 +1,6 @@
 use proc_macro2::TokenStream as TokenStream2;
 use quote::quote;
+use syn::{Expr, GenericParam, Generics};
 
 use crate::{
     analyze::Analysis,
@@ -53,7 +54,13 @@ pub fn codegen(app: &App, analysis: &Analysis) -> TokenStream2 {
         })
         .collect();
 
+    let shared_bounds = resource_bounds(&app.shared_resources_generics);
+    let local_bounds = resource_bounds(&app.local_resources_generics);
+
     root_init.push(quote! {
+        #shared_bounds
+        #local_bounds
+
         #[doc = r"Shared resources"]
         #shared_vis struct #shared {
             #(#shared_resources)*
@@ -103,3 +110,41 @@ pub fn codegen(app: &App, analysis: &Analysis) -> TokenStream2 {
         #user_init
     )
 }
+
+/// Checks that the defaults of the resource struct generic parameters satisfy their bounds
+fn resource_bounds(generics: &Generics) -> Option<TokenStream2> {
+    if generics.params.is_empty() {
+        return None;
+    }
+
+    let mut params = generics.clone();
+    let mut defaults = vec![];
+    for param in params.params.iter_mut() {
+        match param {
+            GenericParam::Type(param) => {
+                let default = param.default.take();
+                param.eq_token = None;
+                defaults.push(quote!(#default));
+            }
+            GenericParam::Const(param) => {
+                let default = param.default.take();
+                param.eq_token = None;
+                defaults.push(match default {
+                    Some(default @ (Expr::Lit(_) | Expr::Block(_))) => quote!(#default),
+                    default => quote!({ #default }),
+                });
+            }
+            GenericParam::Lifetime(_) => {}
+        }
+    }
+    let where_clause = &params.where_clause;
+
+    Some(quote!(
+        const _: () = {
+            #[allow(non_snake_case)]
+            fn bounds #params () #where_clause {}
+
+            let _: fn() = bounds::<#(#defaults),*>;
+        };
+    ))
+}
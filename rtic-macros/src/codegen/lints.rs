@@ -0,0 +1,284 @@
+use proc_macro2::{Delimiter, Spacing, Span, TokenStream as TokenStream2, TokenTree};
+use quote::{format_ident, quote};
+use syn::{Attribute, Ident, Stmt};
+
+use crate::{
+    analyze::Analysis,
+    syntax::{
+        analyze::Ownership,
+        ast::{Access, App, SharedResources},
+    },
+};
+
+/// A task accessing shared resources, as seen by the lints
+struct Accessor<'a> {
+    name: &'a Ident,
+    cfgs: &'a [Attribute],
+    shared_resources: &'a SharedResources,
+    /// The task body, `None` for `extern` tasks
+    body: Option<TokenStream2>,
+    /// Whether the task can have `#[local]` resources
+    can_have_locals: bool,
+}
+
+/// Generates warnings about the use of shared resources
+///
+/// - resources that are not used by any task
+/// - tasks listing a resource they never use
+/// - resources used by a single task, which can be `#[local]` instead
+/// - contended resources that are only ever read, which can use `&resource` access instead
+///
+/// Proc macros can not emit warnings on stable Rust, so each warning is the use of a deprecated
+/// item spanned at the offending resource.
+pub fn codegen(app: &App, analysis: &Analysis) -> Vec<TokenStream2> {
+    if !app.args.resource_lints {
+        return vec![];
+    }
+
+    let body = |is_extern: bool, stmts: &[Stmt]| (!is_extern).then(|| quote!(#(#stmts)*));
+
+    let accessors: Vec<_> = app
+        .idle
+        .iter()
+        .map(|idle| Accessor {
+            name: &idle.name,
+            cfgs: &[],
+            shared_resources: &idle.args.shared_resources,
+            body: body(idle.is_extern, &idle.stmts),
+            can_have_locals: true,
+        })
+        .chain(app.hardware_tasks.iter().map(|(name, task)| Accessor {
+            name,
+            cfgs: &task.cfgs,
+            shared_resources: &task.args.shared_resources,
+            body: body(task.is_extern, &task.stmts),
+            can_have_locals: true,
+        }))
+        .chain(app.software_tasks.iter().map(|(name, task)| Accessor {
+            name,
+            cfgs: &task.cfgs,
+            shared_resources: &task.args.shared_resources,
+            body: body(task.is_extern, &task.stmts),
+            can_have_locals: task.args.capacity == 1,
+        }))
+        .collect();
+
+    let mut warnings = vec![];
+
+    // Cross-core resources are also used by the other cores
+    for (resource, res) in app
+        .shared_resources
+        .iter()
+        .filter(|(_, res)| !res.properties.cross_core)
+    {
+        let users: Vec<_> = accessors
+            .iter()
+            .filter(|task| task.shared_resources.contains_key(resource))
+            .collect();
+
+        if !analysis.shared_resources.contains(resource) {
+            warnings.push(warning(
+                "unused_shared_resource",
+                &res.cfgs,
+                resource.span(),
+                &format!("shared resource `{resource}` is not used by any task"),
+            ));
+            continue;
+        }
+
+        match (&users[..], analysis.ownerships.get(resource)) {
+            ([task], Some(Ownership::Owned { .. })) if task.can_have_locals => {
+                warnings.push(warning(
+                    "single_task_shared_resource",
+                    &res.cfgs,
+                    resource.span(),
+                    &format!(
+                        "shared resource `{resource}` is only used by `{}`, it can be a `#[local]` resource",
+                        task.name
+                    ),
+                ));
+            }
+
+            (_, Some(Ownership::Contended { .. }))
+                if users.iter().all(|task| {
+                    task.shared_resources[resource] == Access::Exclusive
+                        && task.body.as_ref().is_some_and(|body| {
+                            mentions(body.clone(), resource) && only_reads(body.clone(), resource)
+                        })
+                }) =>
+            {
+                warnings.push(warning(
+                    "read_only_shared_resource",
+                    &res.cfgs,
+                    resource.span(),
+                    &format!(
+                        "shared resource `{resource}` is only read, access it with `&{resource}` in all tasks to remove the locks"
+                    ),
+                ));
+            }
+
+            _ => {}
+        }
+    }
+
+    for task in &accessors {
+        let Some(body) = &task.body else {
+            continue;
+        };
+
+        for (resource, _) in task.shared_resources {
+            if !mentions(body.clone(), resource) {
+                warnings.push(warning(
+                    "unused_shared_access",
+                    task.cfgs,
+                    resource.span(),
+                    &format!(
+                        "`{}` has access to shared resource `{resource}` but never uses it",
+                        task.name
+                    ),
+                ));
+            }
+        }
+    }
+
+    warnings
+}
+
+fn warning(lint: &str, cfgs: &[Attribute], span: Span, message: &str) -> TokenStream2 {
+    let lint = format_ident!("{}", lint, span = span);
+
+    quote!(
+        #(#cfgs)*
+        const _: () = {
+            #[deprecated(note = #message)]
+            #[allow(non_upper_case_globals)]
+            const #lint: () = ();
+
+            #[allow(clippy::let_unit_value)]
+            let _ = #lint;
+        };
+    )
+}
+
+/// Whether `ident` appears anywhere in `tokens`
+fn mentions(tokens: TokenStream2, ident: &Ident) -> bool {
+    tokens.into_iter().any(|tt| match tt {
+        TokenTree::Ident(i) => i == *ident,
+        TokenTree::Group(g) => mentions(g.stream(), ident),
+        _ => false,
+    })
+}
+
+/// Whether every use of `resource` in `tokens` is a `lock` that only reads the resource
+///
+/// This is a heuristic on the tokens: `resource` must be locked with a closure which only
+/// dereferences its argument or reads its fields, anything else, such as method calls, counts as
+/// a write. Identifiers named like the resource which are not field accesses, like `let` bindings,
+/// are ignored.
+fn only_reads(tokens: TokenStream2, resource: &Ident) -> bool {
+    let tokens: Vec<_> = tokens.into_iter().collect();
+    let mut i = 0;
+
+    while let Some(tt) = tokens.get(i) {
+        match tt {
+            TokenTree::Ident(ident) if ident == resource => {
+                // `resource.lock(|arg| body)`
+                if let (
+                    Some(TokenTree::Punct(dot)),
+                    Some(TokenTree::Ident(lock)),
+                    Some(TokenTree::Group(args)),
+                ) = (tokens.get(i + 1), tokens.get(i + 2), tokens.get(i + 3))
+                {
+                    if dot.as_char() == '.'
+                        && lock == "lock"
+                        && args.delimiter() == Delimiter::Parenthesis
+                    {
+                        if !closure_only_reads(args.stream()) {
+                            return false;
+                        }
+
+                        i += 4;
+                        continue;
+                    }
+                }
+
+                // Any other use of the `cx.shared.resource` field
+                if matches!(tokens.get(i.wrapping_sub(1)), Some(TokenTree::Punct(p)) if p.as_char() == '.')
+                {
+                    return false;
+                }
+            }
+
+            TokenTree::Group(g) if !only_reads(g.stream(), resource) => return false,
+
+            _ => {}
+        }
+
+        i += 1;
+    }
+
+    true
+}
+
+/// Whether the closure `|arg| body` only reads `arg`
+fn closure_only_reads(tokens: TokenStream2) -> bool {
+    let tokens: Vec<_> = tokens.into_iter().collect();
+
+    let (Some(TokenTree::Punct(open)), Some(TokenTree::Ident(arg)), Some(TokenTree::Punct(close))) =
+        (tokens.first(), tokens.get(1), tokens.get(2))
+    else {
+        return false;
+    };
+
+    if open.as_char() != '|' || close.as_char() != '|' {
+        return false;
+    }
+
+    let body: TokenStream2 = tokens[3..].iter().cloned().collect();
+    reads_only(body, arg)
+}
+
+fn reads_only(tokens: TokenStream2, arg: &Ident) -> bool {
+    let tokens: Vec<_> = tokens.into_iter().collect();
+    let punct = |i: usize| match tokens.get(i) {
+        Some(TokenTree::Punct(p)) => Some((p.as_char(), p.spacing())),
+        _ => None,
+    };
+
+    // `=`, `+=`, `<<=` and the like, but not `==`
+    let assigns = |i: usize| match punct(i) {
+        Some(('=', Spacing::Alone)) => true,
+        Some((c, Spacing::Joint)) if "+-*/%^&|<>".contains(c) => {
+            matches!(punct(i + 1), Some(('=', _)))
+                || (matches!(c, '<' | '>') && matches!(punct(i + 2), Some(('=', _))))
+        }
+        _ => false,
+    };
+
+    for (i, tt) in tokens.iter().enumerate() {
+        match tt {
+            TokenTree::Ident(ident) if ident == arg => {
+                let deref = i > 0
+                    && matches!(punct(i - 1), Some(('*', _)))
+                    && !matches!(tokens.get(i.wrapping_sub(2)), Some(TokenTree::Ident(m)) if m == "mut");
+
+                let field = matches!(punct(i + 1), Some(('.', _)))
+                    && matches!(tokens.get(i + 2), Some(TokenTree::Ident(_)))
+                    && !matches!(tokens.get(i + 3), Some(TokenTree::Group(_)))
+                    && !matches!(punct(i + 3), Some(('.' | '[', _)));
+
+                let next = if field { i + 3 } else { i + 1 };
+
+                if !(deref || field) || assigns(next) {
+                    return false;
+                }
+            }
+
+            TokenTree::Group(g) if !reads_only(g.stream(), arg) => return false,
+
+            _ => {}
+        }
+    }
+
+    true
+}
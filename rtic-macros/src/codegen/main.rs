@@ -1,6 +1,9 @@
 use crate::{
     analyze::Analysis,
-    codegen::{bindings, util},
+    codegen::{
+        bindings::{Backend, Selected},
+        util,
+    },
     syntax::ast::App,
 };
 use proc_macro2::TokenStream as TokenStream2;
@@ -30,17 +33,53 @@ pub fn codegen(app: &App, analysis: &Analysis) -> TokenStream2 {
 
     let mut executor_allocations = Vec::new();
 
-    for (name, _) in app.software_tasks.iter() {
+    for (name, task) in app.software_tasks.iter() {
         let exec_name = util::internal_task_ident(name, "EXEC");
-        let new_n_args = util::new_n_args_ident(app.software_tasks[name].inputs.len());
-
-        executor_allocations.push(quote!(
-            let executor = ::core::mem::ManuallyDrop::new(rtic::export::executor::AsyncTaskExecutor::#new_n_args(#name));
-            executors_size += ::core::mem::size_of_val(&executor);
-            #exec_name.set_in_main(&executor);
-        ));
+        let new_n_args = util::new_n_args_ident(task.inputs.len());
+        let entry = util::task_entry_ident(app, name);
+
+        // With `executors_section` the executors are constructed in statics in that link section
+        let storage = app.args.executors_section.as_ref().map(|section| {
+            let storage_name = util::internal_task_ident(name, "EXEC_STORAGE");
+            let executor_layout = util::executor_layout_n_args_ident(task.inputs.len());
+            let capacity = task.args.capacity as usize;
+
+            executor_allocations.push(quote!(
+                #[link_section = #section]
+                static #storage_name: [rtic::export::executor::ExecutorStorage<
+                    { rtic::export::executor::AsyncTaskExecutor::#executor_layout(&#entry).size() },
+                    { rtic::export::executor::AsyncTaskExecutor::#executor_layout(&#entry).align() },
+                >; #capacity] = [const { rtic::export::executor::ExecutorStorage::new() }; #capacity];
+            ));
+
+            storage_name
+        });
+
+        for i in 0..task.args.capacity as usize {
+            if let Some(storage_name) = &storage {
+                // Not on the stack, so not counted in `executors_size`
+                let in_storage_n_args = util::in_storage_n_args_ident(task.inputs.len());
+
+                executor_allocations.push(quote!(
+                    #exec_name[#i].set_in_main(rtic::export::executor::AsyncTaskExecutor::#in_storage_n_args(#entry, &#storage_name[#i]));
+                ));
+            } else {
+                executor_allocations.push(quote!(
+                    let executor = ::core::mem::ManuallyDrop::new(rtic::export::executor::AsyncTaskExecutor::#new_n_args(#entry));
+                    executors_size += ::core::mem::size_of_val(&executor);
+                    #exec_name[#i].set_in_main(&executor);
+                ));
+            }
+        }
     }
 
+    // With `executors_section` no executor is on the stack of `main`
+    let executors_size = if app.args.executors_section.is_some() {
+        quote!(let executors_size = 0;)
+    } else {
+        quote!(let mut executors_size = 0;)
+    };
+
     let main = util::suffixed("main");
     let init_name = &app.init.name;
 
@@ -50,14 +89,36 @@ pub fn codegen(app: &App, analysis: &Analysis) -> TokenStream2 {
         quote!(executors_size)
     };
 
-    let msp_check = bindings::check_stack_overflow_before_init(app, analysis);
+    // The stack of the other cores is not the one set up by the runtime
+    let msp_check = if app.args.core_id == 0 {
+        Selected::check_stack_overflow_before_init(app, analysis)
+    } else {
+        vec![]
+    };
+
+    // Only the first core enters through the runtime, the others are started by the application
+    let signature = if app.args.core_id == 0 {
+        quote!(
+            #[doc(hidden)]
+            #[no_mangle]
+            unsafe extern "C" fn #main() -> !
+        )
+    } else {
+        let doc = format!(
+            "Runs the application of core {}, to be called once on that core with interrupts disabled",
+            app.args.core_id
+        );
+
+        quote!(
+            #[doc = #doc]
+            pub unsafe extern "C" fn #main() -> !
+        )
+    };
 
     quote!(
         #(#extra_mods_stmts)*
 
-        #[doc(hidden)]
-        #[no_mangle]
-        unsafe extern "C" fn #main() -> ! {
+        #signature {
             #(#assertion_stmts)*
 
             #(#pre_init_stmts)*
@@ -68,7 +129,7 @@ pub fn codegen(app: &App, analysis: &Analysis) -> TokenStream2 {
             }
 
             // Generate allocations for async executors.
-            let mut executors_size = 0;
+            #executors_size
             #(#executor_allocations)*
 
             #(#msp_check)*
@@ -1,5 +1,9 @@
 use crate::syntax::{ast::App, Context};
-use crate::{analyze::Analysis, codegen::bindings::interrupt_mod, codegen::util};
+use crate::{
+    analyze::Analysis,
+    codegen::bindings::{Backend, Selected},
+    codegen::util,
+};
 use proc_macro2::TokenStream as TokenStream2;
 use quote::quote;
 
@@ -11,20 +15,22 @@ pub fn codegen(ctxt: Context, app: &App, analysis: &Analysis) -> TokenStream2 {
     let mut values = vec![];
     // Used to copy task cfgs to the whole module
     let mut task_cfgs = vec![];
+    let export = Selected::export_path();
 
     let name = ctxt.ident(app);
 
     match ctxt {
         Context::Init => {
             fields.push(quote!(
-                /// The space used to allocate async executors in bytes.
+                /// The space used to allocate async executors on the stack in bytes, `0` with
+                /// `executors_section`.
                 pub executors_size: usize
             ));
 
             if app.args.core {
                 fields.push(quote!(
                     /// Core peripherals
-                    pub core: rtic::export::Peripherals
+                    pub core: #export::Peripherals
                 ));
 
                 values.push(quote!(core: core));
@@ -101,7 +107,7 @@ pub fn codegen(ctxt: Context, app: &App, analysis: &Analysis) -> TokenStream2 {
 
     let core = if ctxt.is_init() {
         if app.args.core {
-            Some(quote!(core: rtic::export::Peripherals, executors_size: usize))
+            Some(quote!(core: #export::Peripherals, executors_size: usize))
         } else {
             Some(quote!(executors_size: usize))
         }
@@ -150,45 +156,140 @@ pub fn codegen(ctxt: Context, app: &App, analysis: &Analysis) -> TokenStream2 {
         task_cfgs.clone_from(cfgs);
 
         let pend_interrupt = if priority > 0 {
-            let int_mod = interrupt_mod(app);
             let interrupt = &analysis.interrupts.get(&priority).expect("UREACHABLE").0;
-            quote!(rtic::export::pend(#int_mod::#interrupt);)
+            Selected::pend_interrupt(app, interrupt)
         } else {
             quote!()
         };
 
         let internal_spawn_ident = util::internal_task_ident(name, "spawn");
+        let entry = util::task_entry_ident(app, name);
+        let output_ty = util::output_ty(&spawnee.output);
         let from_ptr_n_args = util::from_ptr_n_args_ident(spawnee.inputs.len());
         let (input_args, input_tupled, input_untupled, input_ty) =
             util::regroup_inputs(&spawnee.inputs);
 
-        // Spawn caller
-        items.push(quote!(
-            #(#cfgs)*
-            /// Spawns the task directly
-            #[allow(non_snake_case)]
-            #[doc(hidden)]
-            pub fn #internal_spawn_ident(#(#input_args,)*) -> ::core::result::Result<(), #input_ty> {
-                // SAFETY: If `try_allocate` succeeds one must call `spawn`, which we do.
-                unsafe {
-                    let exec = rtic::export::executor::AsyncTaskExecutor::#from_ptr_n_args(#name, &#exec_name);
-                    if exec.try_allocate() {
-                        exec.spawn(#name(unsafe { #name::Context::new() } #(,#input_untupled)*));
-                        #pend_interrupt
-
-                        Ok(())
-                    } else {
-                        Err(#input_tupled)
+        // Use the first free instance of the task, `on_spawn` consumes the handle
+        let try_spawn = |on_spawn: TokenStream2| {
+            quote!(
+                for exec in #exec_name.iter() {
+                    // SAFETY: If `try_allocate` succeeds one must call `spawn` and `handle`,
+                    // which we do.
+                    unsafe {
+                        let exec = rtic::export::executor::AsyncTaskExecutor::#from_ptr_n_args(#entry, exec);
+                        if exec.try_allocate() {
+                            exec.spawn(#entry(unsafe { #name::Context::new() } #(,#input_untupled)*));
+                            #pend_interrupt
+
+                            let handle = exec.handle(|| { #pend_interrupt });
+                            return Ok(#on_spawn);
+                        }
                     }
                 }
-            }
-        ));
+
+                Err(#input_tupled)
+            )
+        };
+
+        if spawnee.args.queue.is_some() {
+            // Queued requests have no instance yet, so there is no handle to return
+            let try_spawn_queued = try_spawn(quote!(::core::mem::drop(handle)));
+            let queue_name = util::internal_task_ident(name, "SPAWN_QUEUE");
+            let internal_try_spawn_ident = util::internal_task_ident(name, "try_spawn");
+            let internal_spawn_wait_ident = util::internal_task_ident(name, "spawn_wait");
+
+            items.push(quote!(
+                #(#cfgs)*
+                /// Spawns the task if an instance is free, used by the spawn queue
+                #[allow(non_snake_case)]
+                #[doc(hidden)]
+                pub fn #internal_try_spawn_ident(input: #input_ty) -> ::core::result::Result<(), #input_ty> {
+                    let #input_tupled = input;
+
+                    #try_spawn_queued
+                }
+
+                #(#cfgs)*
+                /// Spawns the task, or queues the request if all instances are busy
+                #[allow(non_snake_case)]
+                #[doc(hidden)]
+                pub fn #internal_spawn_ident(#(#input_args,)*) -> ::core::result::Result<(), #input_ty> {
+                    #queue_name.spawn_or_enqueue(#input_tupled, #internal_try_spawn_ident, || { #pend_interrupt })
+                }
+
+                #(#cfgs)*
+                /// Spawns the task, waiting for space in the spawn queue if it is full
+                #[allow(non_snake_case)]
+                #[doc(hidden)]
+                pub async fn #internal_spawn_wait_ident(#(#input_args,)*) {
+                    let mut input = Some(#input_tupled);
+
+                    #queue_name
+                        .wait_for(|| {
+                            match #queue_name.spawn_or_enqueue(input.take().unwrap(), #internal_try_spawn_ident, || { #pend_interrupt }) {
+                                Ok(()) => Some(()),
+                                Err(i) => {
+                                    input = Some(i);
+                                    None
+                                }
+                            }
+                        })
+                        .await
+                }
+            ));
+
+            module_items.push(quote!(
+                #(#cfgs)*
+                #[doc(inline)]
+                pub use super::#internal_spawn_wait_ident as spawn_wait;
+            ));
+        } else {
+            let try_spawn_handle = try_spawn(quote!(handle));
+
+            // Spawn caller
+            items.push(quote!(
+                #(#cfgs)*
+                /// Spawns the task directly
+                #[allow(non_snake_case)]
+                #[doc(hidden)]
+                pub fn #internal_spawn_ident(#(#input_args,)*) -> ::core::result::Result<rtic::TaskHandle<#output_ty>, #input_ty> {
+                    #try_spawn_handle
+                }
+            ));
+        }
 
         module_items.push(quote!(
             #(#cfgs)*
             #[doc(inline)]
             pub use super::#internal_spawn_ident as spawn;
         ));
+
+        if spawnee.args.mailbox.is_some() {
+            let mailbox_name = util::internal_task_ident(name, "MAILBOX");
+            let internal_cross_core_spawn_ident =
+                util::internal_task_ident(name, "cross_core_spawn");
+            // EXPECT: checked in `preprocess`
+            let doorbell = app.args.doorbell.as_ref().expect("UNREACHABLE");
+
+            items.push(quote!(
+                #(#cfgs)*
+                /// Spawns the task from another core, through its mailbox
+                #[allow(non_snake_case)]
+                #[doc(hidden)]
+                pub fn #internal_cross_core_spawn_ident(#(#input_args,)*) -> ::core::result::Result<(), #input_ty> {
+                    #mailbox_name.push(#input_tupled)?;
+                    <#doorbell as rtic::Doorbell>::ring();
+
+                    Ok(())
+                }
+            ));
+
+            module_items.push(quote!(
+                #(#cfgs)*
+                #[doc(inline)]
+                pub use super::#internal_cross_core_spawn_ident as cross_core_spawn;
+            ));
+        }
     }
 
     if items.is_empty() {
@@ -1,4 +1,11 @@
-use crate::{analyze::Analysis, codegen::util, syntax::ast::App};
+use crate::{
+    analyze::Analysis,
+    codegen::{
+        bindings::{Backend, Selected},
+        util,
+    },
+    syntax::ast::App,
+};
 use proc_macro2::TokenStream as TokenStream2;
 use quote::quote;
 
@@ -9,9 +16,9 @@ pub fn codegen(app: &App, analysis: &Analysis) -> Vec<TokenStream2> {
     // Initialize shared resources
     for (name, res) in &app.shared_resources {
         let mangled_name = util::static_shared_resource_ident(name);
-        // If it's live
+        // If it's live, or used by other cores
         let cfgs = res.cfgs.clone();
-        if analysis.shared_resources.get(name).is_some() {
+        if analysis.shared_resources.get(name).is_some() || res.properties.cross_core {
             stmts.push(quote!(
                 // We include the cfgs
                 #(#cfgs)*
@@ -23,6 +30,18 @@ pub fn codegen(app: &App, analysis: &Analysis) -> Vec<TokenStream2> {
         }
     }
 
+    // Let the other cores access the cross-core resources
+    if app
+        .shared_resources
+        .values()
+        .any(|res| res.properties.cross_core)
+    {
+        let ready = util::cross_core_ready_ident();
+        stmts.push(quote!(
+            #ready.store(true, rtic::export::atomic::Ordering::Release);
+        ));
+    }
+
     // Initialize local resources
     for (name, res) in &app.local_resources {
         let mangled_name = util::static_local_resource_ident(name);
@@ -41,7 +60,8 @@ pub fn codegen(app: &App, analysis: &Analysis) -> Vec<TokenStream2> {
     }
 
     // Enable the interrupts -- this completes the `init`-ialization phase
-    stmts.push(quote!(rtic::export::interrupt::enable();));
+    let export = Selected::export_path();
+    stmts.push(quote!(#export::interrupt::enable();));
 
     stmts
 }
@@ -1,4 +1,5 @@
-use super::bindings::{pre_init_checks, pre_init_enable_interrupts};
+use super::bindings::{Backend, Selected};
+use super::{stack, timing};
 use crate::analyze::Analysis;
 use crate::syntax::ast::App;
 use proc_macro2::TokenStream as TokenStream2;
@@ -7,20 +8,29 @@ use quote::quote;
 /// Generates code that runs before `#[init]`
 pub fn codegen(app: &App, analysis: &Analysis) -> Vec<TokenStream2> {
     let mut stmts = vec![];
+    let export = Selected::export_path();
 
     // Disable interrupts -- `init` must run with interrupts disabled
-    stmts.push(quote!(rtic::export::interrupt::disable();));
+    stmts.push(quote!(#export::interrupt::disable();));
 
     if app.args.core {
         stmts.push(quote!(
             // To set the variable in cortex_m so the peripherals cannot be taken multiple times
-            let mut core: rtic::export::Peripherals = rtic::export::Peripherals::steal().into();
+            let mut core: #export::Peripherals = #export::Peripherals::steal().into();
         ));
     }
 
-    stmts.append(&mut pre_init_checks(app, analysis));
+    stmts.append(&mut Selected::pre_init_checks(app, analysis));
 
-    stmts.append(&mut pre_init_enable_interrupts(app, analysis));
+    if stack::enabled(app) {
+        stmts.push(quote!(rtic::export::stack::paint();));
+    }
+
+    if timing::enabled() {
+        stmts.push(quote!(rtic::export::timing::enable();));
+    }
+
+    stmts.append(&mut Selected::pre_init_enable_interrupts(app, analysis));
 
     stmts
 }
@@ -3,12 +3,13 @@ use crate::{analyze::Analysis, codegen::util};
 use proc_macro2::TokenStream as TokenStream2;
 use quote::quote;
 
-use super::bindings::impl_mutex;
+use super::bindings::{Backend, Selected};
 
 /// Generates `static` variables and shared resource proxies
 pub fn codegen(app: &App, analysis: &Analysis) -> TokenStream2 {
     let mut mod_app = vec![];
     let mut mod_resources = vec![];
+    let mut mod_cross_core = vec![];
 
     for (name, res) in &app.shared_resources {
         let cfgs = &res.cfgs;
@@ -44,6 +45,36 @@ pub fn codegen(app: &App, analysis: &Analysis) -> TokenStream2 {
         // For future use
         // let doc = format!(" RTIC internal: {}:{}", file!(), line!());
 
+        if res.properties.cross_core {
+            let ready = util::cross_core_ready_ident();
+            let accessor = util::internal_task_ident(name, "cross_core");
+            let docs = &res.docs;
+
+            mod_app.push(quote!(
+                #(#cfgs)*
+                /// Returns the resource once `init` of this application has returned
+                #[allow(non_snake_case)]
+                #[doc(hidden)]
+                #[inline(always)]
+                pub fn #accessor() -> ::core::option::Option<&'static #ty> {
+                    if #ready.load(rtic::export::atomic::Ordering::Acquire) {
+                        // SAFETY: The resource is initialized and only accessed by shared
+                        // reference, which `CrossCore` allows from any core.
+                        ::core::option::Option::Some(unsafe { &*(*#mangled_name.get()).as_ptr() })
+                    } else {
+                        ::core::option::Option::None
+                    }
+                }
+            ));
+
+            mod_cross_core.push(quote!(
+                #(#cfgs)*
+                #(#docs)*
+                #[doc(inline)]
+                pub use super::#accessor as #name;
+            ));
+        }
+
         let shared_name = util::need_to_lock_ident(name);
 
         if !res.properties.lock_free {
@@ -79,7 +110,7 @@ pub fn codegen(app: &App, analysis: &Analysis) -> TokenStream2 {
             // For future use
             // let doc = format!(" RTIC internal ({} resource): {}:{}", doc, file!(), line!());
 
-            mod_app.push(impl_mutex(
+            mod_app.push(Selected::impl_mutex(
                 app,
                 analysis,
                 cfgs,
@@ -100,9 +131,28 @@ pub fn codegen(app: &App, analysis: &Analysis) -> TokenStream2 {
         })
     };
 
+    let mod_cross_core = if mod_cross_core.is_empty() {
+        quote!()
+    } else {
+        let ready = util::cross_core_ready_ident();
+
+        quote!(
+            #[doc(hidden)]
+            static #ready: rtic::export::atomic::AtomicBool =
+                rtic::export::atomic::AtomicBool::new(false);
+
+            /// Shared resources the other cores can access, `None` until `init` has returned
+            pub mod cross_core {
+                #(#mod_cross_core)*
+            }
+        )
+    };
+
     quote!(
         #(#mod_app)*
 
         #mod_resources
+
+        #mod_cross_core
     )
 }
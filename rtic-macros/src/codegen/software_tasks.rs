@@ -1,10 +1,14 @@
-use crate::syntax::{ast::App, Context};
+use crate::syntax::{
+    ast::{App, RestartPolicy},
+    Context,
+};
 use crate::{
     analyze::Analysis,
-    codegen::{local_resources_struct, module, shared_resources_struct},
+    codegen::{local_resources_struct, module, shared_resources_struct, util},
 };
 use proc_macro2::TokenStream as TokenStream2;
-use quote::quote;
+use quote::{quote, quote_spanned};
+use syn::spanned::Spanned;
 
 pub fn codegen(app: &App, analysis: &Analysis) -> TokenStream2 {
     let mut mod_app = vec![];
@@ -37,12 +41,13 @@ pub fn codegen(app: &App, analysis: &Analysis) -> TokenStream2 {
             let cfgs = &task.cfgs;
             let stmts = &task.stmts;
             let inputs = &task.inputs;
+            let output = &task.output;
 
             user_tasks.push(quote!(
                 #(#attrs)*
                 #(#cfgs)*
                 #[allow(non_snake_case)]
-                async fn #name<'a>(#context: #name::Context<'a> #(,#inputs)*) {
+                async fn #name<'a>(#context: #name::Context<'a> #(,#inputs)*) #output {
                     use rtic::Mutex as _;
                     use rtic::mutex::prelude::*;
 
@@ -51,6 +56,93 @@ pub fn codegen(app: &App, analysis: &Analysis) -> TokenStream2 {
             ));
         }
 
+        // Errors are reported and the task is restarted by a supervisor around the task
+        if util::is_supervised(app, name) {
+            let cfgs = &task.cfgs;
+            let output = &task.output;
+            let entry = util::task_entry_ident(app, name);
+            let name_str = name.to_string();
+            let (input_args, _, input_untupled, _) = util::regroup_inputs(&task.inputs);
+
+            let on_error = app
+                .args
+                .on_task_error
+                .as_ref()
+                .map(|on_task_error| quote!(#on_task_error(#name_str, error);));
+            let error = if on_error.is_some() {
+                quote!(error)
+            } else {
+                quote!(_)
+            };
+
+            let supervisor = if task.args.restart == RestartPolicy::Never {
+                quote!(
+                    let result = #name(context #(,#input_untupled)*).await;
+                    if let Err(#error) = &result {
+                        #on_error
+                    }
+
+                    result
+                )
+            } else {
+                // A task failing without awaiting would otherwise keep its dispatcher busy
+                let (restarts, restart) = match task.args.restart {
+                    RestartPolicy::Times(n) => (
+                        quote!(let mut restarts: u32 = 0;),
+                        quote!(
+                            if restarts < #n {
+                                restarts += 1;
+                                rtic::export::executor::yield_now().await;
+                                continue;
+                            }
+                        ),
+                    ),
+                    _ => (
+                        quote!(),
+                        quote!(
+                            rtic::export::executor::yield_now().await;
+                            continue;
+                        ),
+                    ),
+                };
+
+                // The arguments are cloned for each run, a missing `Clone` is reported at the
+                // type of the argument
+                let cloned_inputs = task.inputs.iter().zip(&input_untupled).map(|(input, arg)| {
+                    let ty = &input.ty;
+                    quote_spanned!(ty.span()=> <#ty as ::core::clone::Clone>::clone(&#arg))
+                });
+
+                quote!(
+                    let mut context = Some(context);
+                    #restarts
+
+                    loop {
+                        // SAFETY: The context of the previous run is gone as the run completed.
+                        let context = context
+                            .take()
+                            .unwrap_or_else(|| unsafe { #name::Context::new() });
+
+                        let result = #name(context #(,#cloned_inputs)*).await;
+                        if let Err(#error) = &result {
+                            #on_error
+                            #restart
+                        }
+
+                        return result;
+                    }
+                )
+            };
+
+            root.push(quote!(
+                #(#cfgs)*
+                #[allow(non_snake_case, clippy::redundant_clone)]
+                async fn #entry<'a>(context: #name::Context<'a> #(,#input_args)*) #output {
+                    #supervisor
+                }
+            ));
+        }
+
         root.push(module::codegen(Context::SoftwareTask(name), app, analysis));
     }
 
@@ -0,0 +1,69 @@
+use crate::analyze::Analysis;
+use crate::syntax::ast::App;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+
+/// Whether the stack usage is measured, with the `stack-usage` feature
+///
+/// The stack of the other cores is not the one set up by the runtime, so only the application
+/// of the first core is measured.
+pub fn enabled(app: &App) -> bool {
+    cfg!(feature = "stack-usage") && app.args.core_id == 0
+}
+
+/// The highest priority level of the interrupt handlers
+fn max_level(app: &App, analysis: &Analysis) -> u8 {
+    app.hardware_tasks
+        .values()
+        .map(|task| task.args.priority)
+        .chain(analysis.channels.keys().copied())
+        .max()
+        .unwrap_or(0)
+}
+
+/// Generates the `stack` module with the measurements of the priority levels
+pub fn codegen(app: &App, analysis: &Analysis) -> TokenStream2 {
+    if !enabled(app) {
+        return quote!();
+    }
+
+    let levels =
+        (1..=max_level(app, analysis)).map(|level| quote!(rtic::stack::Level::new(#level)));
+    let n_levels = max_level(app, analysis) as usize;
+
+    quote!(
+        /// Measured stack usage, see `rtic::stack`
+        pub mod stack {
+            /// The deepest run of the tasks of each priority level, starting at priority 1
+            pub static LEVELS: [rtic::stack::Level; #n_levels] = [#(#levels),*];
+
+            /// The worst case stack usage in bytes, the thread mode usage plus the deepest run
+            /// of each priority level, as each level can preempt the ones below it
+            ///
+            /// Only meaningful when called from thread mode, e.g. from `idle`.
+            pub fn worst_case() -> usize {
+                rtic::stack::thread_mode_usage()
+                    + LEVELS.iter().map(|level| level.max_bytes()).sum::<usize>()
+            }
+        }
+    )
+}
+
+/// Statements at the start of an interrupt handler
+pub fn enter(app: &App) -> TokenStream2 {
+    if !enabled(app) {
+        return quote!();
+    }
+
+    quote!(let __rtic_internal_stack_entry = rtic::export::stack::enter();)
+}
+
+/// Statements at the end of an interrupt handler of the priority `level`
+pub fn exit(app: &App, level: u8) -> TokenStream2 {
+    if !enabled(app) {
+        return quote!();
+    }
+
+    let index = level as usize - 1;
+    quote!(rtic::export::stack::exit(&stack::LEVELS[#index], __rtic_internal_stack_entry);)
+}
@@ -0,0 +1,88 @@
+use crate::codegen::util;
+use crate::syntax::ast::App;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::Ident;
+
+/// Whether the tasks and locks are measured, with the `measure-timing` feature
+pub fn enabled() -> bool {
+    cfg!(feature = "measure-timing")
+}
+
+/// Generates the measurements of the tasks and locked resources, and the `timing` module
+/// listing them
+pub fn codegen(app: &App) -> TokenStream2 {
+    if !enabled() {
+        return quote!();
+    }
+
+    let mut items = vec![];
+    let mut tasks = vec![];
+    let mut resources = vec![];
+
+    let measurement = |ident: &Ident, name: &Ident| {
+        let name = name.to_string();
+
+        quote!(
+            #[allow(non_upper_case_globals)]
+            #[doc(hidden)]
+            static #ident: rtic::timing::Measurement = rtic::timing::Measurement::new(#name);
+        )
+    };
+
+    // Not cfg-gated, so the tables do not depend on the cfgs of the tasks and resources
+    for name in app.hardware_tasks.keys().chain(app.software_tasks.keys()) {
+        let ident = util::task_timing_ident(name);
+
+        items.push(measurement(&ident, name));
+        tasks.push(quote!(&super::#ident));
+    }
+
+    for (name, res) in &app.shared_resources {
+        if res.properties.lock_free {
+            continue;
+        }
+
+        let ident = util::lock_timing_ident(&util::need_to_lock_ident(name));
+
+        items.push(measurement(&ident, name));
+        resources.push(quote!(&super::#ident));
+    }
+
+    let n_tasks = tasks.len();
+    let n_resources = resources.len();
+
+    quote!(
+        #(#items)*
+
+        /// Measured execution and blocking times, see `rtic::timing`
+        pub mod timing {
+            /// The longest run of each hardware task and poll of each software task
+            pub static TASKS: [&rtic::timing::Measurement; #n_tasks] = [#(#tasks),*];
+
+            /// The longest lock of each shared resource
+            pub static RESOURCES: [&rtic::timing::Measurement; #n_resources] = [#(#resources),*];
+        }
+    )
+}
+
+/// Measures `run`, a run of the task `name` or a poll of one of its instances
+pub fn measure_task(name: &Ident, run: TokenStream2) -> TokenStream2 {
+    if !enabled() {
+        return run;
+    }
+
+    let ident = util::task_timing_ident(name);
+    quote!(rtic::export::timing::measure_task(&#ident, || { #run }))
+}
+
+/// Measures `f`, the closure of the `lock` of the resource proxy `name`
+#[cfg(feature = "cortex-m-basepri")]
+pub fn measure_lock(name: &Ident, f: TokenStream2) -> TokenStream2 {
+    if !enabled() {
+        return f;
+    }
+
+    let ident = util::lock_timing_ident(name);
+    quote!(|r| rtic::export::timing::measure_lock(&#ident, || (#f)(r)))
+}
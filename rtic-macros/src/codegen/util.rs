@@ -1,8 +1,11 @@
-use crate::syntax::{ast::App, Context};
+use crate::syntax::{
+    ast::{App, RestartPolicy},
+    Context,
+};
 use core::sync::atomic::{AtomicUsize, Ordering};
 use proc_macro2::{Span, TokenStream as TokenStream2};
 use quote::quote;
-use syn::{Ident, PatType};
+use syn::{Ident, PatType, ReturnType};
 
 const RTIC_INTERNAL: &str = "__rtic_internal";
 
@@ -16,6 +19,31 @@ pub fn internal_task_ident(task: &Ident, ident_name: &str) -> Ident {
     mark_internal_name(&format!("{task}_{ident_name}"))
 }
 
+/// Whether the errors of a software task are handled by a generated supervisor
+pub fn is_supervised(app: &App, task: &Ident) -> bool {
+    let task = &app.software_tasks[task];
+
+    task.is_fallible
+        && (app.args.on_task_error.is_some() || task.args.restart != RestartPolicy::Never)
+}
+
+/// The function run by the executors of a software task, the task or its supervisor
+pub fn task_entry_ident(app: &App, task: &Ident) -> Ident {
+    if is_supervised(app, task) {
+        internal_task_ident(task, "supervisor")
+    } else {
+        task.clone()
+    }
+}
+
+/// The output type of a task
+pub fn output_ty(output: &ReturnType) -> TokenStream2 {
+    match output {
+        ReturnType::Default => quote!(()),
+        ReturnType::Type(_, ty) => quote!(#ty),
+    }
+}
+
 fn link_section_index() -> usize {
     static INDEX: AtomicUsize = AtomicUsize::new(0);
 
@@ -140,6 +168,11 @@ pub fn static_shared_resource_ident(name: &Ident) -> Ident {
     mark_internal_name(&format!("shared_resource_{name}"))
 }
 
+/// Set once the cross-core resources are initialized
+pub fn cross_core_ready_ident() -> Ident {
+    mark_internal_name("CROSS_CORE_READY")
+}
+
 pub fn static_local_resource_ident(name: &Ident) -> Ident {
     mark_internal_name(&format!("local_resource_{name}"))
 }
@@ -152,6 +185,16 @@ pub fn need_to_lock_ident(name: &Ident) -> Ident {
     Ident::new(&format!("{name}_that_needs_to_be_locked"), name.span())
 }
 
+/// Measurement of a task, with the `measure-timing` feature
+pub fn task_timing_ident(task: &Ident) -> Ident {
+    internal_task_ident(task, "TIMING")
+}
+
+/// Measurement of the locks of the resource proxy `name`, with the `measure-timing` feature
+pub fn lock_timing_ident(name: &Ident) -> Ident {
+    mark_internal_name(&format!("{name}_TIMING"))
+}
+
 pub fn zero_prio_dispatcher_ident() -> Ident {
     Ident::new("__rtic_internal_async_0_prio_dispatcher", Span::call_site())
 }
@@ -171,3 +214,18 @@ pub fn from_ptr_n_args_ident(n: usize) -> Ident {
 pub fn new_n_args_ident(n: usize) -> Ident {
     Ident::new(&format!("new_{}_args", n + 1), Span::call_site())
 }
+
+pub fn in_storage_n_args_ident(n: usize) -> Ident {
+    Ident::new(&format!("in_storage_{}_args", n + 1), Span::call_site())
+}
+
+pub fn future_size_n_args_ident(n: usize) -> Ident {
+    Ident::new(&format!("future_size_{}_args", n + 1), Span::call_site())
+}
+
+pub fn executor_layout_n_args_ident(n: usize) -> Ident {
+    Ident::new(
+        &format!("executor_layout_{}_args", n + 1),
+        Span::call_site(),
+    )
+}
@@ -0,0 +1,179 @@
+//! Checks the output of the selected backend against the contract of [`Backend`]
+//!
+//! Used by the `conformance` tests, a new backend should pass them before it is used to build
+//! applications.
+
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote};
+use syn::{parse, parse::Parser, Attribute, Block, File, Ident, Item, Path};
+
+use crate::{
+    analyze::Analysis,
+    codegen::{
+        self,
+        bindings::{Backend, Selected},
+    },
+    syntax::{
+        analyze::{Analysis as SyntaxAnalysis, Ownership},
+        ast::App,
+    },
+};
+
+/// Interrupts of the selected backend's test device that `dispatchers = auto` picks from
+pub fn free_interrupts() -> Vec<Ident> {
+    let names: &[&str] = if cfg!(feature = "riscv-esp32c3") {
+        &["FROM_CPU_INTR0", "FROM_CPU_INTR1", "FROM_CPU_INTR2"]
+    } else {
+        &["SSI0", "QEI0", "UART1"]
+    };
+
+    names
+        .iter()
+        .map(|name| Ident::new(name, Span::call_site()))
+        .collect()
+}
+
+/// Runs the code generation of the selected backend on `app` and validates every hook
+pub fn app(app: &App, analysis: &Analysis) -> parse::Result<()> {
+    let syntax_analysis: &SyntaxAnalysis = analysis;
+
+    path("export_path", Selected::export_path())?;
+    path("interrupt_mod", Selected::interrupt_mod(app))?;
+
+    stmts(
+        "extra_assertions",
+        Selected::extra_assertions(app, syntax_analysis),
+    )?;
+    stmts(
+        "pre_init_checks",
+        Selected::pre_init_checks(app, syntax_analysis),
+    )?;
+    stmts(
+        "pre_init_enable_interrupts",
+        Selected::pre_init_enable_interrupts(app, analysis),
+    )?;
+    stmts("interrupt_entry", Selected::interrupt_entry(app, analysis))?;
+    stmts("interrupt_exit", Selected::interrupt_exit(app, analysis))?;
+    stmts(
+        "check_stack_overflow_before_init",
+        Selected::check_stack_overflow_before_init(app, analysis),
+    )?;
+
+    items(
+        "async_prio_limit",
+        Selected::async_prio_limit(app, analysis),
+    )?;
+    items("extra_modules", Selected::extra_modules(app, analysis))?;
+
+    for (dispatcher, _) in analysis.interrupts.values() {
+        stmts(
+            "pend_interrupt",
+            vec![Selected::pend_interrupt(app, dispatcher)],
+        )?;
+        stmts(
+            "async_entry",
+            Selected::async_entry(app, analysis, dispatcher.clone()),
+        )?;
+        attrs(
+            "handler_config",
+            Selected::handler_config(app, analysis, dispatcher.clone()),
+        )?;
+    }
+
+    for task in app.hardware_tasks.values() {
+        attrs(
+            "handler_config",
+            Selected::handler_config(app, analysis, task.args.binds.clone()),
+        )?;
+    }
+
+    for (name, res) in &app.shared_resources {
+        let ty = &res.ty;
+        let proxy = format_ident!("{}_that_needs_to_be_locked", name);
+        let ceiling = match analysis.ownerships.get(name) {
+            Some(Ownership::Owned { priority } | Ownership::CoOwned { priority }) => *priority,
+            Some(Ownership::Contended { ceiling }) => *ceiling,
+            None => 0,
+        };
+        let mutex = Selected::impl_mutex(
+            app,
+            analysis,
+            &res.cfgs,
+            true,
+            &proxy,
+            &quote!(#ty),
+            ceiling,
+            &quote!(::core::ptr::null_mut()),
+        );
+
+        let file = parse_as::<File>("impl_mutex", mutex)?;
+        let implements_mutex = file.items.iter().any(|item| {
+            matches!(item, Item::Impl(imp) if imp
+                .trait_
+                .as_ref()
+                .and_then(|(_, path, _)| path.segments.last())
+                .is_some_and(|segment| segment.ident == "Mutex"))
+        });
+
+        if !implements_mutex {
+            return Err(violation(
+                "impl_mutex",
+                "it must implement `rtic::Mutex` for the resource proxy",
+            ));
+        }
+    }
+
+    syn::parse2::<File>(codegen::app(app, analysis))
+        .map(|_| ())
+        .map_err(|e| {
+            parse::Error::new(
+                Span::call_site(),
+                format!(
+                    "backend contract violated: the generated application does not parse ({e})"
+                ),
+            )
+        })
+}
+
+fn path(hook: &str, tokens: TokenStream2) -> parse::Result<()> {
+    parse_as::<Path>(hook, tokens).map(|_| ())
+}
+
+fn stmts(hook: &str, tokens: Vec<TokenStream2>) -> parse::Result<()> {
+    Block::parse_within
+        .parse2(quote!(#(#tokens)*))
+        .map(|_| ())
+        .map_err(|e| violation(hook, &format!("it must return statements ({e})")))
+}
+
+fn items(hook: &str, tokens: Vec<TokenStream2>) -> parse::Result<()> {
+    parse_as::<File>(hook, quote!(#(#tokens)*)).map(|_| ())
+}
+
+fn attrs(hook: &str, tokens: Vec<TokenStream2>) -> parse::Result<()> {
+    Attribute::parse_outer
+        .parse2(quote!(#(#tokens)*))
+        .map(|_| ())
+        .map_err(|e| violation(hook, &format!("it must return outer attributes ({e})")))
+}
+
+fn parse_as<T: parse::Parse>(hook: &str, tokens: TokenStream2) -> parse::Result<T> {
+    syn::parse2(tokens).map_err(|e| {
+        violation(
+            hook,
+            &format!("it must return a `{}` ({e})", short_type_name::<T>()),
+        )
+    })
+}
+
+fn short_type_name<T>() -> &'static str {
+    let name = std::any::type_name::<T>();
+    name.rsplit("::").next().unwrap_or(name)
+}
+
+fn violation(hook: &str, message: &str) -> parse::Error {
+    parse::Error::new(
+        Span::call_site(),
+        format!("backend contract violated by `{hook}`: {message}"),
+    )
+}
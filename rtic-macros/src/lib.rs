@@ -16,12 +16,15 @@ macro_rules! with_backend {
             feature = "test-template",
             feature = "riscv-esp32c3",
             feature = "riscv-slic",
+            feature = "riscv-clic",
+            feature = "riscv-plic",
+            feature = "external-backend",
         ))]
         $($tokens)*
     };
 }
 
-with_backend! { mod: [analyze, check, codegen, preprocess, syntax] }
+with_backend! { mod: [analyze, check, codegen, conformance, preprocess, syntax, task_module] }
 with_backend! { use std::{fs, env, path::Path}; }
 with_backend! { use proc_macro::TokenStream; }
 
@@ -30,10 +33,37 @@ with_backend! {
     #[doc(hidden)]
     #[proc_macro_attribute]
     pub fn mock_app(args: TokenStream, input: TokenStream) -> TokenStream {
-        if let Err(e) = syntax::parse(args, input) {
-            e.to_compile_error().into()
-        } else {
-            "fn main() {}".parse().unwrap()
+        match syntax::parse(args, input)
+            .and_then(|(app, analysis)| analyze::app(analysis, &app).map(|_| ()))
+        {
+            Err(e) => e.to_compile_error().into(),
+            Ok(()) => "fn main() {}".parse().unwrap(),
+        }
+    }
+}
+
+with_backend! {
+    // Used to check a backend against the `Backend` contract in testing
+    #[doc(hidden)]
+    #[proc_macro_attribute]
+    pub fn conformance_app(args: TokenStream, input: TokenStream) -> TokenStream {
+        let (mut app, analysis) = match syntax::parse(args, input) {
+            Err(e) => return e.to_compile_error().into(),
+            Ok(x) => x,
+        };
+
+        // The apps are shared by the backends, which have different interrupts
+        if app.args.auto_dispatchers.is_some() && app.args.free_interrupts.is_empty() {
+            app.args.free_interrupts = conformance::free_interrupts();
+        }
+
+        match preprocess::app(&mut app, &analysis)
+            .and_then(|_| check::app(&app, &analysis))
+            .and_then(|_| analyze::app(analysis, &app))
+            .and_then(|analysis| conformance::app(&app, &analysis))
+        {
+            Err(e) => e.to_compile_error().into(),
+            Ok(()) => "fn main() {}".parse().unwrap(),
         }
     }
 }
@@ -48,6 +78,13 @@ with_backend! {
     /// Should never panic, cargo feeds a path which is later converted to a string
     #[proc_macro_attribute]
     pub fn app(_args: TokenStream, _input: TokenStream) -> TokenStream {
+        // Include the task modules first, each one invokes the app again
+        match task_module::include(_args.clone().into(), _input.clone().into()) {
+            Err(e) => return e.to_compile_error().into(),
+            Ok(Some(ts)) => return ts.into(),
+            Ok(None) => {}
+        }
+
         let (mut app, analysis) = match syntax::parse(_args, _input) {
             Err(e) => return e.to_compile_error().into(),
             Ok(x) => x,
@@ -64,7 +101,10 @@ with_backend! {
             return e.to_compile_error().into();
         }
 
-        let analysis = analyze::app(analysis, &app);
+        let analysis = match analyze::app(analysis, &app) {
+            Err(e) => return e.to_compile_error().into(),
+            Ok(x) => x,
+        };
 
         let ts = codegen::app(&app, &analysis);
 
@@ -113,11 +153,28 @@ with_backend! {
     }
 }
 
+with_backend! {
+    /// Attribute used to declare a module of tasks and resources which is included into the
+    /// RTIC application with `#[app(task_modules = [..])]`
+    ///
+    /// For user documentation see the [RTIC book](https://rtic.rs)
+    #[proc_macro_attribute]
+    pub fn task_module(args: TokenStream, input: TokenStream) -> TokenStream {
+        match task_module::task_module(args.into(), input.into()) {
+            Err(e) => e.to_compile_error().into(),
+            Ok(ts) => ts.into(),
+        }
+    }
+}
+
 #[cfg(not(any(
     feature = "cortex-m-source-masking",
     feature = "cortex-m-basepri",
     feature = "test-template",
     feature = "riscv-esp32c3",
     feature = "riscv-slic",
+    feature = "riscv-clic",
+    feature = "riscv-plic",
+    feature = "external-backend",
 )))]
 compile_error!("Cannot compile. No backend feature selected.");
@@ -1,7 +1,245 @@
-use crate::codegen::bindings::pre_init_preprocessing;
-use crate::syntax::{analyze::Analysis, ast::App};
-use syn::parse;
+use crate::codegen::{
+    bindings::{Backend, Selected},
+    util,
+};
+use crate::syntax::{
+    analyze::Analysis,
+    ast::{App, Dispatcher, HardwareTask, HardwareTaskArgs, LocalResources, SharedResources},
+};
+use proc_macro2::Span;
+use quote::quote;
+use std::{collections::BTreeSet, fs, path::Path};
+use syn::{parse, parse::Parser, Block, Ident};
 
 pub fn app(app: &mut App, analysis: &Analysis) -> parse::Result<()> {
-    pre_init_preprocessing(app, analysis)
+    Selected::pre_init_preprocessing(app, analysis)?;
+    // Before picking dispatchers, which must not take the inter-processor interrupt
+    ipi_task(app)?;
+    auto_dispatchers(app)
+}
+
+/// Name of the hardware task bound to `#[app(ipi = ..)]`
+pub fn ipi_task_ident() -> Ident {
+    util::mark_internal_name("ipi")
+}
+
+// Bind a hardware task to the inter-processor interrupt, which spawns the tasks requested through
+// the mailboxes by the other cores
+fn ipi_task(app: &mut App) -> parse::Result<()> {
+    let mailbox_tasks: Vec<_> = app
+        .software_tasks
+        .iter()
+        .filter(|(_, task)| task.args.mailbox.is_some())
+        .collect();
+
+    let (ipi, doorbell) = match (&app.args.ipi, &app.args.doorbell) {
+        (Some(ipi), Some(doorbell)) => (ipi.clone(), doorbell.clone()),
+        (Some(ipi), None) => {
+            return Err(parse::Error::new(
+                ipi.span(),
+                "`ipi` needs a `doorbell` to raise and clear the interrupt",
+            ));
+        }
+        (None, Some(doorbell)) => {
+            return Err(parse::Error::new(
+                doorbell.segments[0].ident.span(),
+                "`doorbell` needs the `ipi` interrupt it raises",
+            ));
+        }
+        (None, None) => {
+            if let Some((name, _)) = mailbox_tasks.first() {
+                return Err(parse::Error::new(
+                    name.span(),
+                    "tasks with a `mailbox` need `#[app(ipi = .., doorbell = ..)]` to be spawned by other cores",
+                ));
+            }
+
+            return Ok(());
+        }
+    };
+
+    if app.args.dispatchers.contains_key(&ipi) {
+        return Err(parse::Error::new(
+            ipi.span(),
+            "the inter-processor interrupt can not be a dispatcher",
+        ));
+    }
+
+    if app
+        .hardware_tasks
+        .values()
+        .any(|task| task.args.binds == ipi)
+    {
+        return Err(parse::Error::new(
+            ipi.span(),
+            "the inter-processor interrupt can not be bound to a task",
+        ));
+    }
+
+    // Spawn the requests, a request is left in its mailbox when all instances of the task are
+    // busy and delivered when the dispatcher runs the task again
+    let deliveries = mailbox_tasks.iter().map(|(name, task)| {
+        let cfgs = &task.cfgs;
+        let mailbox = util::internal_task_ident(name, "MAILBOX");
+        let spawn = util::internal_task_ident(name, "spawn");
+        let (_, input_tupled, input_untupled, _) = util::regroup_inputs(&task.inputs);
+
+        quote!(
+            #(#cfgs)*
+            unsafe {
+                #mailbox.drain(|#input_tupled| #spawn(#(#input_untupled),*).map(|_| ()));
+            }
+        )
+    });
+
+    let stmts = Block::parse_within
+        .parse2(quote!(
+            <#doorbell as rtic::Doorbell>::acknowledge();
+
+            #(#deliveries)*
+        ))
+        .expect("UNREACHABLE");
+
+    let priority = mailbox_tasks
+        .iter()
+        .map(|(_, task)| task.args.priority)
+        .max()
+        .unwrap_or(0)
+        .max(1);
+
+    app.hardware_tasks.insert(
+        ipi_task_ident(),
+        HardwareTask {
+            args: HardwareTaskArgs {
+                binds: ipi,
+                priority,
+                local_resources: LocalResources::new(),
+                shared_resources: SharedResources::new(),
+            },
+            cfgs: vec![],
+            attrs: vec![],
+            context: Box::new(syn::parse_quote!(_)),
+            stmts,
+            is_extern: false,
+        },
+    );
+
+    Ok(())
+}
+
+// Pick a dispatcher for each priority level of the software tasks from the free interrupts
+fn auto_dispatchers(app: &mut App) -> parse::Result<()> {
+    let Some(span) = app.args.auto_dispatchers else {
+        return Ok(());
+    };
+
+    let need = app
+        .software_tasks
+        .values()
+        .map(|task| task.args.priority)
+        .filter(|prio| *prio > 0)
+        .collect::<BTreeSet<_>>()
+        .len()
+        .saturating_sub(app.args.dispatchers.len());
+
+    if need == 0 {
+        return Ok(());
+    }
+
+    let bound = app
+        .hardware_tasks
+        .values()
+        .map(|task| &task.args.binds)
+        .collect::<BTreeSet<_>>();
+
+    // The interrupts listed by the user override the ones of the device
+    let free_interrupts = if app.args.free_interrupts.is_empty() {
+        device_interrupts(span).ok_or_else(|| {
+            parse::Error::new(
+                span,
+                "`dispatchers = auto` could not find the `device.x` of the device crate, \
+                    list the free interrupts instead, e.g. `free_interrupts = [SSI0, QEI0]`",
+            )
+        })?
+    } else {
+        app.args.free_interrupts.clone()
+    };
+
+    let candidates = free_interrupts
+        .into_iter()
+        .filter(|interrupt| {
+            !bound.contains(interrupt) && !app.args.dispatchers.contains_key(interrupt)
+        })
+        .collect::<Vec<_>>();
+
+    if candidates.len() < need {
+        return Err(parse::Error::new(
+            span,
+            format!(
+                "not enough free interrupts to dispatch all software tasks \
+                    (need: {need}; available: {})",
+                candidates.len()
+            ),
+        ));
+    }
+
+    for interrupt in candidates.into_iter().take(need) {
+        app.args
+            .dispatchers
+            .insert(interrupt, Dispatcher { attrs: vec![] });
+    }
+
+    Ok(())
+}
+
+// The interrupts of the device, in the order of the vector table.
+//
+// A procedural macro can not inspect the `Interrupt` enum of the PAC. Instead, the interrupts are
+// read from the `device.x` linker script, with one `PROVIDE(<interrupt> = DefaultHandler);` per
+// interrupt, that the build script of the device crate puts on the linker search path. Cargo
+// passes that path to the compiler of the app as `-L`.
+fn device_interrupts(span: Span) -> Option<Vec<Ident>> {
+    let mut args = std::env::args();
+    let mut search_paths = vec![];
+
+    while let Some(arg) = args.next() {
+        let path = match arg.strip_prefix("-L") {
+            Some("") => args.next()?,
+            Some(path) => path.to_string(),
+            None => continue,
+        };
+
+        // `-L [KIND=]PATH`
+        let path = match path.split_once('=') {
+            Some(("native" | "crate" | "dependency" | "framework" | "all", path)) => path.into(),
+            _ => path,
+        };
+
+        search_paths.push(path);
+    }
+
+    let device_x = search_paths
+        .iter()
+        .find_map(|path| fs::read_to_string(Path::new(path).join("device.x")).ok())?;
+
+    let interrupts = device_x
+        .lines()
+        .filter_map(|line| {
+            let (name, handler) = line
+                .trim()
+                .strip_prefix("PROVIDE(")?
+                .strip_suffix(");")?
+                .split_once('=')?;
+
+            if handler.trim() != "DefaultHandler" {
+                return None;
+            }
+
+            let mut interrupt = syn::parse_str::<Ident>(name.trim()).ok()?;
+            interrupt.set_span(span);
+            Some(interrupt)
+        })
+        .collect();
+
+    Some(interrupts)
 }
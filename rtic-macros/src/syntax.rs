@@ -12,8 +12,9 @@ use crate::syntax::ast::App;
 mod accessors;
 pub mod analyze;
 pub mod ast;
-mod backend;
+pub mod backend;
 mod check;
+mod optimize;
 mod parse;
 
 /// An ordered map keyed by identifier
@@ -106,8 +107,9 @@ pub fn parse2(
     args: TokenStream2,
     input: TokenStream2,
 ) -> Result<(ast::App, analyze::Analysis), syn::parse::Error> {
-    let app = parse::app(args, input)?;
+    let mut app = parse::app(args, input)?;
     check::app(&app)?;
+    optimize::app(&mut app);
 
     match analyze::app(&app) {
         Err(e) => Err(e),
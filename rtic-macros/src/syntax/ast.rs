@@ -1,6 +1,11 @@
 //! Abstract Syntax Tree
 
-use syn::{Attribute, Expr, Ident, Item, ItemUse, Pat, PatType, Path, Stmt, Type};
+use proc_macro2::Span;
+use std::collections::BTreeMap;
+use syn::{
+    Attribute, Expr, GenericArgument, Ident, Item, ItemUse, LitStr, Pat, PatType, Path, ReturnType,
+    Stmt, Type,
+};
 
 use crate::syntax::{backend::BackendArgs, Map};
 
@@ -25,11 +30,17 @@ pub struct App {
 
     pub shared_resources_vis: syn::Visibility,
 
+    /// Generic parameters of the `#[shared]` struct, their defaults select the resource types
+    pub shared_resources_generics: syn::Generics,
+
     /// Task local resources defined in `#[local]`
     pub local_resources: Map<LocalResource>,
 
     pub local_resources_vis: syn::Visibility,
 
+    /// Generic parameters of the `#[local]` struct, their defaults select the resource types
+    pub local_resources_generics: syn::Generics,
+
     /// User imports
     pub user_imports: Vec<ItemUse>,
 
@@ -41,6 +52,10 @@ pub struct App {
 
     /// Async software tasks: `#[task]`
     pub software_tasks: Map<SoftwareTask>,
+
+    /// Declared task priorities mapped to the priorities used by the generated code, filled in
+    /// when `optimize_priorities` is enabled
+    pub priority_map: BTreeMap<u8, u8>,
 }
 
 /// Interrupts used to dispatch software tasks
@@ -69,9 +84,37 @@ pub struct AppArgs {
     /// Interrupts used to dispatch software tasks
     pub dispatchers: Dispatchers,
 
+    /// `dispatchers = auto`, the dispatchers are picked from the free interrupts of the device
+    pub auto_dispatchers: Option<Span>,
+
+    /// Interrupts that `dispatchers = auto` can pick from, in order of preference
+    pub free_interrupts: Vec<Ident>,
+
     /// Backend-specific arguments
     #[allow(dead_code)]
     pub backend: Option<BackendArgs>,
+
+    /// Function called with the task name and the error when a software task returns `Err`
+    pub on_task_error: Option<Path>,
+
+    /// Compress the task priorities to the minimum number of levels
+    pub optimize_priorities: bool,
+
+    /// Warn about unused, single task and read only shared resources
+    pub resource_lints: bool,
+
+    /// The core running this application, `main` is only exported for core 0
+    pub core_id: u8,
+
+    /// Inter-processor interrupt delivering the spawns from the other cores
+    pub ipi: Option<Ident>,
+
+    /// Implementation of `rtic::Doorbell` raising and clearing the `ipi`
+    pub doorbell: Option<Path>,
+
+    /// Link section of the statics holding the executors of the software tasks, instead of the
+    /// stack of `main`
+    pub executors_section: Option<LitStr>,
 }
 
 /// The `init`-ialization function
@@ -96,9 +139,15 @@ pub struct Init {
     /// The name of the user provided shared resources struct
     pub user_shared_struct: Ident,
 
+    /// The generic arguments of the shared resources struct in the return type
+    pub user_shared_args: Vec<GenericArgument>,
+
     /// The name of the user provided local resources struct
     pub user_local_struct: Ident,
 
+    /// The generic arguments of the local resources struct in the return type
+    pub user_local_args: Vec<GenericArgument>,
+
     /// The init function is declared externally
     pub is_extern: bool,
 }
@@ -167,6 +216,9 @@ impl Default for IdleArgs {
 pub struct SharedResourceProperties {
     /// A lock free (exclusive resource)
     pub lock_free: bool,
+
+    /// Accessible from the applications of the other cores
+    pub cross_core: bool,
 }
 
 /// A shared resource, defined in `#[shared]`
@@ -231,6 +283,12 @@ pub struct SoftwareTask {
     /// The inputs of this software task
     pub inputs: Vec<PatType>,
 
+    /// The return type of this software task
+    pub output: ReturnType,
+
+    /// The task returns a `Result`
+    pub is_fallible: bool,
+
     /// The statements that make up the task handler
     pub stmts: Vec<Stmt>,
 
@@ -245,6 +303,24 @@ pub struct SoftwareTaskArgs {
     /// The priority of this task
     pub priority: u8,
 
+    /// The span of the `priority` argument, if it was given
+    pub priority_span: Option<Span>,
+
+    /// The number of instances of this task that can run concurrently
+    pub capacity: u8,
+
+    /// The number of spawns that are queued while all instances are busy
+    pub queue: Option<u8>,
+
+    /// The number of spawns from other cores that can be in flight
+    pub mailbox: Option<u8>,
+
+    /// The largest size of the future of this task in bytes
+    pub max_size: Option<usize>,
+
+    /// How often the task is restarted after returning `Err`
+    pub restart: RestartPolicy,
+
     /// Local resources that can be accessed from this context
     pub local_resources: LocalResources,
 
@@ -256,12 +332,31 @@ impl Default for SoftwareTaskArgs {
     fn default() -> Self {
         Self {
             priority: 0,
+            priority_span: None,
+            capacity: 1,
+            queue: None,
+            mailbox: None,
+            max_size: None,
+            restart: RestartPolicy::Never,
             local_resources: LocalResources::new(),
             shared_resources: SharedResources::new(),
         }
     }
 }
 
+/// Restart policy of a fallible software task
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RestartPolicy {
+    /// The task is not restarted
+    Never,
+
+    /// The task is restarted every time it returns `Err`
+    Always,
+
+    /// The task is restarted at most this many times per spawn
+    Times(u32),
+}
+
 /// A hardware task
 #[derive(Debug)]
 #[non_exhaustive]
@@ -4,6 +4,9 @@
     feature = "test-template",
     feature = "riscv-esp32c3",
     feature = "riscv-slic",
+    feature = "riscv-clic",
+    feature = "riscv-plic",
+    feature = "external-backend",
 )))]
 compile_error!("No backend selected");
 
@@ -19,6 +22,12 @@ pub use esp32c3::*;
 #[cfg(feature = "riscv-slic")]
 pub use riscv_slic::*;
 
+#[cfg(feature = "riscv-clic")]
+pub use riscv_clic::*;
+
+#[cfg(feature = "riscv-plic")]
+pub use riscv_plic::*;
+
 #[cfg(any(feature = "cortex-m-source-masking", feature = "cortex-m-basepri"))]
 mod cortex;
 
@@ -30,3 +39,17 @@ mod esp32c3;
 
 #[cfg(feature = "riscv-slic")]
 mod riscv_slic;
+
+#[cfg(feature = "riscv-clic")]
+mod riscv_clic;
+
+#[cfg(feature = "riscv-plic")]
+mod riscv_plic;
+
+#[cfg(feature = "external-backend")]
+pub use external::*;
+
+#[cfg(feature = "external-backend")]
+mod external {
+    include!(concat!(env!("RTIC_EXTERNAL_BACKEND"), "/syntax.rs"));
+}
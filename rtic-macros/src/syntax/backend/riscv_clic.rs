@@ -0,0 +1,71 @@
+use syn::{
+    braced,
+    parse::{Parse, ParseStream},
+    Error, Expr, Ident, LitInt, Result, Token,
+};
+
+#[derive(Debug)]
+pub struct BackendArgs {
+    /// Base address of the memory mapped CLIC registers
+    pub base: Expr,
+    /// Number of `clicintctl` bits used for the interrupt level
+    pub level_bits: u8,
+}
+
+impl Parse for BackendArgs {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let content;
+        let braces = braced!(content in input);
+
+        let mut base = None;
+        let mut level_bits = None;
+
+        while !content.is_empty() {
+            let ident: Ident = content.parse()?;
+            let _: Token![=] = content.parse()?;
+
+            match &*ident.to_string() {
+                "base" if base.is_none() => base = Some(content.parse()?),
+
+                "level_bits" if level_bits.is_none() => {
+                    let lit: LitInt = content.parse()?;
+                    let bits = lit
+                        .base10_parse::<u8>()
+                        .ok()
+                        .filter(|b| (1..=8).contains(b));
+
+                    if bits.is_none() {
+                        return Err(Error::new(
+                            lit.span(),
+                            "`level_bits` must be between 1 and 8",
+                        ));
+                    }
+
+                    level_bits = bits;
+                }
+
+                "base" | "level_bits" => {
+                    return Err(Error::new(ident.span(), "argument passed more than once"));
+                }
+
+                _ => {
+                    return Err(Error::new(ident.span(), "unexpected argument"));
+                }
+            }
+
+            if content.is_empty() {
+                break;
+            }
+
+            let _: Token![,] = content.parse()?;
+        }
+
+        match (base, level_bits) {
+            (Some(base), Some(level_bits)) => Ok(BackendArgs { base, level_bits }),
+            _ => Err(Error::new(
+                braces.span.join(),
+                "the CLIC backend needs the `base` address and the number of `level_bits`",
+            )),
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use syn::{
+    braced,
+    parse::{Parse, ParseStream},
+    Error, Expr, Ident, LitInt, Path, Result, Token,
+};
+
+#[derive(Debug)]
+pub struct BackendArgs {
+    /// Base address of the memory mapped PLIC registers
+    pub base: Expr,
+    /// PLIC context of the hart in machine mode
+    pub context: Expr,
+    /// Highest priority supported by the PLIC
+    pub max_priority: u8,
+    /// Device specific `fn(u16)` which raises a request of a source, the pending bits of the PLIC
+    /// are read-only
+    pub pend: Path,
+}
+
+impl Parse for BackendArgs {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let content;
+        let braces = braced!(content in input);
+
+        let mut base = None;
+        let mut context = None;
+        let mut max_priority = None;
+        let mut pend = None;
+
+        while !content.is_empty() {
+            let ident: Ident = content.parse()?;
+            let _: Token![=] = content.parse()?;
+
+            match &*ident.to_string() {
+                "base" if base.is_none() => base = Some(content.parse()?),
+
+                "context" if context.is_none() => context = Some(content.parse()?),
+
+                "max_priority" if max_priority.is_none() => {
+                    let lit: LitInt = content.parse()?;
+                    let max = lit.base10_parse::<u8>().ok().filter(|&m| m >= 1);
+
+                    if max.is_none() {
+                        return Err(Error::new(
+                            lit.span(),
+                            "`max_priority` must be between 1 and 255",
+                        ));
+                    }
+
+                    max_priority = max;
+                }
+
+                "pend" if pend.is_none() => pend = Some(content.parse()?),
+
+                "base" | "context" | "max_priority" | "pend" => {
+                    return Err(Error::new(ident.span(), "argument passed more than once"));
+                }
+
+                _ => {
+                    return Err(Error::new(ident.span(), "unexpected argument"));
+                }
+            }
+
+            if content.is_empty() {
+                break;
+            }
+
+            let _: Token![,] = content.parse()?;
+        }
+
+        match (base, context, max_priority, pend) {
+            (Some(base), Some(context), Some(max_priority), Some(pend)) => Ok(BackendArgs {
+                base,
+                context,
+                max_priority,
+                pend,
+            }),
+            _ => Err(Error::new(
+                braces.span.join(),
+                "the PLIC backend needs the `base` address, the hart `context`, the `max_priority` \
+                    and the `pend` function of the device",
+            )),
+        }
+    }
+}
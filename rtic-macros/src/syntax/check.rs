@@ -17,6 +17,13 @@ pub fn app(app: &App) -> parse::Result<()> {
         }
 
         if access.is_exclusive() {
+            if app.shared_resources[name].properties.cross_core {
+                return Err(parse::Error::new(
+                    name.span(),
+                    "cross-core resources can only be accessed by shared reference; use `&x` instead of `x`",
+                ));
+            }
+
             owners.insert(name);
         }
     }
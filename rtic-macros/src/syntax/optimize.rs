@@ -1,36 +1,38 @@
-use std::collections::{BTreeSet, HashMap};
+use std::collections::{BTreeMap, BTreeSet};
 
 use crate::syntax::ast::App;
 
-pub fn app(app: &mut App, settings: &Settings) {
+pub fn app(app: &mut App) {
     // "compress" priorities
     // If the user specified, for example, task priorities of "1, 3, 6",
     // compress them into "1, 2, 3" as to leave no gaps
-    if settings.optimize_priorities {
-        // all task priorities ordered in ascending order
+    if app.args.optimize_priorities {
+        // all task priorities ordered in ascending order, priority 0 tasks run in `main` and
+        // keep their priority
         let priorities = app
             .hardware_tasks
             .values()
-            .map(|task| Some(task.args.priority))
-            .chain(
-                app.software_tasks
-                    .values()
-                    .map(|task| Some(task.args.priority)),
-            )
+            .map(|task| task.args.priority)
+            .chain(app.software_tasks.values().map(|task| task.args.priority))
+            .filter(|priority| *priority > 0)
             .collect::<BTreeSet<_>>();
 
         let map = priorities
             .iter()
             .cloned()
             .zip(1..)
-            .collect::<HashMap<_, _>>();
+            .collect::<BTreeMap<_, _>>();
 
         for task in app.hardware_tasks.values_mut() {
-            task.args.priority = map[&Some(task.args.priority)];
+            task.args.priority = map[&task.args.priority];
         }
 
         for task in app.software_tasks.values_mut() {
-            task.args.priority = map[&Some(task.args.priority)];
+            if task.args.priority > 0 {
+                task.args.priority = map[&task.args.priority];
+            }
         }
+
+        app.priority_map = map;
     }
 }
@@ -15,7 +15,10 @@ use syn::{
 };
 
 use crate::syntax::{
-    ast::{App, AppArgs, HardwareTaskArgs, IdleArgs, InitArgs, SoftwareTaskArgs, TaskLocal},
+    ast::{
+        App, AppArgs, HardwareTaskArgs, IdleArgs, InitArgs, RestartPolicy, SoftwareTaskArgs,
+        TaskLocal,
+    },
     Either,
 };
 
@@ -190,6 +193,16 @@ fn task_args(tokens: TokenStream2) -> parse::Result<Either<HardwareTaskArgs, Sof
         let mut shared_resources = None;
         let mut local_resources = None;
         let mut prio_span = None;
+        let mut capacity = None;
+        let mut capacity_span = None;
+        let mut queue = None;
+        let mut queue_span = None;
+        let mut mailbox = None;
+        let mut mailbox_span = None;
+        let mut max_size = None;
+        let mut max_size_span = None;
+        let mut restart = None;
+        let mut restart_span = None;
 
         loop {
             if input.is_empty() {
@@ -248,6 +261,175 @@ fn task_args(tokens: TokenStream2) -> parse::Result<Either<HardwareTaskArgs, Sof
                     priority = Some(value.unwrap());
                 }
 
+                "capacity" => {
+                    if capacity.is_some() {
+                        return Err(parse::Error::new(
+                            ident.span(),
+                            "argument appears more than once",
+                        ));
+                    }
+
+                    // #lit
+                    let lit: LitInt = input.parse()?;
+
+                    if !lit.suffix().is_empty() {
+                        return Err(parse::Error::new(
+                            lit.span(),
+                            "this literal must be unsuffixed",
+                        ));
+                    }
+
+                    let value = lit.base10_parse::<u8>().ok().filter(|value| *value > 0);
+                    if value.is_none() {
+                        return Err(parse::Error::new(
+                            lit.span(),
+                            "this literal must be in the range 1...255",
+                        ));
+                    }
+
+                    capacity_span = Some(lit.span());
+                    capacity = value;
+                }
+
+                "queue" => {
+                    if queue.is_some() {
+                        return Err(parse::Error::new(
+                            ident.span(),
+                            "argument appears more than once",
+                        ));
+                    }
+
+                    // #lit
+                    let lit: LitInt = input.parse()?;
+
+                    if !lit.suffix().is_empty() {
+                        return Err(parse::Error::new(
+                            lit.span(),
+                            "this literal must be unsuffixed",
+                        ));
+                    }
+
+                    let value = lit.base10_parse::<u8>().ok().filter(|value| *value > 0);
+                    if value.is_none() {
+                        return Err(parse::Error::new(
+                            lit.span(),
+                            "this literal must be in the range 1...255",
+                        ));
+                    }
+
+                    queue_span = Some(lit.span());
+                    queue = value;
+                }
+
+                "mailbox" => {
+                    if mailbox.is_some() {
+                        return Err(parse::Error::new(
+                            ident.span(),
+                            "argument appears more than once",
+                        ));
+                    }
+
+                    // #lit
+                    let lit: LitInt = input.parse()?;
+
+                    if !lit.suffix().is_empty() {
+                        return Err(parse::Error::new(
+                            lit.span(),
+                            "this literal must be unsuffixed",
+                        ));
+                    }
+
+                    let value = lit.base10_parse::<u8>().ok().filter(|value| *value > 0);
+                    if value.is_none() {
+                        return Err(parse::Error::new(
+                            lit.span(),
+                            "this literal must be in the range 1...255",
+                        ));
+                    }
+
+                    mailbox_span = Some(lit.span());
+                    mailbox = value;
+                }
+
+                "max_size" => {
+                    if max_size.is_some() {
+                        return Err(parse::Error::new(
+                            ident.span(),
+                            "argument appears more than once",
+                        ));
+                    }
+
+                    // #lit
+                    let lit: LitInt = input.parse()?;
+
+                    if !lit.suffix().is_empty() {
+                        return Err(parse::Error::new(
+                            lit.span(),
+                            "this literal must be unsuffixed",
+                        ));
+                    }
+
+                    let value = lit.base10_parse::<usize>().ok();
+                    if value.is_none() {
+                        return Err(parse::Error::new(
+                            lit.span(),
+                            "this literal must be a size in bytes",
+                        ));
+                    }
+
+                    max_size_span = Some(lit.span());
+                    max_size = value;
+                }
+
+                "restart" => {
+                    if restart.is_some() {
+                        return Err(parse::Error::new(
+                            ident.span(),
+                            "argument appears more than once",
+                        ));
+                    }
+
+                    // #lit | always | never
+                    if input.peek(LitInt) {
+                        let lit: LitInt = input.parse()?;
+
+                        if !lit.suffix().is_empty() {
+                            return Err(parse::Error::new(
+                                lit.span(),
+                                "this literal must be unsuffixed",
+                            ));
+                        }
+
+                        let value = lit.base10_parse::<u32>().ok();
+                        if value.is_none() {
+                            return Err(parse::Error::new(
+                                lit.span(),
+                                "this literal must be in the range 0...4294967295",
+                            ));
+                        }
+
+                        restart_span = Some(lit.span());
+                        restart = value.map(|value| match value {
+                            0 => RestartPolicy::Never,
+                            n => RestartPolicy::Times(n),
+                        });
+                    } else {
+                        let policy: Ident = input.parse()?;
+
+                        restart = Some(match &*policy.to_string() {
+                            "always" => RestartPolicy::Always,
+                            "never" => RestartPolicy::Never,
+                            _ => {
+                                return Err(parse::Error::new(
+                                    policy.span(),
+                                    "expected `always`, `never` or the number of restarts",
+                                ));
+                            }
+                        });
+                        restart_span = Some(policy.span());
+                    }
+                }
+
                 "shared" => {
                     if shared_resources.is_some() {
                         return Err(parse::Error::new(
@@ -286,6 +468,41 @@ fn task_args(tokens: TokenStream2) -> parse::Result<Either<HardwareTaskArgs, Sof
         let local_resources = local_resources.unwrap_or_default();
 
         Ok(if let Some(binds) = binds {
+            if let Some(span) = capacity_span {
+                return Err(parse::Error::new(
+                    span,
+                    "hardware tasks can not have a capacity",
+                ));
+            }
+
+            if let Some(span) = queue_span {
+                return Err(parse::Error::new(
+                    span,
+                    "hardware tasks can not have a spawn queue",
+                ));
+            }
+
+            if let Some(span) = mailbox_span {
+                return Err(parse::Error::new(
+                    span,
+                    "hardware tasks can not have a mailbox",
+                ));
+            }
+
+            if let Some(span) = max_size_span {
+                return Err(parse::Error::new(
+                    span,
+                    "hardware tasks can not have a `max_size`, they have no future",
+                ));
+            }
+
+            if let Some(span) = restart_span {
+                return Err(parse::Error::new(
+                    span,
+                    "hardware tasks can not have a restart policy",
+                ));
+            }
+
             // Hardware tasks can't run at anything lower than 1
             let priority = priority.unwrap_or(1);
 
@@ -305,9 +522,24 @@ fn task_args(tokens: TokenStream2) -> parse::Result<Either<HardwareTaskArgs, Sof
         } else {
             // Software tasks start at idle priority
             let priority = priority.unwrap_or(0);
+            let capacity = capacity.unwrap_or(1);
+
+            // Local resources can only be handed out to a single instance
+            if capacity > 1 && !local_resources.is_empty() {
+                return Err(parse::Error::new(
+                    capacity_span.unwrap(),
+                    "software tasks with a capacity larger than 1 can not have local resources",
+                ));
+            }
 
             Either::Right(SoftwareTaskArgs {
                 priority,
+                priority_span: prio_span,
+                capacity,
+                queue,
+                mailbox,
+                max_size,
+                restart: restart.unwrap_or(RestartPolicy::Never),
                 shared_resources,
                 local_resources,
             })
@@ -1,11 +1,13 @@
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
 
 // use indexmap::map::Entry;
 use proc_macro2::TokenStream as TokenStream2;
 use syn::{
-    parse::{self, ParseStream, Parser},
+    bracketed,
+    parse::{self, Parse, ParseStream, Parser},
     spanned::Spanned,
-    Expr, ExprArray, Fields, ForeignItem, Ident, Item, LitBool, Path, Token, Visibility,
+    Expr, ExprArray, Fields, ForeignItem, Generics, Ident, Item, ItemUse, LitBool, LitInt, LitStr,
+    Path, Token, Visibility,
 };
 
 use crate::syntax::{
@@ -29,6 +31,15 @@ impl AppArgs {
             let mut peripherals = true;
             let mut dispatchers = Dispatchers::new();
             let mut backend = None;
+            let mut on_task_error = None;
+            let mut auto_dispatchers = None;
+            let mut free_interrupts = Vec::new();
+            let mut optimize_priorities = false;
+            let mut resource_lints = false;
+            let mut core_id = 0;
+            let mut ipi = None;
+            let mut doorbell = None;
+            let mut executors_section = None;
 
             loop {
                 if input.is_empty() {
@@ -84,8 +95,25 @@ impl AppArgs {
                         }
                     }
 
+                    "task_modules" => {
+                        // The task modules are included before parsing, see `task_module.rs`
+                        let content;
+                        bracketed!(content in input);
+                        content.parse_terminated(Path::parse, Token![,])?;
+                    }
+
                     "dispatchers" => {
-                        if let Ok(p) = input.parse::<ExprArray>() {
+                        if input.peek(Ident) {
+                            let auto: Ident = input.parse()?;
+                            if auto != "auto" {
+                                return Err(parse::Error::new(
+                                    auto.span(),
+                                    "unexpected argument value; expected an array or `auto`",
+                                ));
+                            }
+
+                            auto_dispatchers = Some(auto.span());
+                        } else if let Ok(p) = input.parse::<ExprArray>() {
                             for e in p.elems {
                                 match e {
                                     Expr::Path(ep) => {
@@ -128,6 +156,21 @@ impl AppArgs {
                         }
                     }
 
+                    "free_interrupts" => {
+                        let content;
+                        bracketed!(content in input);
+                        for interrupt in content.parse_terminated(Ident::parse, Token![,])? {
+                            if free_interrupts.contains(&interrupt) {
+                                return Err(parse::Error::new(
+                                    interrupt.span(),
+                                    "this interrupt is listed more than once",
+                                ));
+                            }
+
+                            free_interrupts.push(interrupt);
+                        }
+                    }
+
                     "backend" => {
                         if let Ok(p) = input.parse::<BackendArgs>() {
                             backend = Some(p);
@@ -139,6 +182,90 @@ impl AppArgs {
                         }
                     }
 
+                    "optimize_priorities" => {
+                        if let Ok(p) = input.parse::<LitBool>() {
+                            optimize_priorities = p.value;
+                        } else {
+                            return Err(parse::Error::new(
+                                ident.span(),
+                                "unexpected argument value; this should be a boolean",
+                            ));
+                        }
+                    }
+
+                    "resource_lints" => {
+                        if let Ok(p) = input.parse::<LitBool>() {
+                            resource_lints = p.value;
+                        } else {
+                            return Err(parse::Error::new(
+                                ident.span(),
+                                "unexpected argument value; this should be a boolean",
+                            ));
+                        }
+                    }
+
+                    "core_id" => {
+                        if let Ok(p) = input.parse::<LitInt>() {
+                            core_id = p.base10_parse::<u8>()?;
+                        } else {
+                            return Err(parse::Error::new(
+                                ident.span(),
+                                "unexpected argument value; this should be an integer",
+                            ));
+                        }
+                    }
+
+                    "ipi" => {
+                        if let Ok(p) = input.parse::<Ident>() {
+                            ipi = Some(p);
+                        } else {
+                            return Err(parse::Error::new(
+                                ident.span(),
+                                "unexpected argument value; this should be an interrupt",
+                            ));
+                        }
+                    }
+
+                    "doorbell" => {
+                        if let Ok(p) = input.parse::<Path>() {
+                            doorbell = Some(p);
+                        } else {
+                            return Err(parse::Error::new(
+                                ident.span(),
+                                "unexpected argument value; this should be a path",
+                            ));
+                        }
+                    }
+
+                    "executors_section" => {
+                        if let Ok(p) = input.parse::<LitStr>() {
+                            if p.value().is_empty() {
+                                return Err(parse::Error::new(
+                                    p.span(),
+                                    "the link section name can not be empty",
+                                ));
+                            }
+
+                            executors_section = Some(p);
+                        } else {
+                            return Err(parse::Error::new(
+                                ident.span(),
+                                "unexpected argument value; this should be a link section name",
+                            ));
+                        }
+                    }
+
+                    "on_task_error" => {
+                        if let Ok(p) = input.parse::<Path>() {
+                            on_task_error = Some(p);
+                        } else {
+                            return Err(parse::Error::new(
+                                ident.span(),
+                                "unexpected argument value; this should be a path",
+                            ));
+                        }
+                    }
+
                     _ => {
                         return Err(parse::Error::new(ident.span(), "unexpected argument"));
                     }
@@ -158,12 +285,28 @@ impl AppArgs {
                 return Err(parse::Error::new(input.span(), "missing `device = ...`"));
             };
 
+            if let (Some(interrupt), None) = (free_interrupts.first(), auto_dispatchers) {
+                return Err(parse::Error::new(
+                    interrupt.span(),
+                    "`free_interrupts` is only used by `dispatchers = auto`",
+                ));
+            }
+
             Ok(AppArgs {
                 device,
                 core,
                 peripherals,
                 dispatchers,
+                auto_dispatchers,
+                free_interrupts,
                 backend,
+                on_task_error,
+                optimize_priorities,
+                resource_lints,
+                core_id,
+                ipi,
+                doorbell,
+                executors_section,
             })
         })
         .parse2(tokens)
@@ -177,10 +320,12 @@ impl App {
 
         let mut shared_resources_ident = None;
         let mut shared_resources_vis = Visibility::Inherited;
+        let mut shared_resources_generics = Generics::default();
         let mut shared_resources = Map::new();
 
         let mut local_resources_ident = None;
         let mut local_resources_vis = Visibility::Inherited;
+        let mut local_resources_generics = Generics::default();
         let mut local_resources = Map::new();
 
         let mut hardware_tasks = Map::new();
@@ -313,16 +458,28 @@ impl App {
                     {
                         let span = struct_item.ident.span();
 
-                        shared_resources_ident = Some(struct_item.ident.clone());
+                        // Task modules can add resources to the `#[shared]` struct by declaring a
+                        // struct with the same name, its fields are merged
+                        if let Some(ident) = &shared_resources_ident {
+                            if *ident != struct_item.ident {
+                                return Err(parse::Error::new(
+                                    span,
+                                    "`#[shared]` struct must appear at most once, or be named the same in all task modules",
+                                ));
+                            }
 
-                        if !shared_resources.is_empty() {
-                            return Err(parse::Error::new(
-                                span,
-                                "`#[shared]` struct must appear at most once",
-                            ));
-                        }
+                            if !struct_item.generics.params.is_empty() {
+                                return Err(parse::Error::new(
+                                    struct_item.generics.span(),
+                                    "only the first `#[shared]` struct can have generic parameters",
+                                ));
+                            }
+                        } else {
+                            shared_resources_ident = Some(struct_item.ident.clone());
+                            shared_resources_vis = struct_item.vis.clone();
 
-                        shared_resources_vis = struct_item.vis.clone();
+                            shared_resources_generics = struct_item.generics.clone();
+                        }
 
                         if let Fields::Named(fields) = &mut struct_item.fields {
                             for field in &mut fields.named {
@@ -351,16 +508,28 @@ impl App {
                     {
                         let span = struct_item.ident.span();
 
-                        local_resources_ident = Some(struct_item.ident.clone());
+                        // Task modules can add resources to the `#[local]` struct by declaring a
+                        // struct with the same name, its fields are merged
+                        if let Some(ident) = &local_resources_ident {
+                            if *ident != struct_item.ident {
+                                return Err(parse::Error::new(
+                                    span,
+                                    "`#[local]` struct must appear at most once, or be named the same in all task modules",
+                                ));
+                            }
 
-                        if !local_resources.is_empty() {
-                            return Err(parse::Error::new(
-                                span,
-                                "`#[local]` struct must appear at most once",
-                            ));
-                        }
+                            if !struct_item.generics.params.is_empty() {
+                                return Err(parse::Error::new(
+                                    struct_item.generics.span(),
+                                    "only the first `#[local]` struct can have generic parameters",
+                                ));
+                            }
+                        } else {
+                            local_resources_ident = Some(struct_item.ident.clone());
+                            local_resources_vis = struct_item.vis.clone();
 
-                        local_resources_vis = struct_item.vis.clone();
+                            local_resources_generics = struct_item.generics.clone();
+                        }
 
                         if let Fields::Named(fields) = &mut struct_item.fields {
                             for field in &mut fields.named {
@@ -496,8 +665,18 @@ impl App {
                     }
                 }
                 Item::Use(itemuse_) => {
-                    // Store the user provided use-statements
-                    user_imports.push(itemuse_.clone());
+                    // Store the user provided use-statements, skipping imports which are repeated
+                    // in task modules
+                    for tree in util::use_leaves(&itemuse_.tree) {
+                        let import = ItemUse {
+                            tree,
+                            ..itemuse_.clone()
+                        };
+
+                        if !user_imports.contains(&import) {
+                            user_imports.push(import);
+                        }
+                    }
                 }
                 _ => {
                     // Anything else within the module should not make any difference
@@ -530,6 +709,20 @@ impl App {
             ));
         }
 
+        // The generic arguments in the return type of `init`, or else the defaults, select the
+        // resource types
+        let shared_resources_generics =
+            util::resource_generics(&shared_resources_generics, &init.user_shared_args)?;
+        for resource in shared_resources.values_mut() {
+            *resource.ty = util::substitute_generics(&resource.ty, &shared_resources_generics);
+        }
+
+        let local_resources_generics =
+            util::resource_generics(&local_resources_generics, &init.user_local_args)?;
+        for resource in local_resources.values_mut() {
+            *resource.ty = util::substitute_generics(&resource.ty, &local_resources_generics);
+        }
+
         Ok(App {
             args,
             name: input.ident,
@@ -537,12 +730,15 @@ impl App {
             idle,
             shared_resources,
             shared_resources_vis,
+            shared_resources_generics,
             local_resources,
             local_resources_vis,
+            local_resources_generics,
             user_imports,
             user_code,
             hardware_tasks,
             software_tasks,
+            priority_map: BTreeMap::new(),
         })
     }
 }
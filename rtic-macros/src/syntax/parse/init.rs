@@ -21,8 +21,10 @@ impl Init {
         let name = item.sig.ident.to_string();
 
         if valid_signature {
-            if let Ok((user_shared_struct, user_local_struct)) =
-                util::type_is_init_return(&item.sig.output)
+            if let Ok((
+                (user_shared_struct, user_shared_args),
+                (user_local_struct, user_local_args),
+            )) = util::type_is_init_return(&item.sig.output)
             {
                 if let Some((context, Ok(rest))) = util::parse_inputs(item.sig.inputs, &name) {
                     if rest.is_empty() {
@@ -33,7 +35,9 @@ impl Init {
                             name: item.sig.ident,
                             stmts: item.block.stmts,
                             user_shared_struct,
+                            user_shared_args,
                             user_local_struct,
+                            user_local_args,
                             is_extern: false,
                         });
                     }
@@ -58,8 +62,10 @@ impl Init {
         let name = item.sig.ident.to_string();
 
         if valid_signature {
-            if let Ok((user_shared_struct, user_local_struct)) =
-                util::type_is_init_return(&item.sig.output)
+            if let Ok((
+                (user_shared_struct, user_shared_args),
+                (user_local_struct, user_local_args),
+            )) = util::type_is_init_return(&item.sig.output)
             {
                 if let Some((context, Ok(rest))) = util::parse_inputs(item.sig.inputs, &name) {
                     if rest.is_empty() {
@@ -70,7 +76,9 @@ impl Init {
                             name: item.sig.ident,
                             stmts: Vec::<Stmt>::new(),
                             user_shared_struct,
+                            user_shared_args,
                             user_local_struct,
+                            user_local_args,
                             is_extern: true,
                         });
                     }
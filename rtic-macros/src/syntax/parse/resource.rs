@@ -1,4 +1,4 @@
-use syn::{parse, Field};
+use syn::{parse, spanned::Spanned, Field};
 
 use crate::syntax::parse::util::FilterAttrs;
 use crate::syntax::{
@@ -15,13 +15,26 @@ impl SharedResource {
         } = util::filter_attributes(item.attrs.clone());
 
         let lock_free = util::extract_lock_free(&mut attrs)?;
+        let cross_core = util::extract_cross_core(&mut attrs)?;
+
+        if lock_free && cross_core {
+            return Err(parse::Error::new(
+                item.ident
+                    .as_ref()
+                    .map_or(item.span(), |ident| ident.span()),
+                "cross-core resources can not be `#[lock_free]`",
+            ));
+        }
 
         Ok(SharedResource {
             cfgs,
             attrs,
             docs,
             ty: Box::new(item.ty.clone()),
-            properties: SharedResourceProperties { lock_free },
+            properties: SharedResourceProperties {
+                lock_free,
+                cross_core,
+            },
             vis: item.vis.clone(),
         })
     }
@@ -1,5 +1,7 @@
 use syn::{parse, ForeignItemFn, ItemFn, Stmt};
 
+use crate::syntax::ast::RestartPolicy;
+
 use crate::syntax::parse::util::FilterAttrs;
 use crate::syntax::{
     ast::{SoftwareTask, SoftwareTaskArgs},
@@ -9,7 +11,7 @@ use crate::syntax::{
 impl SoftwareTask {
     pub(crate) fn parse(args: SoftwareTaskArgs, item: ItemFn) -> parse::Result<Self> {
         let valid_signature = util::check_fn_signature(&item, true)
-            && util::type_is_unit(&item.sig.output)
+            && !util::type_is_bottom(&item.sig.output)
             && item.sig.asyncness.is_some();
 
         let span = item.sig.ident.span();
@@ -19,6 +21,21 @@ impl SoftwareTask {
         if valid_signature {
             if let Some((context, Ok(inputs))) = util::parse_inputs(item.sig.inputs, &name) {
                 let FilterAttrs { cfgs, attrs, .. } = util::filter_attributes(item.attrs);
+                let is_fallible = util::type_is_result(&item.sig.output);
+
+                if args.restart != RestartPolicy::Never && !is_fallible {
+                    return Err(parse::Error::new(
+                        span,
+                        "only software tasks returning a `Result` can be restarted",
+                    ));
+                }
+
+                if args.queue.is_some() && !util::type_is_unit(&item.sig.output) {
+                    return Err(parse::Error::new(
+                        span,
+                        "software tasks with a `queue` can not return a value: a queued spawn has no `TaskHandle` to retrieve it",
+                    ));
+                }
 
                 return Ok(SoftwareTask {
                     args,
@@ -26,6 +43,8 @@ impl SoftwareTask {
                     cfgs,
                     context,
                     inputs,
+                    output: item.sig.output,
+                    is_fallible,
                     stmts: item.block.stmts,
                     is_extern: false,
                 });
@@ -45,7 +64,7 @@ impl SoftwareTask {
         item: ForeignItemFn,
     ) -> parse::Result<Self> {
         let valid_signature = util::check_foreign_fn_signature(&item, true)
-            && util::type_is_unit(&item.sig.output)
+            && !util::type_is_bottom(&item.sig.output)
             && item.sig.asyncness.is_some();
 
         let span = item.sig.ident.span();
@@ -55,6 +74,21 @@ impl SoftwareTask {
         if valid_signature {
             if let Some((context, Ok(inputs))) = util::parse_inputs(item.sig.inputs, &name) {
                 let FilterAttrs { cfgs, attrs, .. } = util::filter_attributes(item.attrs);
+                let is_fallible = util::type_is_result(&item.sig.output);
+
+                if args.restart != RestartPolicy::Never && !is_fallible {
+                    return Err(parse::Error::new(
+                        span,
+                        "only software tasks returning a `Result` can be restarted",
+                    ));
+                }
+
+                if args.queue.is_some() && !util::type_is_unit(&item.sig.output) {
+                    return Err(parse::Error::new(
+                        span,
+                        "software tasks with a `queue` can not return a value: a queued spawn has no `TaskHandle` to retrieve it",
+                    ));
+                }
 
                 return Ok(SoftwareTask {
                     args,
@@ -62,6 +96,8 @@ impl SoftwareTask {
                     cfgs,
                     context,
                     inputs,
+                    output: item.sig.output,
+                    is_fallible,
                     stmts: Vec::<Stmt>::new(),
                     is_extern: true,
                 });
@@ -1,10 +1,13 @@
 use syn::{
     bracketed,
     parse::{self, ParseStream},
+    parse_quote,
     punctuated::Punctuated,
     spanned::Spanned,
-    Abi, AttrStyle, Attribute, Expr, ExprPath, FnArg, ForeignItemFn, Ident, ItemFn, Pat, PatType,
-    Path, PathArguments, ReturnType, Token, Type, Visibility,
+    visit_mut::{self, VisitMut},
+    Abi, AttrStyle, Attribute, ConstParam, Expr, ExprPath, FnArg, ForeignItemFn, GenericArgument,
+    GenericParam, Generics, Ident, ItemFn, Pat, PatType, Path, PathArguments, ReturnType, Stmt,
+    Token, Type, TypeParam, TypePath, UseGroup, UseName, UsePath, UseRename, UseTree, Visibility,
 };
 
 use crate::syntax::{
@@ -91,6 +94,15 @@ pub fn extract_lock_free(attrs: &mut Vec<Attribute>) -> parse::Result<bool> {
     }
 }
 
+pub fn extract_cross_core(attrs: &mut Vec<Attribute>) -> parse::Result<bool> {
+    if let Some(pos) = attrs.iter().position(|attr| attr_eq(attr, "cross_core")) {
+        attrs.remove(pos);
+        Ok(true)
+    } else {
+        Ok(false)
+    }
+}
+
 pub fn parse_shared_resources(content: ParseStream<'_>) -> parse::Result<SharedResources> {
     let inner;
     bracketed!(inner in content);
@@ -313,26 +325,34 @@ pub fn type_is_bottom(ty: &ReturnType) -> bool {
     }
 }
 
-fn extract_init_resource_name_ident(ty: Type) -> Result<Ident, ()> {
+fn extract_init_resource_struct(ty: Type) -> Result<(Ident, Vec<GenericArgument>), ()> {
     match ty {
-        Type::Path(path) => {
+        Type::Path(path) if path.qself.is_none() => {
             let path = path.path;
 
-            if path.leading_colon.is_some()
-                || path.segments.len() != 1
-                || path.segments[0].arguments != PathArguments::None
-            {
-                Err(())
-            } else {
-                Ok(path.segments[0].ident.clone())
+            if path.leading_colon.is_some() || path.segments.len() != 1 {
+                return Err(());
             }
+
+            let segment = &path.segments[0];
+            let args = match &segment.arguments {
+                PathArguments::None => vec![],
+                PathArguments::AngleBracketed(args) => args.args.iter().cloned().collect(),
+                PathArguments::Parenthesized(_) => return Err(()),
+            };
+
+            Ok((segment.ident.clone(), args))
         }
         _ => Err(()),
     }
 }
 
-/// Checks Init's return type, return the user provided types for analysis
-pub fn type_is_init_return(ty: &ReturnType) -> Result<(Ident, Ident), ()> {
+/// Checks Init's return type, return the user provided types and their generic arguments for
+/// analysis
+#[allow(clippy::type_complexity)]
+pub fn type_is_init_return(
+    ty: &ReturnType,
+) -> Result<((Ident, Vec<GenericArgument>), (Ident, Vec<GenericArgument>)), ()> {
     match ty {
         ReturnType::Default => Err(()),
 
@@ -345,8 +365,8 @@ pub fn type_is_init_return(ty: &ReturnType) -> Result<(Ident, Ident), ()> {
                 // provided structs are correct.
                 if t.elems.len() == 2 {
                     return Ok((
-                        extract_init_resource_name_ident(t.elems[0].clone())?,
-                        extract_init_resource_name_ident(t.elems[1].clone())?,
+                        extract_init_resource_struct(t.elems[0].clone())?,
+                        extract_init_resource_struct(t.elems[1].clone())?,
                     ));
                 }
 
@@ -374,6 +394,22 @@ pub fn type_is_path(ty: &Type, segments: &[&str]) -> bool {
     }
 }
 
+/// Checks if the return type is a `Result`, by the name of the type
+pub fn type_is_result(ty: &ReturnType) -> bool {
+    if let ReturnType::Type(_, ty) = ty {
+        if let Type::Path(tpath) = &**ty {
+            return tpath.qself.is_none()
+                && tpath
+                    .path
+                    .segments
+                    .last()
+                    .is_some_and(|segment| segment.ident == "Result");
+        }
+    }
+
+    false
+}
+
 pub fn type_is_unit(ty: &ReturnType) -> bool {
     if let ReturnType::Type(_, ty) = ty {
         if let Type::Tuple(ref tuple) = **ty {
@@ -385,3 +421,249 @@ pub fn type_is_unit(ty: &ReturnType) -> bool {
         true
     }
 }
+
+/// Selects the concrete types of a resource struct: the generic arguments of the struct in the
+/// return type of `init` replace the defaults of the parameters, then the defaults are resolved
+pub fn resource_generics(generics: &Generics, args: &[GenericArgument]) -> parse::Result<Generics> {
+    if let Some(arg) = args.get(generics.params.len()) {
+        return Err(parse::Error::new(
+            arg.span(),
+            "this resource struct does not have that many generic parameters",
+        ));
+    }
+
+    let mut generics = generics.clone();
+    for (param, arg) in generics.params.iter_mut().zip(args) {
+        match (param, arg) {
+            (GenericParam::Type(param), GenericArgument::Type(ty)) => {
+                param.eq_token.get_or_insert_with(Default::default);
+                param.default = Some(ty.clone());
+            }
+            (GenericParam::Const(param), GenericArgument::Const(expr)) => {
+                param.eq_token.get_or_insert_with(Default::default);
+                param.default = Some(expr.clone());
+            }
+            // A constant given by name parses as a type, e.g. `Local<SAMPLES>`
+            (GenericParam::Const(param), GenericArgument::Type(ty)) => {
+                param.eq_token.get_or_insert_with(Default::default);
+                param.default = Some(parse_quote!(#ty));
+            }
+            (_, arg) => {
+                return Err(parse::Error::new(
+                    arg.span(),
+                    "this generic argument does not match the parameter of the resource struct",
+                ))
+            }
+        }
+    }
+
+    check_resource_generics(&generics)?;
+
+    Ok(resolve_generic_defaults(&generics))
+}
+
+/// Checks that every generic parameter of a resource struct has a default, which selects the
+/// concrete type of the resources
+fn check_resource_generics(generics: &Generics) -> parse::Result<()> {
+    for param in &generics.params {
+        let (span, has_default) = match param {
+            GenericParam::Type(param) => (param.ident.span(), param.default.is_some()),
+            GenericParam::Const(param) => (param.ident.span(), param.default.is_some()),
+            GenericParam::Lifetime(param) => {
+                return Err(parse::Error::new(
+                    param.lifetime.span(),
+                    "resource structs can not have lifetime parameters",
+                ))
+            }
+        };
+
+        if !has_default {
+            return Err(parse::Error::new(
+                span,
+                "generic parameters of resource structs must have a default, or an argument \
+                 in the return type of `init`, which selects the concrete type",
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Replaces the generic parameters of a resource struct in `ty` by their defaults
+pub fn substitute_generics(ty: &Type, generics: &Generics) -> Type {
+    let mut ty = ty.clone();
+    Substitute::new(generics).visit_type_mut(&mut ty);
+    ty
+}
+
+/// Replaces the generic parameters used in the defaults of `generics` by their own defaults, so
+/// that every default is a concrete type or value
+pub fn resolve_generic_defaults(generics: &Generics) -> Generics {
+    let mut resolved = generics.clone();
+    for (end, param) in resolved.params.iter_mut().enumerate() {
+        let mut earlier = Substitute { generics, end };
+        match param {
+            GenericParam::Type(TypeParam {
+                default: Some(default),
+                ..
+            }) => earlier.visit_type_mut(default),
+            GenericParam::Const(ConstParam {
+                default: Some(default),
+                ..
+            }) => earlier.visit_expr_mut(default),
+            _ => {}
+        }
+    }
+
+    resolved
+}
+
+// Substitutes the first `end` generic parameters, a default can only refer to the parameters
+// declared before it, e.g. `struct Shared<A = u8, B = Vec<A>>`
+struct Substitute<'a> {
+    generics: &'a Generics,
+    end: usize,
+}
+
+impl<'a> Substitute<'a> {
+    fn new(generics: &'a Generics) -> Self {
+        Substitute {
+            generics,
+            end: generics.params.len(),
+        }
+    }
+
+    // The parameter named by `path` and a substitution for its default
+    fn param(&self, path: &Path) -> Option<(&'a GenericParam, Self)> {
+        let ident = path.get_ident()?;
+
+        self.generics
+            .params
+            .iter()
+            .take(self.end)
+            .enumerate()
+            .find(|(_, param)| match param {
+                GenericParam::Type(param) => param.ident == *ident,
+                GenericParam::Const(param) => param.ident == *ident,
+                GenericParam::Lifetime(_) => false,
+            })
+            .map(|(end, param)| {
+                (
+                    param,
+                    Substitute {
+                        generics: self.generics,
+                        end,
+                    },
+                )
+            })
+    }
+}
+
+impl VisitMut for Substitute<'_> {
+    fn visit_type_mut(&mut self, ty: &mut Type) {
+        if let Type::Path(TypePath { qself: None, path }) = ty {
+            if let Some((
+                GenericParam::Type(TypeParam {
+                    default: Some(default),
+                    ..
+                }),
+                mut earlier,
+            )) = self.param(path)
+            {
+                *ty = default.clone();
+                earlier.visit_type_mut(ty);
+                return;
+            }
+        }
+
+        visit_mut::visit_type_mut(self, ty);
+    }
+
+    fn visit_generic_argument_mut(&mut self, arg: &mut GenericArgument) {
+        // const parameters used as generic arguments parse as types, e.g. `Vec<u8, N>`
+        if let GenericArgument::Type(Type::Path(TypePath { qself: None, path })) = arg {
+            if let Some((
+                GenericParam::Const(ConstParam {
+                    default: Some(default),
+                    ..
+                }),
+                mut earlier,
+            )) = self.param(path)
+            {
+                let mut default = default.clone();
+                earlier.visit_expr_mut(&mut default);
+                *arg = GenericArgument::Const(match default {
+                    Expr::Lit(_) | Expr::Block(_) => default,
+                    _ => parse_quote!({ #default }),
+                });
+                return;
+            }
+        }
+
+        visit_mut::visit_generic_argument_mut(self, arg);
+    }
+
+    fn visit_expr_mut(&mut self, expr: &mut Expr) {
+        if let Expr::Path(ExprPath {
+            qself: None, path, ..
+        }) = expr
+        {
+            if let Some((
+                GenericParam::Const(ConstParam {
+                    default: Some(default),
+                    ..
+                }),
+                mut earlier,
+            )) = self.param(path)
+            {
+                *expr = match default {
+                    // unwrap `{ CONST }`, the braces are only needed in generic arguments
+                    Expr::Block(block) => match block.block.stmts.as_slice() {
+                        [Stmt::Expr(inner, None)] => inner.clone(),
+                        _ => default.clone(),
+                    },
+                    _ => default.clone(),
+                };
+                earlier.visit_expr_mut(expr);
+                return;
+            }
+        }
+
+        visit_mut::visit_expr_mut(self, expr);
+    }
+}
+
+/// Splits a `use` tree into one tree per imported item, so that imports repeated by the app and
+/// its task modules can be de-duplicated
+pub fn use_leaves(tree: &UseTree) -> Vec<UseTree> {
+    match tree {
+        UseTree::Path(path) => use_leaves(&path.tree)
+            .into_iter()
+            .map(|tree| {
+                UseTree::Path(UsePath {
+                    tree: Box::new(tree),
+                    ..path.clone()
+                })
+            })
+            .collect(),
+
+        UseTree::Group(group) => group
+            .items
+            .iter()
+            .flat_map(|tree| match tree {
+                // `self` can only be imported within a group
+                UseTree::Name(UseName { ident }) | UseTree::Rename(UseRename { ident, .. })
+                    if ident == "self" =>
+                {
+                    vec![UseTree::Group(UseGroup {
+                        brace_token: group.brace_token,
+                        items: [tree.clone()].into_iter().collect(),
+                    })]
+                }
+                tree => use_leaves(tree),
+            })
+            .collect(),
+
+        tree => vec![tree.clone()],
+    }
+}
@@ -0,0 +1,145 @@
+use proc_macro2::{Delimiter, TokenStream as TokenStream2, TokenTree};
+use quote::{format_ident, quote};
+use syn::{
+    parse::{self, Parser},
+    punctuated::Punctuated,
+    spanned::Spanned,
+    ItemMod, Path, Token, Visibility,
+};
+
+/// Includes the first of the `task_modules = [..]` into the app
+///
+/// The task module macro appends its items to the app module and invokes `#[rtic::app]` again
+/// with the remaining task modules, until all of them are included. Returns `None` when there is
+/// nothing left to include.
+pub fn include(args: TokenStream2, input: TokenStream2) -> parse::Result<Option<TokenStream2>> {
+    let mut args = split_args(args);
+
+    let Some(pos) = args.iter().position(
+        |arg| matches!(arg.first(), Some(TokenTree::Ident(ident)) if ident == "task_modules"),
+    ) else {
+        return Ok(None);
+    };
+
+    let modules = match &args[pos][..] {
+        [_, TokenTree::Punct(eq), TokenTree::Group(group)]
+            if eq.as_char() == '=' && group.delimiter() == Delimiter::Bracket =>
+        {
+            Punctuated::<Path, Token![,]>::parse_terminated.parse2(group.stream())?
+        }
+        arg => {
+            let arg: TokenStream2 = arg.iter().cloned().collect();
+            return Err(parse::Error::new_spanned(
+                arg,
+                "expected `task_modules = [..]` with a list of task module paths",
+            ));
+        }
+    };
+
+    let mut modules = modules.into_iter();
+    let Some(first) = modules.next() else {
+        return Ok(None);
+    };
+
+    let rest: Vec<_> = modules.collect();
+    args[pos] = quote!(task_modules = [#(#rest),*]).into_iter().collect();
+    let args = args
+        .into_iter()
+        .map(|arg| arg.into_iter().collect::<TokenStream2>());
+
+    Ok(Some(quote!(
+        #first! {
+            #[::rtic::app(#(#args),*)]
+            #input
+        }
+    )))
+}
+
+/// Turns a `#[task_module]` into a macro which appends the items of the module to the app
+pub fn task_module(args: TokenStream2, input: TokenStream2) -> parse::Result<TokenStream2> {
+    if !args.is_empty() {
+        return Err(parse::Error::new(
+            args.span(),
+            "`#[task_module]` takes no arguments",
+        ));
+    }
+
+    let module: ItemMod = syn::parse2(input)?;
+
+    let Some((_, items)) = &module.content else {
+        return Err(parse::Error::new(
+            module.span(),
+            "task modules must be inline, e.g. `mod sensors { .. }`",
+        ));
+    };
+
+    if let Some(attr) = module
+        .attrs
+        .iter()
+        .find(|attr| !attr.path().is_ident("doc"))
+    {
+        return Err(parse::Error::new(
+            attr.span(),
+            "task modules can only have doc attributes",
+        ));
+    }
+
+    let items: TokenStream2 = quote!(#(#items)*);
+    if let Some(span) = find_dollar(items.clone()) {
+        return Err(parse::Error::new(
+            span,
+            "task modules can not contain `$`, move `macro_rules!` out of the task module",
+        ));
+    }
+
+    let docs = &module.attrs;
+    let name = &module.ident;
+    let vis = match &module.vis {
+        // `macro_rules!` can not be re-exported outside of the crate
+        Visibility::Inherited => quote!(),
+        _ => quote!(pub(crate)),
+    };
+    let internal_name = format_ident!("__rtic_task_module_{}", name);
+
+    Ok(quote!(
+        #(#docs)*
+        #[doc(hidden)]
+        #[allow(unused_macros)]
+        macro_rules! #internal_name {
+            ($(#[$($attr:tt)*])* $vis:vis mod $app:ident { $($body:tt)* }) => {
+                $(#[$($attr)*])*
+                $vis mod $app {
+                    $($body)*
+
+                    #items
+                }
+            };
+        }
+
+        #[allow(unused_imports)]
+        #vis use #internal_name as #name;
+    ))
+}
+
+/// Splits the app arguments at the top level commas
+fn split_args(args: TokenStream2) -> Vec<Vec<TokenTree>> {
+    let mut split = vec![vec![]];
+
+    for tt in args {
+        match tt {
+            TokenTree::Punct(p) if p.as_char() == ',' => split.push(vec![]),
+            tt => split.last_mut().unwrap().push(tt),
+        }
+    }
+
+    split.retain(|arg| !arg.is_empty());
+    split
+}
+
+fn find_dollar(tokens: TokenStream2) -> Option<proc_macro2::Span> {
+    tokens.into_iter().find_map(|tt| match tt {
+        TokenTree::Punct(p) if p.as_char() == '$' => Some(p.span()),
+        TokenTree::Group(g) => find_dollar(g.stream()),
+        _ => None,
+    })
+}
@@ -0,0 +1,13 @@
+use syn::{
+    parse::{Parse, ParseStream},
+    Result,
+};
+
+#[derive(Debug)]
+pub struct BackendArgs {}
+
+impl Parse for BackendArgs {
+    fn parse(_input: ParseStream) -> Result<Self> {
+        Ok(BackendArgs {})
+    }
+}
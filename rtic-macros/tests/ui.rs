@@ -5,3 +5,19 @@ fn ui() {
     let t = TestCases::new();
     t.compile_fail("ui/*.rs");
 }
+
+// Checks the selected backend against the `Backend` contract. The SLIC backend needs
+// backend-specific `#[app]` arguments and is not covered, the CLIC and PLIC backends have their
+// own apps.
+#[cfg(not(feature = "riscv-slic"))]
+#[test]
+fn conformance() {
+    let t = TestCases::new();
+    if cfg!(feature = "riscv-clic") {
+        t.pass("ui/conformance/riscv-clic/*.rs");
+    } else if cfg!(feature = "riscv-plic") {
+        t.pass("ui/conformance/riscv-plic/*.rs");
+    } else {
+        t.pass("ui/conformance/*.rs");
+    }
+}
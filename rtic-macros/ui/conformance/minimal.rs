@@ -0,0 +1,13 @@
+#[rtic_macros::conformance_app(device = mock, peripherals = false)]
+mod app {
+    #[shared]
+    struct Shared {}
+
+    #[local]
+    struct Local {}
+
+    #[init]
+    fn init(_: init::Context) -> (Shared, Local) {
+        (Shared {}, Local {})
+    }
+}
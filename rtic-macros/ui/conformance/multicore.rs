@@ -0,0 +1,22 @@
+#[rtic_macros::conformance_app(device = mock, dispatchers = auto, core_id = 1, ipi = GPIOB, doorbell = Doorbell)]
+mod app {
+    #[shared]
+    struct Shared {
+        #[cross_core]
+        counter: u32,
+    }
+
+    #[local]
+    struct Local {}
+
+    #[init]
+    fn init(_: init::Context) -> (Shared, Local) {
+        (Shared { counter: 0 }, Local {})
+    }
+
+    #[task(priority = 1, mailbox = 4, shared = [&counter])]
+    async fn ping(_: ping::Context, _n: u32) {}
+
+    #[task(priority = 2, mailbox = 1, queue = 2)]
+    async fn pong(_: pong::Context) {}
+}
@@ -0,0 +1,62 @@
+#[rtic_macros::conformance_app(
+    device = mock,
+    dispatchers = auto,
+    backend = { base = 0xD200_0000, level_bits = 4 }
+)]
+mod app {
+    #[shared]
+    struct Shared {
+        contended: u32,
+        owned: u32,
+        #[lock_free]
+        lock_free: u32,
+    }
+
+    #[local]
+    struct Local {
+        local: u32,
+    }
+
+    #[init]
+    fn init(_: init::Context) -> (Shared, Local) {
+        (
+            Shared {
+                contended: 0,
+                owned: 0,
+                lock_free: 0,
+            },
+            Local { local: 0 },
+        )
+    }
+
+    #[idle(shared = [contended])]
+    fn idle(mut cx: idle::Context) -> ! {
+        loop {
+            cx.shared.contended.lock(|contended| *contended += 1);
+        }
+    }
+
+    #[task(binds = UART0, priority = 3, shared = [contended, owned, lock_free], local = [local])]
+    fn uart0(mut cx: uart0::Context) {
+        cx.shared.contended.lock(|contended| *contended += 1);
+        cx.shared.owned.lock(|owned| *owned += 1);
+        *cx.shared.lock_free += 1;
+        *cx.local.local += 1;
+    }
+
+    #[task(binds = GPIOA, priority = 3, shared = [lock_free])]
+    fn gpioa(cx: gpioa::Context) {
+        *cx.shared.lock_free += 1;
+    }
+
+    #[task(priority = 1)]
+    async fn low(_: low::Context) {}
+
+    #[task(priority = 1)]
+    async fn also_low(_: also_low::Context) {}
+
+    #[task(priority = 2, shared = [contended])]
+    async fn high(mut cx: high::Context) {
+        cx.shared.contended.lock(|contended| *contended += 1);
+    }
+}
@@ -0,0 +1,25 @@
+#[rtic_macros::conformance_app(device = mock, dispatchers = auto)]
+mod app {
+    #[shared]
+    struct Shared {
+        value: u32,
+    }
+
+    #[local]
+    struct Local {}
+
+    #[init]
+    fn init(_: init::Context) -> (Shared, Local) {
+        (Shared { value: 0 }, Local {})
+    }
+
+    #[task(shared = [value])]
+    async fn background(mut cx: background::Context) {
+        cx.shared.value.lock(|value| *value += 1);
+    }
+
+    #[task(priority = 1, shared = [value])]
+    async fn foo(mut cx: foo::Context) {
+        cx.shared.value.lock(|value| *value += 1);
+    }
+}
@@ -0,0 +1,22 @@
+#![no_main]
+
+#[rtic_macros::mock_app(device = mock, dispatchers = [EXTI0])]
+mod app {
+    #[shared]
+    struct Shared {}
+
+    #[local]
+    struct Local {}
+
+    #[init]
+    fn init(_: init::Context) -> (Shared, Local) {}
+
+    #[task(priority = 1)]
+    async fn foo(_: foo::Context) {}
+
+    #[task(priority = 2)]
+    async fn bar(_: bar::Context) {}
+
+    #[task(priority = 3)]
+    async fn baz(_: baz::Context) {}
+}
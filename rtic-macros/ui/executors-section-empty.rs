@@ -0,0 +1,4 @@
+#![no_main]
+
+#[rtic_macros::mock_app(device = mock, executors_section = "")]
+mod app {}
@@ -0,0 +1,15 @@
+#![no_main]
+
+#[rtic_macros::mock_app(device = mock)]
+mod app {
+    #[shared]
+    struct Shared {}
+
+    #[local]
+    struct Local<'a> {
+        a: &'a u32,
+    }
+
+    #[init]
+    fn init(_: init::Context) -> (Shared, Local) {}
+}
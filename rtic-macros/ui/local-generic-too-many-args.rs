@@ -0,0 +1,15 @@
+#![no_main]
+
+#[rtic_macros::mock_app(device = mock)]
+mod app {
+    #[shared]
+    struct Shared {}
+
+    #[local]
+    struct Local<T = u8> {
+        a: T,
+    }
+
+    #[init]
+    fn init(_: init::Context) -> (Shared, Local<u16, u32>) {}
+}
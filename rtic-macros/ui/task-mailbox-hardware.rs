@@ -0,0 +1,7 @@
+#![no_main]
+
+#[rtic_macros::mock_app(device = mock)]
+mod app {
+    #[task(binds = UART0, mailbox = 2)]
+    fn foo(_: foo::Context) {}
+}
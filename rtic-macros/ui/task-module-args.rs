@@ -0,0 +1,4 @@
+#[rtic_macros::task_module(priority = 1)]
+mod tasks {}
+
+fn main() {}
@@ -0,0 +1,8 @@
+#[rtic_macros::task_module]
+mod tasks {
+    macro_rules! foo {
+        ($e:expr) => {};
+    }
+}
+
+fn main() {}
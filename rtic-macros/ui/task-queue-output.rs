@@ -0,0 +1,9 @@
+#![no_main]
+
+#[rtic_macros::mock_app(device = mock)]
+mod app {
+    #[task(queue = 2)]
+    async fn foo(_: foo::Context) -> u32 {
+        0
+    }
+}
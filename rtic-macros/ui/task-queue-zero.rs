@@ -0,0 +1,7 @@
+#![no_main]
+
+#[rtic_macros::mock_app(device = mock)]
+mod app {
+    #[task(queue = 0)]
+    async fn foo(_: foo::Context) {}
+}
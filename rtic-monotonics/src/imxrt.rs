@@ -26,11 +26,7 @@
 //! }
 //! ```
 
-use portable_atomic::{AtomicU32, Ordering};
-use rtic_time::{
-    half_period_counter::calculate_now,
-    timer_queue::{TimerQueue, TimerQueueBackend},
-};
+use rtic_time::half_period_monotonic::{HalfPeriodMonotonic, HalfPeriodState, HalfPeriodTimer};
 
 pub use imxrt_ral as ral;
 
@@ -53,7 +49,10 @@ macro_rules! __internal_create_imxrt_timer_interrupt {
         #[allow(non_snake_case)]
         unsafe extern "C" fn $timer() {
             use $crate::TimerQueueBackend;
-            $crate::imxrt::$mono_backend::timer_queue().on_monotonic_interrupt();
+            $crate::rtic_time::half_period_monotonic::HalfPeriodMonotonic::<
+                $crate::imxrt::$mono_backend,
+            >::timer_queue()
+            .on_monotonic_interrupt();
         }
     };
 }
@@ -77,7 +76,9 @@ macro_rules! __internal_create_imxrt_timer_struct {
         }
 
         impl $crate::TimerQueueBasedMonotonic for $name {
-            type Backend = $crate::imxrt::$mono_backend;
+            type Backend = $crate::rtic_time::half_period_monotonic::HalfPeriodMonotonic<
+                $crate::imxrt::$mono_backend,
+            >;
             type Instant = $crate::fugit::Instant<
                 <Self::Backend as $crate::TimerQueueBackend>::Ticks,
                 1,
@@ -132,8 +133,8 @@ macro_rules! imxrt_gpt2_monotonic {
 }
 
 macro_rules! make_timer {
-    ($mono_name:ident, $backend_name:ident, $timer:ident, $period:ident, $tq:ident$(, doc: ($($doc:tt)*))?) => {
-        /// GPT based [`TimerQueueBackend`].
+    ($backend_name:ident, $timer:ident$(, doc: ($($doc:tt)*))?) => {
+        /// GPT based [`HalfPeriodTimer`].
         $(
             #[cfg_attr(docsrs, doc(cfg($($doc)*)))]
         )?
@@ -142,10 +143,6 @@ macro_rules! make_timer {
 
         use ral::gpt::$timer;
 
-        /// Number of 2^31 periods elapsed since boot.
-        static $period: AtomicU32 = AtomicU32::new(0);
-        static $tq: TimerQueue<$backend_name> = TimerQueue::new();
-
         impl $backend_name {
             /// Starts the timer.
             ///
@@ -156,8 +153,6 @@ macro_rules! make_timer {
 
                 // Disable the timer.
                 ral::modify_reg!(ral::gpt, gpt, CR, EN: 0);
-                // Clear all status registers.
-                ral::write_reg!(ral::gpt, gpt, SR, 0b11_1111);
 
                 // Base configuration
                 ral::modify_reg!(ral::gpt, gpt, CR,
@@ -165,16 +160,6 @@ macro_rules! make_timer {
                     FRR: 1,     // Free-Run mode
                 );
 
-                // Reset period
-                $period.store(0, Ordering::SeqCst);
-
-                // Enable interrupts
-                ral::write_reg!(ral::gpt, gpt, IR,
-                    ROVIE: 1,   // Rollover interrupt
-                    OF1IE: 1,   // Timer compare 1 interrupt (for half-periods)
-                    OF2IE: 1,   // Timer compare 2 interrupt (for dynamic wakeup)
-                );
-
                 // Configure half-period interrupt
                 ral::write_reg!(ral::gpt, gpt, OCR[0], 0x8000_0000);
 
@@ -182,14 +167,7 @@ macro_rules! make_timer {
                 // so it gets combined with rollover interrupt
                 ral::write_reg!(ral::gpt, gpt, OCR[1], 0x0000_0000);
 
-                // Initialize timer queue
-                $tq.initialize(Self {});
-
-                // Enable the timer
-                ral::modify_reg!(ral::gpt, gpt, CR, EN: 1);
-                ral::modify_reg!(ral::gpt, gpt, CR,
-                    ENMOD: 0,   // Keep state when disabled
-                );
+                HalfPeriodMonotonic::<Self>::start();
 
                 // SAFETY: We take full ownership of the peripheral and interrupt vector,
                 // plus we are not using any external shared resources so we won't impact
@@ -201,67 +179,82 @@ macro_rules! make_timer {
             }
         }
 
-        impl TimerQueueBackend for $backend_name {
-            type Ticks = u64;
-
-            fn now() -> Self::Ticks {
-                let gpt = unsafe{ $timer::instance() };
+        impl HalfPeriodTimer for $backend_name {
+            type Counter = u32;
 
-                calculate_now(
-                    || $period.load(Ordering::Relaxed),
-                    || ral::read_reg!(ral::gpt, gpt, CNT)
-                )
+            fn read_counter() -> u32 {
+                let gpt = unsafe { $timer::instance() };
+                ral::read_reg!(ral::gpt, gpt, CNT)
             }
 
-            fn set_compare(instant: Self::Ticks) {
-                let gpt = unsafe{ $timer::instance() };
+            fn set_compare(value: u64) {
+                let gpt = unsafe { $timer::instance() };
 
                 // Set the timer regardless of whether it is multiple periods in the future,
                 // or even already in the past.
                 // The worst thing that can happen is a spurious wakeup, and with a timer
                 // period of half an hour, this is hardly a problem.
-
-                let ticks_wrapped = instant as u32;
-
-                ral::write_reg!(ral::gpt, gpt, OCR[1], ticks_wrapped);
+                ral::write_reg!(ral::gpt, gpt, OCR[1], value as u32);
             }
 
             fn clear_compare_flag() {
-                let gpt = unsafe{ $timer::instance() };
+                let gpt = unsafe { $timer::instance() };
                 ral::write_reg!(ral::gpt, gpt, SR, OF2: 1);
             }
 
-            fn pend_interrupt() {
-                cortex_m::peripheral::NVIC::pend(ral::Interrupt::$timer);
-            }
-
-            fn on_interrupt() {
-                let gpt = unsafe{ $timer::instance() };
-
-                let (rollover, half_rollover) = ral::read_reg!(ral::gpt, gpt, SR, ROV, OF1);
-
-                if rollover != 0 {
-                    let prev = $period.fetch_add(1, Ordering::Relaxed);
+            fn take_overflow_flag() -> bool {
+                let gpt = unsafe { $timer::instance() };
+                let rollover = ral::read_reg!(ral::gpt, gpt, SR, ROV) != 0;
+                if rollover {
                     ral::write_reg!(ral::gpt, gpt, SR, ROV: 1);
-                    assert!(prev % 2 == 1, "Monotonic must have skipped an interrupt!");
                 }
+                rollover
+            }
 
-                if half_rollover != 0 {
-                    let prev = $period.fetch_add(1, Ordering::Relaxed);
+            // A compare match on channel 1 is a half-period overflow
+            fn take_half_period_flag() -> bool {
+                let gpt = unsafe { $timer::instance() };
+                let half_rollover = ral::read_reg!(ral::gpt, gpt, SR, OF1) != 0;
+                if half_rollover {
                     ral::write_reg!(ral::gpt, gpt, SR, OF1: 1);
-                    assert!(prev % 2 == 0, "Monotonic must have skipped an interrupt!");
                 }
+                half_rollover
+            }
+
+            fn start() {
+                let gpt = unsafe { $timer::instance() };
+
+                // Clear all status registers.
+                ral::write_reg!(ral::gpt, gpt, SR, 0b11_1111);
+
+                // Enable interrupts
+                ral::write_reg!(ral::gpt, gpt, IR,
+                    ROVIE: 1,   // Rollover interrupt
+                    OF1IE: 1,   // Timer compare 1 interrupt (for half-periods)
+                    OF2IE: 1,   // Timer compare 2 interrupt (for dynamic wakeup)
+                );
+
+                // Enable the timer, `ENMOD` resets the counter
+                ral::modify_reg!(ral::gpt, gpt, CR, EN: 1);
+                ral::modify_reg!(ral::gpt, gpt, CR,
+                    ENMOD: 0,   // Keep state when disabled
+                );
+            }
+
+            fn pend_interrupt() {
+                cortex_m::peripheral::NVIC::pend(ral::Interrupt::$timer);
             }
 
-            fn timer_queue() -> &'static TimerQueue<Self> {
-                &$tq
+            fn state() -> &'static HalfPeriodState<Self> {
+                static STATE: HalfPeriodState<$backend_name> = HalfPeriodState::new();
+                &STATE
             }
         }
     };
 }
 
 #[cfg(feature = "imxrt_gpt1")]
-make_timer!(Gpt1, Gpt1Backend, GPT1, GPT1_HALFPERIODS, GPT1_TQ);
+make_timer!(Gpt1Backend, GPT1);
 
 #[cfg(feature = "imxrt_gpt2")]
-make_timer!(Gpt2, Gpt2Backend, GPT2, GPT2_HALFPERIODS, GPT2_TQ);
+make_timer!(Gpt2Backend, GPT2);
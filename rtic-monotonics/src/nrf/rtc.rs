@@ -61,11 +61,7 @@ pub use nrf5340_net_pac::{self as pac, RTC0_NS as RTC0, RTC1_NS as RTC1};
 #[doc(hidden)]
 pub use nrf9160_pac::{self as pac, RTC0_NS as RTC0, RTC1_NS as RTC1};
 
-use portable_atomic::{AtomicU32, Ordering};
-use rtic_time::{
-    half_period_counter::calculate_now,
-    timer_queue::{TimerQueue, TimerQueueBackend},
-};
+use rtic_time::half_period_monotonic::{HalfPeriodMonotonic, HalfPeriodState, HalfPeriodTimer};
 
 #[doc(hidden)]
 #[macro_export]
@@ -75,7 +71,10 @@ macro_rules! __internal_create_nrf_rtc_interrupt {
         #[allow(non_snake_case)]
         unsafe extern "C" fn $rtc() {
             use $crate::TimerQueueBackend;
-            $crate::nrf::rtc::$mono_backend::timer_queue().on_monotonic_interrupt();
+            $crate::rtic_time::half_period_monotonic::HalfPeriodMonotonic::<
+                $crate::nrf::rtc::$mono_backend,
+            >::timer_queue()
+            .on_monotonic_interrupt();
         }
     };
 }
@@ -99,7 +98,9 @@ macro_rules! __internal_create_nrf_rtc_struct {
         }
 
         impl $crate::TimerQueueBasedMonotonic for $name {
-            type Backend = $crate::nrf::rtc::$mono_backend;
+            type Backend = $crate::rtic_time::half_period_monotonic::HalfPeriodMonotonic<
+                $crate::nrf::rtc::$mono_backend,
+            >;
             type Instant = $crate::fugit::Instant<
                 <Self::Backend as $crate::TimerQueueBackend>::Ticks,
                 1,
@@ -152,10 +153,14 @@ macro_rules! nrf_rtc2_monotonic {
     };
 }
 
-struct TimerValueU24(u32);
+/// The 24-bit counter of the RTC.
+#[derive(Clone, Copy)]
+pub struct TimerValueU24(u32);
+
 impl rtic_time::half_period_counter::TimerValue for TimerValueU24 {
     const BITS: u32 = 24;
 }
+
 impl From<TimerValueU24> for u64 {
     fn from(value: TimerValueU24) -> Self {
         Self::from(value.0)
@@ -163,16 +168,13 @@ impl From<TimerValueU24> for u64 {
 }
 
 macro_rules! make_rtc {
-    ($backend_name:ident, $rtc:ident, $overflow:ident, $tq:ident$(, doc: ($($doc:tt)*))?) => {
-        /// RTC based [`TimerQueueBackend`].
+    ($backend_name:ident, $rtc:ident$(, doc: ($($doc:tt)*))?) => {
+        /// RTC based [`HalfPeriodTimer`].
         $(
             #[cfg_attr(docsrs, doc(cfg($($doc)*)))]
         )?
         pub struct $backend_name;
 
-        static $overflow: AtomicU32 = AtomicU32::new(0);
-        static $tq: TimerQueue<$backend_name> = TimerQueue::new();
-
         impl $backend_name {
             /// Starts the timer.
             ///
@@ -193,37 +195,7 @@ macro_rules! make_rtc {
                 rtc.cc[0].write(|w| unsafe { w.bits(0) }); // Dynamic wakeup
                 rtc.cc[1].write(|w| unsafe { w.bits(0x80_0000) }); // Half-period
 
-                // Timing critical, make sure we don't get interrupted
-                critical_section::with(|_|{
-                    // Reset the timer
-                    rtc.tasks_clear.write(|w| unsafe { w.bits(1) });
-                    rtc.tasks_start.write(|w| unsafe { w.bits(1) });
-
-                    // Clear pending events.
-                    // Should be close enough to the timer reset that we don't miss any events.
-                    rtc.events_ovrflw.write(|w| w);
-                    rtc.events_compare[0].write(|w| w);
-                    rtc.events_compare[1].write(|w| w);
-
-                    // Make sure overflow counter is synced with the timer value
-                    $overflow.store(0, Ordering::SeqCst);
-
-                    // Initialized the timer queue
-                    $tq.initialize(Self {});
-
-                    // Enable interrupts.
-                    // Should be close enough to the timer reset that we don't miss any events.
-                    rtc.intenset.write(|w| w
-                        .compare0().set()
-                        .compare1().set()
-                        .ovrflw().set()
-                    );
-                    rtc.evtenset.write(|w| w
-                        .compare0().set()
-                        .compare1().set()
-                        .ovrflw().set()
-                    );
-                });
+                HalfPeriodMonotonic::<Self>::start();
 
                 // SAFETY: We take full ownership of the peripheral and interrupt vector,
                 // plus we are not using any external shared resources so we won't impact
@@ -235,32 +207,15 @@ macro_rules! make_rtc {
             }
         }
 
-        impl TimerQueueBackend for $backend_name {
-            type Ticks = u64;
+        impl HalfPeriodTimer for $backend_name {
+            type Counter = TimerValueU24;
 
-            fn now() -> Self::Ticks {
+            fn read_counter() -> TimerValueU24 {
                 let rtc = unsafe { &*$rtc::PTR };
-                calculate_now(
-                    || $overflow.load(Ordering::Relaxed),
-                    || TimerValueU24(rtc.counter.read().bits())
-                )
+                TimerValueU24(rtc.counter.read().bits())
             }
 
-            fn on_interrupt() {
-                let rtc = unsafe { &*$rtc::PTR };
-                if rtc.events_ovrflw.read().bits() == 1 {
-                    rtc.events_ovrflw.write(|w| unsafe { w.bits(0) });
-                    let prev = $overflow.fetch_add(1, Ordering::Relaxed);
-                    assert!(prev % 2 == 1, "Monotonic must have skipped an interrupt!");
-                }
-                if rtc.events_compare[1].read().bits() == 1 {
-                    rtc.events_compare[1].write(|w| unsafe { w.bits(0) });
-                    let prev = $overflow.fetch_add(1, Ordering::Relaxed);
-                    assert!(prev % 2 == 0, "Monotonic must have skipped an interrupt!");
-                }
-            }
-
-            fn set_compare(mut instant: Self::Ticks) {
+            fn set_compare(mut value: u64) {
                 let rtc = unsafe { &*$rtc::PTR };
 
                 const MAX: u64 = 0xff_ffff;
@@ -269,26 +224,17 @@ macro_rules! make_rtc {
                 // We rely on the fact that this entire section runs within one
                 // RTC clock tick. (which it will do easily if it doesn't get
                 // interrupted)
-                critical_section::with(|_|{
-                    let now = Self::now();
-                    // wrapping_sub deals with the u64 overflow corner case
-                    let diff = instant.wrapping_sub(now);
-                    let val = if diff <= MAX {
-                        // Now we know `instant` whill happen within one `MAX` time duration.
-
-                        // Errata: Timer interrupts don't fire if they are scheduled less than
-                        // two ticks in the future. Make it three, because the timer could
-                        // tick right now.
-                        if diff < 3 {
-                            instant = now.wrapping_add(3);
-                        }
-
-                        (instant & MAX) as u32
-                    } else {
-                        0
-                    };
-
-                    unsafe { rtc.cc[0].write(|w| w.bits(val)) };
+                critical_section::with(|_| {
+                    let now = u64::from(rtc.counter.read().bits());
+
+                    // Errata: Timer interrupts don't fire if they are scheduled less than
+                    // two ticks in the future. Make it three, because the timer could
+                    // tick right now.
+                    if value.wrapping_sub(now) & MAX < 3 {
+                        value = now.wrapping_add(3) & MAX;
+                    }
+
+                    unsafe { rtc.cc[0].write(|w| w.bits(value as u32)) };
                 });
             }
 
@@ -297,18 +243,65 @@ macro_rules! make_rtc {
                 unsafe { rtc.events_compare[0].write(|w| w.bits(0)) };
             }
 
+            fn take_overflow_flag() -> bool {
+                let rtc = unsafe { &*$rtc::PTR };
+                let flag = rtc.events_ovrflw.read().bits() == 1;
+                if flag {
+                    rtc.events_ovrflw.write(|w| unsafe { w.bits(0) });
+                }
+                flag
+            }
+
+            // A compare match on channel 1 is a half-period overflow
+            fn take_half_period_flag() -> bool {
+                let rtc = unsafe { &*$rtc::PTR };
+                let flag = rtc.events_compare[1].read().bits() == 1;
+                if flag {
+                    rtc.events_compare[1].write(|w| unsafe { w.bits(0) });
+                }
+                flag
+            }
+
+            fn start() {
+                let rtc = unsafe { &*$rtc::PTR };
+
+                // Reset the timer
+                rtc.tasks_clear.write(|w| unsafe { w.bits(1) });
+                rtc.tasks_start.write(|w| unsafe { w.bits(1) });
+
+                // Clear pending events.
+                // Should be close enough to the timer reset that we don't miss any events.
+                rtc.events_ovrflw.write(|w| w);
+                rtc.events_compare[0].write(|w| w);
+                rtc.events_compare[1].write(|w| w);
+
+                // Enable interrupts.
+                // Should be close enough to the timer reset that we don't miss any events.
+                rtc.intenset.write(|w| w
+                    .compare0().set()
+                    .compare1().set()
+                    .ovrflw().set()
+                );
+                rtc.evtenset.write(|w| w
+                    .compare0().set()
+                    .compare1().set()
+                    .ovrflw().set()
+                );
+            }
+
             fn pend_interrupt() {
                 pac::NVIC::pend(pac::Interrupt::$rtc);
             }
 
-            fn timer_queue() -> &'static TimerQueue<Self> {
-                &$tq
+            fn state() -> &'static HalfPeriodState<Self> {
+                static STATE: HalfPeriodState<$backend_name> = HalfPeriodState::new();
+                &STATE
             }
         }
     };
 }
 
-make_rtc!(Rtc0Backend, RTC0, RTC0_OVERFLOWS, RTC0_TQ);
-make_rtc!(Rtc1Backend, RTC1, RTC1_OVERFLOWS, RTC1_TQ);
+make_rtc!(Rtc0Backend, RTC0);
+make_rtc!(Rtc1Backend, RTC1);
 #[cfg(any(feature = "nrf52832", feature = "nrf52833", feature = "nrf52840"))]
-make_rtc!(Rtc2Backend, RTC2, RTC2_OVERFLOWS, RTC2_TQ, doc: (any(feature = "nrf52832", feature = "nrf52833", feature = "nrf52840")));
+make_rtc!(Rtc2Backend, RTC2, doc: (any(feature = "nrf52832", feature = "nrf52833", feature = "nrf52840")));
@@ -71,11 +71,7 @@ pub use nrf5340_net_pac::{
 #[doc(hidden)]
 pub use nrf9160_pac::{self as pac, TIMER0_NS as TIMER0, TIMER1_NS as TIMER1, TIMER2_NS as TIMER2};
 
-use portable_atomic::{AtomicU32, Ordering};
-use rtic_time::{
-    half_period_counter::calculate_now,
-    timer_queue::{TimerQueue, TimerQueueBackend},
-};
+use rtic_time::half_period_monotonic::{HalfPeriodMonotonic, HalfPeriodState, HalfPeriodTimer};
 
 #[doc(hidden)]
 #[macro_export]
@@ -85,7 +81,10 @@ macro_rules! __internal_create_nrf_timer_interrupt {
         #[allow(non_snake_case)]
         unsafe extern "C" fn $timer() {
             use $crate::TimerQueueBackend;
-            $crate::nrf::timer::$mono_backend::timer_queue().on_monotonic_interrupt();
+            $crate::rtic_time::half_period_monotonic::HalfPeriodMonotonic::<
+                $crate::nrf::timer::$mono_backend,
+            >::timer_queue()
+            .on_monotonic_interrupt();
         }
     };
 }
@@ -123,7 +122,9 @@ macro_rules! __internal_create_nrf_timer_struct {
         }
 
         impl $crate::TimerQueueBasedMonotonic for $name {
-            type Backend = $crate::nrf::timer::$mono_backend;
+            type Backend = $crate::rtic_time::half_period_monotonic::HalfPeriodMonotonic<
+                $crate::nrf::timer::$mono_backend,
+            >;
             type Instant = $crate::fugit::Instant<
                 <Self::Backend as $crate::TimerQueueBackend>::Ticks,
                 1,
@@ -202,16 +203,13 @@ macro_rules! nrf_timer4_monotonic {
 }
 
 macro_rules! make_timer {
-    ($backend_name:ident, $timer:ident, $overflow:ident, $tq:ident$(, doc: ($($doc:tt)*))?) => {
-        /// Timer peripheral based [`TimerQueueBackend`].
+    ($backend_name:ident, $timer:ident$(, doc: ($($doc:tt)*))?) => {
+        /// Timer peripheral based [`HalfPeriodTimer`].
         $(
             #[cfg_attr(docsrs, doc(cfg($($doc)*)))]
         )?
         pub struct $backend_name;
 
-        static $overflow: AtomicU32 = AtomicU32::new(0);
-        static $tq: TimerQueue<$backend_name> = TimerQueue::new();
-
         impl $backend_name {
             /// Starts the timer.
             ///
@@ -234,32 +232,7 @@ macro_rules! make_timer {
                 timer.cc[1].write(|w| unsafe { w.cc().bits(0x0000_0000) }); // Overflow
                 timer.cc[2].write(|w| unsafe { w.cc().bits(0x8000_0000) }); // Half-period
 
-                // Timing critical, make sure we don't get interrupted
-                critical_section::with(|_|{
-                    // Reset the timer
-                    timer.tasks_clear.write(|w| unsafe { w.bits(1) });
-                    timer.tasks_start.write(|w| unsafe { w.bits(1) });
-
-                    // Clear pending events.
-                    // Should be close enough to the timer reset that we don't miss any events.
-                    timer.events_compare[0].write(|w| w);
-                    timer.events_compare[1].write(|w| w);
-                    timer.events_compare[2].write(|w| w);
-
-                    // Make sure overflow counter is synced with the timer value
-                    $overflow.store(0, Ordering::SeqCst);
-
-                    // Initialized the timer queue
-                    $tq.initialize(Self {});
-
-                    // Enable interrupts.
-                    // Should be close enough to the timer reset that we don't miss any events.
-                    timer.intenset.modify(|_, w| w
-                        .compare0().set()
-                        .compare1().set()
-                        .compare2().set()
-                    );
-                });
+                HalfPeriodMonotonic::<Self>::start();
 
                 // SAFETY: We take full ownership of the peripheral and interrupt vector,
                 // plus we are not using any external shared resources so we won't impact
@@ -271,64 +244,83 @@ macro_rules! make_timer {
             }
         }
 
-        impl TimerQueueBackend for $backend_name {
-            type Ticks = u64;
+        impl HalfPeriodTimer for $backend_name {
+            type Counter = u32;
 
-            fn now() -> Self::Ticks {
+            fn read_counter() -> u32 {
                 let timer = unsafe { &*$timer::PTR };
+                timer.tasks_capture[3].write(|w| unsafe { w.bits(1) });
+                timer.cc[3].read().bits()
+            }
 
-                calculate_now(
-                    || $overflow.load(Ordering::Relaxed),
-                    || {
-                        timer.tasks_capture[3].write(|w| unsafe { w.bits(1) });
-                        timer.cc[3].read().bits()
-                    }
-                )
+            fn set_compare(value: u64) {
+                let timer = unsafe { &*$timer::PTR };
+                timer.cc[0].write(|w| unsafe { w.cc().bits(value as u32) });
             }
 
-            fn on_interrupt() {
+            fn clear_compare_flag() {
                 let timer = unsafe { &*$timer::PTR };
+                timer.events_compare[0].write(|w| w);
+            }
 
-                // If there is a compare match on channel 1, it is an overflow
-                if timer.events_compare[1].read().bits() & 1 != 0 {
+            // A compare match on channel 1 is an overflow
+            fn take_overflow_flag() -> bool {
+                let timer = unsafe { &*$timer::PTR };
+                let flag = timer.events_compare[1].read().bits() & 1 != 0;
+                if flag {
                     timer.events_compare[1].write(|w| w);
-                    let prev = $overflow.fetch_add(1, Ordering::Relaxed);
-                    assert!(prev % 2 == 1, "Monotonic must have skipped an interrupt!");
                 }
+                flag
+            }
 
-                // If there is a compare match on channel 2, it is a half-period overflow
-                if timer.events_compare[2].read().bits() & 1 != 0 {
+            // A compare match on channel 2 is a half-period overflow
+            fn take_half_period_flag() -> bool {
+                let timer = unsafe { &*$timer::PTR };
+                let flag = timer.events_compare[2].read().bits() & 1 != 0;
+                if flag {
                     timer.events_compare[2].write(|w| w);
-                    let prev = $overflow.fetch_add(1, Ordering::Relaxed);
-                    assert!(prev % 2 == 0, "Monotonic must have skipped an interrupt!");
                 }
+                flag
             }
 
-            fn set_compare(instant: Self::Ticks) {
+            fn start() {
                 let timer = unsafe { &*$timer::PTR };
-                timer.cc[0].write(|w| unsafe { w.cc().bits(instant as u32) });
-            }
 
-            fn clear_compare_flag() {
-                let timer = unsafe { &*$timer::PTR };
+                // Reset the timer
+                timer.tasks_clear.write(|w| unsafe { w.bits(1) });
+                timer.tasks_start.write(|w| unsafe { w.bits(1) });
+
+                // Clear pending events.
+                // Should be close enough to the timer reset that we don't miss any events.
                 timer.events_compare[0].write(|w| w);
+                timer.events_compare[1].write(|w| w);
+                timer.events_compare[2].write(|w| w);
+
+                // Enable interrupts.
+                // Should be close enough to the timer reset that we don't miss any events.
+                timer.intenset.modify(|_, w| w
+                    .compare0().set()
+                    .compare1().set()
+                    .compare2().set()
+                );
             }
 
             fn pend_interrupt() {
                 pac::NVIC::pend(pac::Interrupt::$timer);
             }
 
-            fn timer_queue() -> &'static TimerQueue<$backend_name> {
-                &$tq
+            fn state() -> &'static HalfPeriodState<Self> {
+                static STATE: HalfPeriodState<$backend_name> = HalfPeriodState::new();
+                &STATE
             }
         }
     };
 }
 
-make_timer!(Timer0Backend, TIMER0, TIMER0_OVERFLOWS, TIMER0_TQ);
-make_timer!(Timer1Backend, TIMER1, TIMER1_OVERFLOWS, TIMER1_TQ);
-make_timer!(Timer2Backend, TIMER2, TIMER2_OVERFLOWS, TIMER2_TQ);
+make_timer!(Timer0Backend, TIMER0);
+make_timer!(Timer1Backend, TIMER1);
+make_timer!(Timer2Backend, TIMER2);
 #[cfg(any(feature = "nrf52832", feature = "nrf52833", feature = "nrf52840"))]
-make_timer!(Timer3Backend, TIMER3, TIMER3_OVERFLOWS, TIMER3_TQ, doc: (any(feature = "nrf52832", feature = "nrf52833", feature = "nrf52840")));
+make_timer!(Timer3Backend, TIMER3, doc: (any(feature = "nrf52832", feature = "nrf52833", feature = "nrf52840")));
 #[cfg(any(feature = "nrf52832", feature = "nrf52833", feature = "nrf52840"))]
-make_timer!(Timer4Backend, TIMER4, TIMER4_OVERFLOWS, TIMER4_TQ, doc: (any(feature = "nrf52832", feature = "nrf52833", feature = "nrf52840")));
+make_timer!(Timer4Backend, TIMER4, doc: (any(feature = "nrf52832", feature = "nrf52833", feature = "nrf52840")));
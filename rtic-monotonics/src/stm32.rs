@@ -51,11 +51,7 @@ pub mod prelude {
     pub use fugit::{self, ExtU64, ExtU64Ceil};
 }
 
-use portable_atomic::{AtomicU64, Ordering};
-use rtic_time::{
-    half_period_counter::calculate_now,
-    timer_queue::{TimerQueue, TimerQueueBackend},
-};
+use rtic_time::half_period_monotonic::{HalfPeriodMonotonic, HalfPeriodState, HalfPeriodTimer};
 use stm32_metapac as pac;
 
 mod _generated {
@@ -74,7 +70,10 @@ macro_rules! __internal_create_stm32_timer_interrupt {
         #[allow(non_snake_case)]
         unsafe extern "C" fn $interrupt_name() {
             use $crate::TimerQueueBackend;
-            $crate::stm32::$mono_backend::timer_queue().on_monotonic_interrupt();
+            $crate::rtic_time::half_period_monotonic::HalfPeriodMonotonic::<
+                $crate::stm32::$mono_backend,
+            >::timer_queue()
+            .on_monotonic_interrupt();
         }
     };
 }
@@ -103,7 +102,9 @@ macro_rules! __internal_create_stm32_timer_struct {
         }
 
         impl $crate::TimerQueueBasedMonotonic for $name {
-            type Backend = $crate::stm32::$mono_backend;
+            type Backend = $crate::rtic_time::half_period_monotonic::HalfPeriodMonotonic<
+                $crate::stm32::$mono_backend,
+            >;
             type Instant = $crate::fugit::Instant<
                 <Self::Backend as $crate::TimerQueueBackend>::Ticks,
                 1,
@@ -207,8 +208,8 @@ macro_rules! stm32_tim15_monotonic {
 }
 
 macro_rules! make_timer {
-    ($backend_name:ident, $timer:ident, $bits:ident, $overflow:ident, $tq:ident$(, doc: ($($doc:tt)*))?) => {
-        /// Monotonic timer backend implementation.
+    ($backend_name:ident, $timer:ident, $bits:ident$(, doc: ($($doc:tt)*))?) => {
+        /// Timer peripheral based [`HalfPeriodTimer`].
         $(
             #[cfg_attr(docsrs, doc(cfg($($doc)*)))]
         )?
@@ -217,9 +218,6 @@ macro_rules! make_timer {
 
         use pac::$timer;
 
-        static $overflow: AtomicU64 = AtomicU64::new(0);
-        static $tq: TimerQueue<$backend_name> = TimerQueue::new();
-
         impl $backend_name {
             /// Starts the timer.
             ///
@@ -246,17 +244,7 @@ macro_rules! make_timer {
                 // Trigger an update event to load the prescaler value to the clock.
                 $timer.egr().write(|r| r.set_ug(true));
 
-                // The above line raises an update event which will indicate that the timer is already finished.
-                // Since this is not the case, it should be cleared.
-                $timer.sr().modify(|r| r.set_uif(false));
-
-                $tq.initialize(Self {});
-                $overflow.store(0, Ordering::SeqCst);
-
-                // Start the counter.
-                $timer.cr1().modify(|r| {
-                    r.set_cen(true);
-                });
+                HalfPeriodMonotonic::<Self>::start();
 
                 // SAFETY: We take full ownership of the peripheral and interrupt vector,
                 // plus we are not using any external shared resources so we won't impact
@@ -268,80 +256,85 @@ macro_rules! make_timer {
             }
         }
 
-        impl TimerQueueBackend for $backend_name {
-            type Ticks = u64;
+        impl HalfPeriodTimer for $backend_name {
+            type Counter = $bits;
 
-            fn now() -> Self::Ticks {
-                calculate_now(
-                    || $overflow.load(Ordering::Relaxed),
-                    || $timer.cnt().read().cnt()
-                )
+            fn read_counter() -> $bits {
+                $timer.cnt().read().cnt() as $bits
             }
 
-            fn set_compare(instant: Self::Ticks) {
-                let now = Self::now();
-
-                // Since the timer may or may not overflow based on the requested compare val, we check how many ticks are left.
-                // `wrapping_sub` takes care of the u64 integer overflow special case.
-                let val = if instant.wrapping_sub(now) <= ($bits::MAX as u64) {
-                    instant as $bits
-                } else {
-                    // In the past or will overflow
-                    0
-                };
-
-                $timer.ccr(1).write(|r| r.set_ccr(val.into()));
+            fn set_compare(value: u64) {
+                $timer.ccr(1).write(|r| r.set_ccr((value as $bits).into()));
             }
 
             fn clear_compare_flag() {
                 $timer.sr().modify(|r| r.set_ccif(1, false));
             }
 
+            // The update event is the full-period overflow
+            fn take_overflow_flag() -> bool {
+                let flag = $timer.sr().read().uif();
+                if flag {
+                    $timer.sr().modify(|r| r.set_uif(false));
+                }
+                flag
+            }
+
+            // A compare match on channel 0 is a half-period overflow
+            fn take_half_period_flag() -> bool {
+                let flag = $timer.sr().read().ccif(0);
+                if flag {
+                    $timer.sr().modify(|r| r.set_ccif(0, false));
+                }
+                flag
+            }
+
+            fn start() {
+                // The update event raised to load the prescaler indicates that the timer is
+                // already finished. Since this is not the case, it should be cleared.
+                $timer.cnt().write(|r| r.set_cnt(0));
+                $timer.sr().modify(|r| {
+                    r.set_uif(false);
+                    r.set_ccif(0, false);
+                });
+
+                // Start the counter.
+                $timer.cr1().modify(|r| {
+                    r.set_cen(true);
+                });
+            }
+
             fn pend_interrupt() {
                 cortex_m::peripheral::NVIC::pend(pac::Interrupt::$timer);
             }
 
-            fn enable_timer() {
+            fn enable_compare() {
                 $timer.dier().modify(|r| r.set_ccie(1, true));
             }
 
-            fn disable_timer() {
+            fn disable_compare() {
                 $timer.dier().modify(|r| r.set_ccie(1, false));
             }
 
-            fn on_interrupt() {
-                // Full period
-                if $timer.sr().read().uif() {
-                    $timer.sr().modify(|r| r.set_uif(false));
-                    let prev = $overflow.fetch_add(1, Ordering::Relaxed);
-                    assert!(prev % 2 == 1, "Monotonic must have missed an interrupt!");
-                }
-                // Half period
-                if $timer.sr().read().ccif(0) {
-                    $timer.sr().modify(|r| r.set_ccif(0, false));
-                    let prev = $overflow.fetch_add(1, Ordering::Relaxed);
-                    assert!(prev % 2 == 0, "Monotonic must have missed an interrupt!");
-                }
-            }
-
-            fn timer_queue() -> &'static TimerQueue<$backend_name> {
-                &$tq
+            fn state() -> &'static HalfPeriodState<Self> {
+                static STATE: HalfPeriodState<$backend_name> = HalfPeriodState::new();
+                &STATE
             }
         }
     };
 }
 
 #[cfg(feature = "stm32_tim2")]
-make_timer!(Tim2Backend, TIM2, u32, TIMER2_OVERFLOWS, TIMER2_TQ);
+make_timer!(Tim2Backend, TIM2, u32);
 
 #[cfg(feature = "stm32_tim3")]
-make_timer!(Tim3Backend, TIM3, u16, TIMER3_OVERFLOWS, TIMER3_TQ);
+make_timer!(Tim3Backend, TIM3, u16);
 
 #[cfg(feature = "stm32_tim4")]
-make_timer!(Tim4Backend, TIM4, u16, TIMER4_OVERFLOWS, TIMER4_TQ);
+make_timer!(Tim4Backend, TIM4, u16);
 
 #[cfg(feature = "stm32_tim5")]
-make_timer!(Tim5Backend, TIM5, u16, TIMER5_OVERFLOWS, TIMER5_TQ);
+make_timer!(Tim5Backend, TIM5, u16);
 
 #[cfg(feature = "stm32_tim15")]
-make_timer!(Tim15Backend, TIM15, u16, TIMER15_OVERFLOWS, TIMER15_TQ);
+make_timer!(Tim15Backend, TIM15, u16);
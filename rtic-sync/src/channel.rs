@@ -11,7 +11,15 @@ use core::{
 };
 #[doc(hidden)]
 pub use critical_section;
+#[cfg(not(feature = "lock-free-queues"))]
 use heapless::Deque;
+#[cfg(feature = "lock-free-queues")]
+use portable_atomic::{AtomicBool, AtomicUsize};
+#[cfg(feature = "lock-free-queues")]
+use rtic_common::lock_free_queue::LockFreeQueue;
+#[cfg(feature = "lock-free-queues")]
+use rtic_common::waker_registration::AtomicWakerRegistration as WakerRegistration;
+#[cfg(not(feature = "lock-free-queues"))]
 use rtic_common::waker_registration::CriticalSectionWakerRegistration as WakerRegistration;
 use rtic_common::{
     dropper::OnDrop,
@@ -25,11 +33,20 @@ use crate::defmt;
 ///
 /// This channel uses critical sections, however there are extremely small and all `memcpy`
 /// operations of `T` are done without critical sections.
+///
+/// With the `lock-free-queues` feature the queues and counters are lock-free instead, only a
+/// `send` waiting for room in the queue takes critical sections.
 pub struct Channel<T, const N: usize> {
     // Here are all indexes that are not used in `slots` and ready to be allocated.
+    #[cfg(not(feature = "lock-free-queues"))]
     freeq: UnsafeCell<Deque<u8, N>>,
+    #[cfg(feature = "lock-free-queues")]
+    freeq: LockFreeQueue<u8, N>,
     // Here are wakers and indexes to slots that are ready to be dequeued by the receiver.
+    #[cfg(not(feature = "lock-free-queues"))]
     readyq: UnsafeCell<Deque<u8, N>>,
+    #[cfg(feature = "lock-free-queues")]
+    readyq: LockFreeQueue<u8, N>,
     // Waker for the receiver.
     receiver_waker: WakerRegistration,
     // Storage for N `T`s, so we don't memcpy around a lot of `T`s.
@@ -37,15 +54,22 @@ pub struct Channel<T, const N: usize> {
     // If there is no room in the queue a `Sender`s can wait for there to be place in the queue.
     wait_queue: WaitQueue,
     // Keep track of the receiver.
+    #[cfg(not(feature = "lock-free-queues"))]
     receiver_dropped: UnsafeCell<bool>,
+    #[cfg(feature = "lock-free-queues")]
+    receiver_dropped: AtomicBool,
     // Keep track of the number of senders.
+    #[cfg(not(feature = "lock-free-queues"))]
     num_senders: UnsafeCell<usize>,
+    #[cfg(feature = "lock-free-queues")]
+    num_senders: AtomicUsize,
 }
 
 unsafe impl<T, const N: usize> Send for Channel<T, N> {}
 
 unsafe impl<T, const N: usize> Sync for Channel<T, N> {}
 
+#[cfg(not(feature = "lock-free-queues"))]
 struct UnsafeAccess<'a, const N: usize> {
     freeq: &'a mut Deque<u8, N>,
     readyq: &'a mut Deque<u8, N>,
@@ -63,6 +87,7 @@ impl<T, const N: usize> Channel<T, N> {
     const _CHECK: () = assert!(N < 256, "This queue support a maximum of 255 entries");
 
     /// Create a new channel.
+    #[cfg(not(feature = "lock-free-queues"))]
     pub const fn new() -> Self {
         Self {
             freeq: UnsafeCell::new(Deque::new()),
@@ -75,7 +100,22 @@ impl<T, const N: usize> Channel<T, N> {
         }
     }
 
+    /// Create a new channel.
+    #[cfg(feature = "lock-free-queues")]
+    pub const fn new() -> Self {
+        Self {
+            freeq: LockFreeQueue::new(),
+            readyq: LockFreeQueue::new(),
+            receiver_waker: WakerRegistration::new(),
+            slots: [const { UnsafeCell::new(MaybeUninit::uninit()) }; N],
+            wait_queue: WaitQueue::new(),
+            receiver_dropped: AtomicBool::new(false),
+            num_senders: AtomicUsize::new(0),
+        }
+    }
+
     /// Split the queue into a `Sender`/`Receiver` pair.
+    #[cfg(not(feature = "lock-free-queues"))]
     pub fn split(&mut self) -> (Sender<'_, T, N>, Receiver<'_, T, N>) {
         // Fill free queue
         for idx in 0..N as u8 {
@@ -95,6 +135,25 @@ impl<T, const N: usize> Channel<T, N> {
         (Sender(self), Receiver(self))
     }
 
+    /// Split the queue into a `Sender`/`Receiver` pair.
+    #[cfg(feature = "lock-free-queues")]
+    pub fn split(&mut self) -> (Sender<'_, T, N>, Receiver<'_, T, N>) {
+        // Fill free queue
+        for idx in 0..N as u8 {
+            assert!(self.freeq.push(idx).is_ok());
+        }
+
+        assert!(self.freeq.is_full());
+
+        // There is now 1 sender
+        *self.num_senders.get_mut() = 1;
+
+        (Sender(self), Receiver(self))
+    }
+}
+
+#[cfg(not(feature = "lock-free-queues"))]
+impl<T, const N: usize> Channel<T, N> {
     fn access<'a>(&'a self, _cs: critical_section::CriticalSection) -> UnsafeAccess<'a, N> {
         // SAFETY: This is safe as are in a critical section.
         unsafe {
@@ -106,6 +165,127 @@ impl<T, const N: usize> Channel<T, N> {
             }
         }
     }
+
+    fn pop_free(&self) -> Option<u8> {
+        critical_section::with(|cs| self.access(cs).freeq.pop_front())
+    }
+
+    fn push_free(&self, idx: u8) {
+        critical_section::with(|cs| {
+            assert!(!self.access(cs).freeq.is_full());
+            unsafe { self.access(cs).freeq.push_back_unchecked(idx) }
+        });
+    }
+
+    fn pop_ready(&self) -> Option<u8> {
+        critical_section::with(|cs| self.access(cs).readyq.pop_front())
+    }
+
+    fn push_ready(&self, idx: u8) {
+        critical_section::with(|cs| {
+            assert!(!self.access(cs).readyq.is_full());
+            unsafe { self.access(cs).readyq.push_back_unchecked(idx) }
+        });
+    }
+
+    fn free_is_empty(&self) -> bool {
+        critical_section::with(|cs| self.access(cs).freeq.is_empty())
+    }
+
+    fn free_is_full(&self) -> bool {
+        critical_section::with(|cs| self.access(cs).freeq.is_full())
+    }
+
+    fn ready_is_empty(&self) -> bool {
+        critical_section::with(|cs| self.access(cs).readyq.is_empty())
+    }
+
+    fn ready_is_full(&self) -> bool {
+        critical_section::with(|cs| self.access(cs).readyq.is_full())
+    }
+
+    fn receiver_dropped(&self) -> bool {
+        critical_section::with(|cs| *self.access(cs).receiver_dropped)
+    }
+
+    fn drop_receiver(&self) {
+        critical_section::with(|cs| *self.access(cs).receiver_dropped = true);
+    }
+
+    fn num_senders(&self) -> usize {
+        critical_section::with(|cs| *self.access(cs).num_senders)
+    }
+
+    fn add_sender(&self) {
+        critical_section::with(|cs| *self.access(cs).num_senders += 1);
+    }
+
+    /// Returns the number of senders left.
+    fn remove_sender(&self) -> usize {
+        critical_section::with(|cs| {
+            *self.access(cs).num_senders -= 1;
+
+            *self.access(cs).num_senders
+        })
+    }
+}
+
+// The free queue has a slot for each index, so pushing an index taken from it never fails, the
+// same goes for the ready queue.
+#[cfg(feature = "lock-free-queues")]
+impl<T, const N: usize> Channel<T, N> {
+    fn pop_free(&self) -> Option<u8> {
+        self.freeq.pop()
+    }
+
+    fn push_free(&self, idx: u8) {
+        assert!(self.freeq.push(idx).is_ok());
+    }
+
+    fn pop_ready(&self) -> Option<u8> {
+        self.readyq.pop()
+    }
+
+    fn push_ready(&self, idx: u8) {
+        assert!(self.readyq.push(idx).is_ok());
+    }
+
+    fn free_is_empty(&self) -> bool {
+        self.freeq.is_empty()
+    }
+
+    fn free_is_full(&self) -> bool {
+        self.freeq.is_full()
+    }
+
+    fn ready_is_empty(&self) -> bool {
+        self.readyq.is_empty()
+    }
+
+    fn ready_is_full(&self) -> bool {
+        self.readyq.is_full()
+    }
+
+    fn receiver_dropped(&self) -> bool {
+        self.receiver_dropped.load(Ordering::Acquire)
+    }
+
+    fn drop_receiver(&self) {
+        self.receiver_dropped.store(true, Ordering::Release);
+    }
+
+    fn num_senders(&self) -> usize {
+        self.num_senders.load(Ordering::Acquire)
+    }
+
+    fn add_sender(&self) {
+        self.num_senders.fetch_add(1, Ordering::AcqRel);
+    }
+
+    /// Returns the number of senders left.
+    fn remove_sender(&self) -> usize {
+        self.num_senders.fetch_sub(1, Ordering::AcqRel) - 1
+    }
 }
 
 /// Creates a split channel with `'static` lifetime.
@@ -227,10 +407,7 @@ impl<'a, T, const N: usize> Sender<'a, T, N> {
         }
 
         // Write the value into the ready queue.
-        critical_section::with(|cs| {
-            assert!(!self.0.access(cs).readyq.is_full());
-            unsafe { self.0.access(cs).readyq.push_back_unchecked(idx) }
-        });
+        self.0.push_ready(idx);
 
         fence(Ordering::SeqCst);
 
@@ -250,12 +427,11 @@ impl<'a, T, const N: usize> Sender<'a, T, N> {
             return Err(TrySendError::NoReceiver(val));
         }
 
-        let idx =
-            if let Some(idx) = critical_section::with(|cs| self.0.access(cs).freeq.pop_front()) {
-                idx
-            } else {
-                return Err(TrySendError::Full(val));
-            };
+        let idx = if let Some(idx) = self.0.pop_free() {
+            idx
+        } else {
+            return Err(TrySendError::Full(val));
+        };
 
         self.send_footer(idx, val);
 
@@ -287,40 +463,34 @@ impl<'a, T, const N: usize> Sender<'a, T, N> {
             }
 
             //  Do all this in one critical section, else there can be race conditions
-            let queue_idx = critical_section::with(|cs| {
-                let wq_empty = self.0.wait_queue.is_empty();
-                let fq_empty = self.0.access(cs).freeq.is_empty();
-                if !wq_empty || fq_empty {
-                    // SAFETY: This pointer is only dereferenced here and on drop of the future
-                    // which happens outside this `poll_fn`'s stack frame.
-                    let link = unsafe { link_ptr.get() };
-                    if let Some(link) = link {
-                        if !link.is_popped() {
-                            return None;
-                        } else {
-                            // Fall through to dequeue
-                        }
-                    } else {
-                        // Place the link in the wait queue on first run.
-                        let link_ref = link.insert(Link::new(cx.waker().clone()));
-
-                        // SAFETY(new_unchecked): The address to the link is stable as it is defined
-                        // outside this stack frame.
-                        // SAFETY(push): `link_ref` lifetime comes from `link_ptr` that is shadowed,
-                        // and  we make sure in `dropper` that the link is removed from the queue
-                        // before dropping `link_ptr` AND `dropper` makes sure that the shadowed
-                        // `link_ptr` lives until the end of the stack frame.
-                        unsafe { self.0.wait_queue.push(Pin::new_unchecked(link_ref)) };
-
-                        return None;
+            let queue_idx = critical_section::with(|_| {
+                // SAFETY: This pointer is only dereferenced here and on drop of the future
+                // which happens outside this `poll_fn`'s stack frame.
+                let link = unsafe { link_ptr.get() };
+
+                // Only take a free slot if no one is waiting before us
+                let popped = link.as_ref().is_some_and(|link| link.is_popped());
+                if self.0.wait_queue.is_empty() || popped {
+                    if let Some(idx) = self.0.pop_free() {
+                        return Some(idx);
                     }
                 }
 
-                assert!(!self.0.access(cs).freeq.is_empty());
-                // Get index as the queue is guaranteed not empty and the wait queue is empty
-                let idx = unsafe { self.0.access(cs).freeq.pop_front_unchecked() };
+                // Place the link in the wait queue on first run, or again if the slot we were
+                // woken for was taken by a `try_send` in between.
+                if link.as_ref().is_none_or(|link| link.is_popped()) {
+                    let link_ref = link.insert(Link::new(cx.waker().clone()));
+
+                    // SAFETY(new_unchecked): The address to the link is stable as it is defined
+                    // outside this stack frame.
+                    // SAFETY(push): `link_ref` lifetime comes from `link_ptr` that is shadowed,
+                    // and  we make sure in `dropper` that the link is removed from the queue
+                    // before dropping `link_ptr` AND `dropper` makes sure that the shadowed
+                    // `link_ptr` lives until the end of the stack frame.
+                    unsafe { self.0.wait_queue.push(Pin::new_unchecked(link_ref)) };
+                }
 
-                Some(idx)
+                None
             });
 
             if let Some(idx) = queue_idx {
@@ -346,28 +516,24 @@ impl<'a, T, const N: usize> Sender<'a, T, N> {
 
     /// Returns true if there is no `Receiver`s.
     pub fn is_closed(&self) -> bool {
-        critical_section::with(|cs| *self.0.access(cs).receiver_dropped)
+        self.0.receiver_dropped()
     }
 
     /// Is the queue full.
     pub fn is_full(&self) -> bool {
-        critical_section::with(|cs| self.0.access(cs).freeq.is_empty())
+        self.0.free_is_empty()
     }
 
     /// Is the queue empty.
     pub fn is_empty(&self) -> bool {
-        critical_section::with(|cs| self.0.access(cs).freeq.is_full())
+        self.0.free_is_full()
     }
 }
 
 impl<'a, T, const N: usize> Drop for Sender<'a, T, N> {
     fn drop(&mut self) {
         // Count down the reference counter
-        let num_senders = critical_section::with(|cs| {
-            *self.0.access(cs).num_senders -= 1;
-
-            *self.0.access(cs).num_senders
-        });
+        let num_senders = self.0.remove_sender();
 
         // If there are no senders, wake the receiver to do error handling.
         if num_senders == 0 {
@@ -379,7 +545,7 @@ impl<'a, T, const N: usize> Drop for Sender<'a, T, N> {
 impl<'a, T, const N: usize> Clone for Sender<'a, T, N> {
     fn clone(&self) -> Self {
         // Count up the reference counter
-        critical_section::with(|cs| *self.0.access(cs).num_senders += 1);
+        self.0.add_sender();
 
         Self(self.0)
     }
@@ -419,17 +585,14 @@ impl<'a, T, const N: usize> Receiver<'a, T, N> {
     /// Receives a value if there is one in the channel, non-blocking.
     pub fn try_recv(&mut self) -> Result<T, ReceiveError> {
         // Try to get a ready slot.
-        let ready_slot = critical_section::with(|cs| self.0.access(cs).readyq.pop_front());
+        let ready_slot = self.0.pop_ready();
 
         if let Some(rs) = ready_slot {
             // Read the value from the slots, note; this memcpy is not under a critical section.
             let r = unsafe { ptr::read(self.0.slots.get_unchecked(rs as usize).get() as *const T) };
 
             // Return the index to the free queue after we've read the value.
-            critical_section::with(|cs| {
-                assert!(!self.0.access(cs).freeq.is_full());
-                unsafe { self.0.access(cs).freeq.push_back_unchecked(rs) }
-            });
+            self.0.push_free(rs);
 
             fence(Ordering::SeqCst);
 
@@ -473,24 +636,24 @@ impl<'a, T, const N: usize> Receiver<'a, T, N> {
 
     /// Returns true if there are no `Sender`s.
     pub fn is_closed(&self) -> bool {
-        critical_section::with(|cs| *self.0.access(cs).num_senders == 0)
+        self.0.num_senders() == 0
     }
 
     /// Is the queue full.
     pub fn is_full(&self) -> bool {
-        critical_section::with(|cs| self.0.access(cs).readyq.is_full())
+        self.0.ready_is_full()
     }
 
     /// Is the queue empty.
     pub fn is_empty(&self) -> bool {
-        critical_section::with(|cs| self.0.access(cs).readyq.is_empty())
+        self.0.ready_is_empty()
     }
 }
 
 impl<'a, T, const N: usize> Drop for Receiver<'a, T, N> {
     fn drop(&mut self) {
         // Mark the receiver as dropped and wake all waiters
-        critical_section::with(|cs| *self.0.access(cs).receiver_dropped = true);
+        self.0.drop_receiver();
 
         while let Some(waker) = self.0.wait_queue.pop() {
             waker.wake();
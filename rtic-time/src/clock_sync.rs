@@ -0,0 +1,430 @@
+//! Clock-drift compensation and synchronization to an external time reference.
+//!
+//! # Background
+//!
+//! The crystal driving a monotonic is never exactly at its nominal frequency; typical parts drift
+//! by tens of ppm with temperature and age. Two nodes that both count "1 MHz" will therefore
+//! disagree by tens of microseconds after only a second, even if they were started together.
+//!
+//! This module provides [`ClockSync`], which takes pairs of `(local instant, reference time)`
+//! measurements, for example from a PPS (pulse-per-second) input captured by a timer or from a
+//! network synchronization message, and steers a corrected time base towards the reference. The
+//! steering is done with a PI controller on the clock rate, the same approach as used by e.g.
+//! PTP servos:
+//!
+//! - The first measurement steps the corrected time straight to the reference, unless the
+//!   corrected time is already in use as a [`Monotonic`].
+//! - Every following measurement computes the phase error, and adjusts the rate of the corrected
+//!   clock such that the error is removed over the next measurement interval. The integral term
+//!   converges to the actual frequency error of the local oscillator.
+//!
+//! Once [`SynchronizedMonotonic`] has read the corrected time, it is only ever slewed, never
+//! stepped, so it never goes backwards: the first measurement, also after a
+//! [`reset`](ClockSync::reset), then starts the controller at the current corrected time and the
+//! initial offset is slewed away at up to `max_rate_ppb`. Feed the first measurement before
+//! using the monotonic to start with a step.
+//!
+//! # Example
+//!
+//! ```rust
+//! # mod base {
+//! #     pub struct Mono;
+//! #     impl rtic_time::Monotonic for Mono {
+//! #         type Instant = fugit::Instant<u64, 1, 1_000_000>;
+//! #         type Duration = fugit::Duration<u64, 1, 1_000_000>;
+//! #         fn now() -> Self::Instant { Self::Instant::from_ticks(0) }
+//! #         async fn delay(_: Self::Duration) {}
+//! #         async fn delay_until(_: Self::Instant) {}
+//! #         async fn timeout_at<F: core::future::Future>(_: Self::Instant, f: F) -> Result<F::Output, rtic_time::TimeoutError> { Ok(f.await) }
+//! #         async fn timeout_after<F: core::future::Future>(_: Self::Duration, f: F) -> Result<F::Output, rtic_time::TimeoutError> { Ok(f.await) }
+//! #     }
+//! # }
+//! # use base::Mono;
+//! use rtic_time::clock_sync::{ClockSync, ClockSyncConfig, ClockSyncSource, SynchronizedMonotonic};
+//! use rtic_time::Monotonic;
+//!
+//! static CLOCK_SYNC: ClockSync<Mono> = ClockSync::new(ClockSyncConfig::new());
+//!
+//! struct PpsSync;
+//!
+//! impl ClockSyncSource for PpsSync {
+//!     type Base = Mono;
+//!
+//!     fn clock_sync() -> &'static ClockSync<Mono> {
+//!         &CLOCK_SYNC
+//!     }
+//! }
+//!
+//! /// A monotonic that follows the PPS reference instead of the local crystal.
+//! type SyncedMono = SynchronizedMonotonic<PpsSync>;
+//!
+//! /// Called with the captured local timestamp of the `n`-th PPS edge.
+//! fn on_pps_edge(n: u64, captured: <Mono as Monotonic>::Instant) {
+//!     let reference = <Mono as Monotonic>::Instant::from_ticks(n * 1_000_000);
+//!     CLOCK_SYNC.update(captured, reference);
+//! }
+//!
+//! async fn usage() {
+//!     // Timestamps and delays now follow the reference time.
+//!     let timestamp = SyncedMono::now();
+//!     SyncedMono::delay_until(timestamp + fugit::ExtU64::millis(10)).await;
+//! }
+//! ```
+
+use core::cell::Cell;
+use core::future::{poll_fn, Future};
+use core::marker::PhantomData;
+use core::pin::pin;
+use core::task::Poll;
+
+use critical_section::Mutex;
+
+use crate::monotonic::{TimerQueueBasedDuration, TimerQueueBasedInstant};
+use crate::{Monotonic, TimeoutError};
+
+/// Parts per billion, the unit of all rate corrections.
+const PPB: i128 = 1_000_000_000;
+
+/// Tuning parameters of the PI controller used by [`ClockSync`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ClockSyncConfig {
+    /// Proportional gain in thousandths, i.e. `700` is a gain of `0.7`.
+    pub kp_permille: u32,
+    /// Integral gain in thousandths, i.e. `300` is a gain of `0.3`.
+    pub ki_permille: u32,
+    /// Maximum rate correction that will be applied, in parts per billion.
+    ///
+    /// Must be less than `1_000_000_000`, otherwise the corrected time could stand still.
+    pub max_rate_ppb: u32,
+}
+
+impl ClockSyncConfig {
+    /// The default configuration: `kp = 0.7`, `ki = 0.3` and a maximum correction of 500 ppm.
+    pub const fn new() -> Self {
+        Self {
+            kp_permille: 700,
+            ki_permille: 300,
+            max_rate_ppb: 500_000,
+        }
+    }
+}
+
+impl Default for ClockSyncConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Clone, Copy)]
+struct State {
+    /// Local time at which `anchor_reference` was valid.
+    anchor_local: u64,
+    /// Corrected time at `anchor_local`.
+    anchor_reference: u64,
+    /// Local time of the previous measurement.
+    last_local: u64,
+    /// Current rate correction in ppb.
+    rate_ppb: i64,
+    /// Integral term of the controller, the estimated frequency error in ppb.
+    drift_ppb: i64,
+    /// Number of measurements processed, saturating.
+    samples: u32,
+    /// The corrected time was read by `SynchronizedMonotonic`, it must not be stepped anymore.
+    used: bool,
+}
+
+impl State {
+    const fn new() -> Self {
+        Self {
+            anchor_local: 0,
+            anchor_reference: 0,
+            last_local: 0,
+            rate_ppb: 0,
+            drift_ppb: 0,
+            samples: 0,
+            used: false,
+        }
+    }
+
+    fn local_to_reference(self, local: u64) -> u64 {
+        let dt = local.wrapping_sub(self.anchor_local) as i64 as i128;
+        let correction = dt * self.rate_ppb as i128 / PPB;
+
+        self.anchor_reference
+            .wrapping_add(dt as u64)
+            .wrapping_add(correction as u64)
+    }
+
+    fn reference_to_local(self, reference: u64) -> u64 {
+        let dt = reference.wrapping_sub(self.anchor_reference) as i64 as i128;
+        let denom = PPB + self.rate_ppb as i128;
+
+        // Round towards the future so that waiting for the local instant always means
+        // the corrected instant has been reached.
+        let local_dt = (dt * PPB + denom - 1).div_euclid(denom);
+
+        self.anchor_local.wrapping_add(local_dt as u64)
+    }
+}
+
+/// Drift estimation and correction of a `Base` monotonic towards an external reference.
+///
+/// See the [module level documentation](crate::clock_sync) for more details.
+pub struct ClockSync<Base> {
+    config: ClockSyncConfig,
+    state: Mutex<Cell<State>>,
+    _base: PhantomData<Base>,
+}
+
+impl<Base> ClockSync<Base>
+where
+    Base: Monotonic,
+    Base::Instant: TimerQueueBasedInstant<Ticks = u64>,
+{
+    /// Create a new, unsynchronized clock. Until the first call to [`update`](Self::update),
+    /// the corrected time equals the local time.
+    ///
+    /// # Panics
+    ///
+    /// If `config.max_rate_ppb` is not less than `1_000_000_000`.
+    pub const fn new(config: ClockSyncConfig) -> Self {
+        assert!(
+            (config.max_rate_ppb as i128) < PPB,
+            "`max_rate_ppb` must be less than 1_000_000_000"
+        );
+
+        Self {
+            config,
+            state: Mutex::new(Cell::new(State::new())),
+            _base: PhantomData,
+        }
+    }
+
+    /// Feed a measurement of the reference time into the controller.
+    ///
+    /// `local` is the time of the base monotonic at which the reference time was `reference`,
+    /// for example the captured timestamp of a PPS edge. Measurements must be fed in
+    /// chronological order.
+    ///
+    /// Returns the measured offset `reference - corrected` in ticks, before the correction is
+    /// applied.
+    pub fn update(&self, local: Base::Instant, reference: Base::Instant) -> i64 {
+        let local = local.ticks();
+        let reference = reference.ticks();
+
+        critical_section::with(|cs| {
+            let cell = self.state.borrow(cs);
+            let mut state = cell.get();
+
+            let predicted = state.local_to_reference(local);
+            let offset = reference.wrapping_sub(predicted) as i64;
+
+            if state.samples == 0 {
+                state.anchor_local = local;
+                state.anchor_reference = if state.used {
+                    // The corrected time is in use as a monotonic, start the controller at the
+                    // current corrected time and slew the offset away.
+                    predicted
+                } else {
+                    // First measurement, step to the reference.
+                    reference
+                };
+                state.last_local = local;
+                state.samples = 1;
+                cell.set(state);
+
+                return offset;
+            }
+
+            let interval = local.wrapping_sub(state.last_local) as i64;
+            if interval <= 0 {
+                // Not newer than the previous measurement, nothing can be learned from it.
+                return offset;
+            }
+
+            let max = self.config.max_rate_ppb as i128;
+            let error_ppb = offset as i128 * PPB / interval as i128;
+            let drift = (state.drift_ppb as i128
+                + error_ppb * self.config.ki_permille as i128 / 1000)
+                .clamp(-max, max);
+            let rate =
+                (drift + error_ppb * self.config.kp_permille as i128 / 1000).clamp(-max, max);
+
+            // Re-anchor at the current corrected time so the corrected clock stays continuous,
+            // the phase error is removed by the new rate.
+            state.anchor_local = local;
+            state.anchor_reference = predicted;
+            state.last_local = local;
+            state.drift_ppb = drift as i64;
+            state.rate_ppb = rate as i64;
+            state.samples = state.samples.saturating_add(1);
+            cell.set(state);
+
+            offset
+        })
+    }
+
+    /// Forget all measurements, the corrected time will again equal the local time.
+    ///
+    /// If the corrected time is in use as a [`Monotonic`], it keeps its current offset to the
+    /// local time instead, so that it never jumps, and the next measurements slew it.
+    pub fn reset(&self) {
+        critical_section::with(|cs| {
+            let cell = self.state.borrow(cs);
+            let state = cell.get();
+
+            cell.set(if state.used {
+                let local = Base::now().ticks();
+                let reference = state.local_to_reference(local);
+
+                State {
+                    anchor_local: local,
+                    anchor_reference: reference,
+                    last_local: local,
+                    used: true,
+                    ..State::new()
+                }
+            } else {
+                State::new()
+            });
+        });
+    }
+
+    /// True if at least one measurement has been processed.
+    pub fn is_synchronized(&self) -> bool {
+        critical_section::with(|cs| self.state.borrow(cs).get().samples > 0)
+    }
+
+    /// The rate correction currently applied to the local time, in parts per billion.
+    ///
+    /// A positive value means the local oscillator is slower than the reference.
+    pub fn rate_ppb(&self) -> i64 {
+        critical_section::with(|cs| self.state.borrow(cs).get().rate_ppb)
+    }
+
+    /// The estimated frequency error of the local oscillator, in parts per billion.
+    pub fn drift_ppb(&self) -> i64 {
+        critical_section::with(|cs| self.state.borrow(cs).get().drift_ppb)
+    }
+
+    /// Convert a local instant to the corrected (reference) time base.
+    ///
+    /// Useful to timestamp events that were captured with the base monotonic.
+    pub fn to_reference(&self, local: Base::Instant) -> Base::Instant {
+        let state = critical_section::with(|cs| self.state.borrow(cs).get());
+
+        Base::Instant::from_ticks(state.local_to_reference(local.ticks()))
+    }
+
+    /// Convert a corrected (reference) instant to the local time base, rounding up.
+    pub fn to_local(&self, reference: Base::Instant) -> Base::Instant {
+        let state = critical_section::with(|cs| self.state.borrow(cs).get());
+
+        Base::Instant::from_ticks(state.reference_to_local(reference.ticks()))
+    }
+
+    /// The current corrected time.
+    pub fn now(&self) -> Base::Instant {
+        self.to_reference(Base::now())
+    }
+
+    /// The current corrected time, read as a monotonic: the corrected time is never stepped
+    /// afterwards.
+    fn monotonic_now(&self) -> Base::Instant {
+        critical_section::with(|cs| {
+            let cell = self.state.borrow(cs);
+            let mut state = cell.get();
+
+            if !state.used {
+                state.used = true;
+                cell.set(state);
+            }
+
+            Base::Instant::from_ticks(state.local_to_reference(Base::now().ticks()))
+        })
+    }
+}
+
+/// Binds a static [`ClockSync`] to a type, for use with [`SynchronizedMonotonic`].
+pub trait ClockSyncSource: 'static {
+    /// The monotonic that is being corrected.
+    type Base: Monotonic;
+
+    /// Returns a reference to the clock synchronization state.
+    fn clock_sync() -> &'static ClockSync<Self::Base>;
+}
+
+/// A [`Monotonic`] that runs on the corrected time of a [`ClockSyncSource`].
+///
+/// Instants and durations have the same types as the ones of the base monotonic, but are
+/// measured in the reference time base.
+///
+/// Delays are handled by the base monotonic; as the rate correction can change while waiting,
+/// the delay is re-armed if the corrected time has not been reached when the base delay ends.
+pub struct SynchronizedMonotonic<S>(PhantomData<S>);
+
+impl<S> Monotonic for SynchronizedMonotonic<S>
+where
+    S: ClockSyncSource,
+    <S::Base as Monotonic>::Instant: TimerQueueBasedInstant<Ticks = u64>,
+    <S::Base as Monotonic>::Duration: TimerQueueBasedDuration<Ticks = u64>,
+{
+    type Instant = <S::Base as Monotonic>::Instant;
+    type Duration = <S::Base as Monotonic>::Duration;
+
+    fn now() -> Self::Instant {
+        S::clock_sync().monotonic_now()
+    }
+
+    async fn delay(duration: Self::Duration) {
+        let now = Self::now().ticks();
+        let mut timeout = now.wrapping_add(duration.ticks());
+        if now != timeout {
+            // Wait for one period longer to compensate for the uncertainty of `now`,
+            // the same as the `TimerQueue` does.
+            timeout = timeout.wrapping_add(1);
+        }
+
+        Self::delay_until(Self::Instant::from_ticks(timeout)).await
+    }
+
+    async fn delay_until(instant: Self::Instant) {
+        while Self::now() < instant {
+            S::Base::delay_until(S::clock_sync().to_local(instant)).await;
+        }
+    }
+
+    async fn timeout_at<F: Future>(
+        instant: Self::Instant,
+        future: F,
+    ) -> Result<F::Output, TimeoutError> {
+        let mut future = pin!(future);
+        let mut delay = pin!(Self::delay_until(instant));
+
+        poll_fn(|cx| {
+            if let Poll::Ready(v) = future.as_mut().poll(cx) {
+                return Poll::Ready(Ok(v));
+            }
+
+            if delay.as_mut().poll(cx).is_ready() {
+                return Poll::Ready(Err(TimeoutError));
+            }
+
+            Poll::Pending
+        })
+        .await
+    }
+
+    async fn timeout_after<F: Future>(
+        duration: Self::Duration,
+        future: F,
+    ) -> Result<F::Output, TimeoutError> {
+        let now = Self::now().ticks();
+        let mut timeout = now.wrapping_add(duration.ticks());
+        if now != timeout {
+            timeout = timeout.wrapping_add(1);
+        }
+
+        Self::timeout_at(Self::Instant::from_ticks(timeout), future).await
+    }
+}
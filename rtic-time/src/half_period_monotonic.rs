@@ -0,0 +1,328 @@
+//! A generic [`TimerQueueBackend`] for wrapping hardware timers.
+//!
+//! Most monotonics are built the same way: a free-running hardware counter is extended to
+//! 64 bits with a [half-period counter](crate::half_period_counter), one compare channel
+//! generates the half-period interrupt and another one the timer queue wakeups.
+//! [`HalfPeriodMonotonic`] contains all of that logic, so that supporting a new timer only
+//! requires implementing [`HalfPeriodTimer`], which is a thin layer over the timer's registers.
+//!
+//! # Requirements on the hardware
+//!
+//! - A free-running up-counter that wraps at `2^BITS`, where `BITS` is defined by
+//!   [`HalfPeriodTimer::Counter`].
+//! - An overflow event, or a compare event at `0`.
+//! - A compare event at `2^(BITS - 1)`, the half-period mark.
+//! - A compare channel for the timer queue.
+//! - All of those events trigger the same interrupt, which calls
+//!   [`TimerQueue::on_monotonic_interrupt`].
+//!
+//! As described in [`half_period_counter`](crate::half_period_counter), the overflow and the
+//! half-period interrupt must never be delayed by more than half a timer period.
+//!
+//! # Example
+//!
+//! ```rust
+//! # mod timer {
+//! #     pub fn count() -> u16 { 0 }
+//! #     pub fn set_compare_1(_val: u16) {}
+//! #     pub fn clear_flags(_mask: u8) -> u8 { 0 }
+//! #     pub fn reset_and_start() {}
+//! #     pub fn pend() {}
+//! # }
+//! use rtic_time::half_period_monotonic::{HalfPeriodMonotonic, HalfPeriodState, HalfPeriodTimer};
+//!
+//! struct MyTimer;
+//!
+//! impl HalfPeriodTimer for MyTimer {
+//!     type Counter = u16;
+//!
+//!     fn read_counter() -> u16 {
+//!         timer::count()
+//!     }
+//!
+//!     fn set_compare(value: u64) {
+//!         timer::set_compare_1(value as u16);
+//!     }
+//!
+//!     fn clear_compare_flag() {
+//!         timer::clear_flags(0b100);
+//!     }
+//!
+//!     fn take_overflow_flag() -> bool {
+//!         timer::clear_flags(0b001) != 0
+//!     }
+//!
+//!     fn take_half_period_flag() -> bool {
+//!         timer::clear_flags(0b010) != 0
+//!     }
+//!
+//!     fn start() {
+//!         // Resets the counter to zero, clears all flags, enables the overflow, the
+//!         // half-period (compare 0 at `0x8000`) and the compare 1 interrupts.
+//!         timer::reset_and_start();
+//!     }
+//!
+//!     fn pend_interrupt() {
+//!         timer::pend();
+//!     }
+//!
+//!     fn state() -> &'static HalfPeriodState<Self> {
+//!         static STATE: HalfPeriodState<MyTimer> = HalfPeriodState::new();
+//!         &STATE
+//!     }
+//! }
+//!
+//! fn init() {
+//!     HalfPeriodMonotonic::<MyTimer>::start();
+//! }
+//! ```
+//!
+//! `HalfPeriodMonotonic<MyTimer>` can now be used as the backend of a
+//! [`TimerQueueBasedMonotonic`](crate::monotonic::TimerQueueBasedMonotonic).
+//!
+//! # Multiple timer queues
+//!
+//! Timers with more compare channels can expose each of them as a [`CompareChannel`] with its
+//! own timer queue and interrupt. [`HalfPeriodChannel`] is the backend for such a channel.
+
+use core::marker::PhantomData;
+use portable_atomic::{AtomicU64, Ordering};
+
+use crate::half_period_counter::{calculate_now, TimerValue};
+use crate::timer_queue::{TimerQueue, TimerQueueBackend};
+
+/// The hardware specific part of a [`HalfPeriodMonotonic`].
+pub trait HalfPeriodTimer: 'static + Sized {
+    /// The type of the counter register, defining the bit width of the timer.
+    type Counter: TimerValue + Into<u64>;
+
+    /// Read the current value of the counter.
+    fn read_counter() -> Self::Counter;
+
+    /// Set the compare value of the timer queue's compare channel.
+    ///
+    /// `value` is already wrapped to the bit width of [`Self::Counter`]. The requested
+    /// instant might be more than one period in the future, which will only cause spurious
+    /// wakeups that the timer queue handles.
+    fn set_compare(value: u64);
+
+    /// Clear the compare flag of the timer queue's compare channel.
+    fn clear_compare_flag();
+
+    /// Return whether an overflow happened, and clear the flag.
+    fn take_overflow_flag() -> bool;
+
+    /// Return whether the half-period mark was passed, and clear the flag.
+    fn take_half_period_flag() -> bool;
+
+    /// Reset the counter to zero, clear all pending flags, enable the overflow, half-period
+    /// and compare interrupts and start counting.
+    ///
+    /// This is called from within a critical section by [`HalfPeriodMonotonic::start`].
+    fn start();
+
+    /// Pend the timer's interrupt.
+    fn pend_interrupt();
+
+    /// Optional. Enable the compare interrupt, see [`TimerQueueBackend::enable_timer`].
+    fn enable_compare() {}
+
+    /// Optional. Disable the compare interrupt, see [`TimerQueueBackend::disable_timer`].
+    fn disable_compare() {}
+
+    /// Returns a reference to the statically allocated state of this timer.
+    fn state() -> &'static HalfPeriodState<Self>;
+}
+
+/// The state of a [`HalfPeriodMonotonic`], which has to be placed in a `static`.
+pub struct HalfPeriodState<Hw: HalfPeriodTimer> {
+    // 64 bits, so that `now` does not wrap even with 16-bit timers.
+    half_periods: AtomicU64,
+    queue: TimerQueue<HalfPeriodMonotonic<Hw>>,
+}
+
+impl<Hw: HalfPeriodTimer> HalfPeriodState<Hw> {
+    /// Create a new state.
+    pub const fn new() -> Self {
+        Self {
+            half_periods: AtomicU64::new(0),
+            queue: TimerQueue::new(),
+        }
+    }
+}
+
+impl<Hw: HalfPeriodTimer> Default for HalfPeriodState<Hw> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A [`TimerQueueBackend`] for any timer implementing [`HalfPeriodTimer`].
+pub struct HalfPeriodMonotonic<Hw>(PhantomData<Hw>);
+
+impl<Hw: HalfPeriodTimer> HalfPeriodMonotonic<Hw> {
+    /// Starts the timer and initializes the timer queue.
+    ///
+    /// This method must be called only once. Unmasking the interrupt is left to the caller.
+    pub fn start() {
+        let state = Hw::state();
+
+        // Timing critical, the half-period counter has to be in sync with the timer value.
+        critical_section::with(|_| {
+            Hw::start();
+            state.half_periods.store(0, Ordering::SeqCst);
+            state.queue.initialize(Self(PhantomData));
+        });
+    }
+}
+
+/// Forwards the bit width of the hardware counter to [`calculate_now`].
+struct Counter<T>(u64, PhantomData<T>);
+
+impl<T: TimerValue> TimerValue for Counter<T> {
+    const BITS: u32 = T::BITS;
+}
+
+impl<T> From<Counter<T>> for u64 {
+    fn from(value: Counter<T>) -> Self {
+        value.0
+    }
+}
+
+/// Wraps an instant to the bit width of the hardware counter.
+fn wrap_to_counter<Hw: HalfPeriodTimer>(instant: u64) -> u64 {
+    instant & (u64::MAX >> (64 - Hw::Counter::BITS))
+}
+
+impl<Hw: HalfPeriodTimer> TimerQueueBackend for HalfPeriodMonotonic<Hw> {
+    type Ticks = u64;
+
+    fn now() -> Self::Ticks {
+        let half_periods = &Hw::state().half_periods;
+
+        calculate_now(
+            || half_periods.load(Ordering::Relaxed),
+            || Counter::<Hw::Counter>(Hw::read_counter().into(), PhantomData),
+        )
+    }
+
+    fn set_compare(instant: Self::Ticks) {
+        Hw::set_compare(wrap_to_counter::<Hw>(instant));
+    }
+
+    fn clear_compare_flag() {
+        Hw::clear_compare_flag();
+    }
+
+    fn pend_interrupt() {
+        Hw::pend_interrupt();
+    }
+
+    fn on_interrupt() {
+        // Only the interrupt writes the counter, so no read-modify-write atomics are needed.
+        let half_periods = &Hw::state().half_periods;
+
+        if Hw::take_overflow_flag() {
+            let prev = half_periods.load(Ordering::Relaxed);
+            assert!(prev % 2 == 1, "Monotonic must have skipped an interrupt!");
+            half_periods.store(prev.wrapping_add(1), Ordering::Relaxed);
+        }
+
+        if Hw::take_half_period_flag() {
+            let prev = half_periods.load(Ordering::Relaxed);
+            assert!(prev % 2 == 0, "Monotonic must have skipped an interrupt!");
+            half_periods.store(prev.wrapping_add(1), Ordering::Relaxed);
+        }
+    }
+
+    fn enable_timer() {
+        Hw::enable_compare();
+    }
+
+    fn disable_timer() {
+        Hw::disable_compare();
+    }
+
+    fn timer_queue() -> &'static TimerQueue<Self> {
+        &Hw::state().queue
+    }
+}
+
+/// An additional compare channel of a [`HalfPeriodTimer`], with its own timer queue.
+///
+/// The channel shares the time base of its timer, so a second
+/// [`TimerQueueBasedMonotonic`](crate::monotonic::TimerQueueBasedMonotonic) with the same
+/// `Instant` and `Duration` types can be built on top of [`HalfPeriodChannel`]. This allows
+/// to e.g. service critical timeouts from a high priority interrupt while the bulk of the
+/// delays is handled at a lower priority.
+///
+/// The channel's interrupt calls [`TimerQueue::on_monotonic_interrupt`] of the channel's queue.
+/// If the hardware only has one interrupt for all compare channels, the timer's interrupt handler
+/// can instead check the channel's flag and pend a different interrupt for the channel.
+pub trait CompareChannel: 'static + Sized {
+    /// The timer this channel belongs to.
+    type Timer: HalfPeriodTimer;
+
+    /// Set the compare value of this channel, see [`HalfPeriodTimer::set_compare`].
+    fn set_compare(value: u64);
+
+    /// Clear the compare flag of this channel.
+    fn clear_compare_flag();
+
+    /// Pend the interrupt of this channel.
+    fn pend_interrupt();
+
+    /// Optional. Enable the compare interrupt of this channel.
+    fn enable_compare() {}
+
+    /// Optional. Disable the compare interrupt of this channel.
+    fn disable_compare() {}
+
+    /// Returns a reference to the statically allocated timer queue of this channel.
+    fn timer_queue() -> &'static TimerQueue<HalfPeriodChannel<Self>>;
+}
+
+/// A [`TimerQueueBackend`] for an additional [`CompareChannel`] of a [`HalfPeriodMonotonic`].
+pub struct HalfPeriodChannel<Ch>(PhantomData<Ch>);
+
+impl<Ch: CompareChannel> HalfPeriodChannel<Ch> {
+    /// Initializes the timer queue of this channel.
+    ///
+    /// This method must be called only once, the timer itself is started by
+    /// [`HalfPeriodMonotonic::start`].
+    pub fn start() {
+        Ch::timer_queue().initialize(Self(PhantomData));
+    }
+}
+
+impl<Ch: CompareChannel> TimerQueueBackend for HalfPeriodChannel<Ch> {
+    type Ticks = u64;
+
+    fn now() -> Self::Ticks {
+        HalfPeriodMonotonic::<Ch::Timer>::now()
+    }
+
+    fn set_compare(instant: Self::Ticks) {
+        Ch::set_compare(wrap_to_counter::<Ch::Timer>(instant));
+    }
+
+    fn clear_compare_flag() {
+        Ch::clear_compare_flag();
+    }
+
+    fn pend_interrupt() {
+        Ch::pend_interrupt();
+    }
+
+    fn enable_timer() {
+        Ch::enable_compare();
+    }
+
+    fn disable_timer() {
+        Ch::disable_compare();
+    }
+
+    fn timer_queue() -> &'static TimerQueue<Self> {
+        Ch::timer_queue()
+    }
+}
@@ -7,9 +7,12 @@
 #![deny(missing_docs)]
 #![allow(async_fn_in_trait)]
 
+pub mod clock_sync;
 pub mod half_period_counter;
+pub mod half_period_monotonic;
 mod linked_list;
 pub mod monotonic;
+pub mod select;
 pub mod timer_queue;
 
 /// This indicates that there was a timeout.
@@ -61,4 +64,24 @@ pub trait Monotonic {
         duration: Self::Duration,
         future: F,
     ) -> Result<F::Output, TimeoutError>;
+
+    /// Race a set of futures against each other and a specific time instant.
+    ///
+    /// Returns which of the futures completed first, see [`select`] for more details.
+    async fn select_timeout_at<S: select::Select>(
+        instant: Self::Instant,
+        futures: S,
+    ) -> Result<S::Output, TimeoutError> {
+        Self::timeout_at(instant, select::select(futures)).await
+    }
+
+    /// Race a set of futures against each other and a specific duration.
+    ///
+    /// Returns which of the futures completed first, see [`select`] for more details.
+    async fn select_timeout_after<S: select::Select>(
+        duration: Self::Duration,
+        futures: S,
+    ) -> Result<S::Output, TimeoutError> {
+        Self::timeout_after(duration, select::select(futures)).await
+    }
 }
@@ -1,5 +1,7 @@
 use core::marker::PhantomPinned;
 use core::pin::Pin;
+#[cfg(feature = "stats")]
+use core::sync::atomic::AtomicUsize;
 use core::sync::atomic::{AtomicPtr, Ordering};
 use critical_section as cs;
 
@@ -9,6 +11,10 @@ use critical_section as cs;
 /// lock free, but it will not deadlock.
 pub(crate) struct LinkedList<T> {
     head: AtomicPtr<Link<T>>,
+    #[cfg(feature = "stats")]
+    len: AtomicUsize,
+    #[cfg(feature = "stats")]
+    max_len: AtomicUsize,
 }
 
 impl<T> LinkedList<T> {
@@ -16,8 +22,54 @@ impl<T> LinkedList<T> {
     pub const fn new() -> Self {
         Self {
             head: AtomicPtr::new(core::ptr::null_mut()),
+            #[cfg(feature = "stats")]
+            len: AtomicUsize::new(0),
+            #[cfg(feature = "stats")]
+            max_len: AtomicUsize::new(0),
         }
     }
+
+    /// The current number of links in the list.
+    #[cfg(feature = "stats")]
+    pub fn len(&self) -> usize {
+        self.len.load(Ordering::Relaxed)
+    }
+
+    /// The highest number of links that were in the list at the same time.
+    #[cfg(feature = "stats")]
+    pub fn max_len(&self) -> usize {
+        self.max_len.load(Ordering::Relaxed)
+    }
+
+    /// Reset the high-water mark to the current length.
+    #[cfg(feature = "stats")]
+    pub fn reset_max_len(&self) {
+        cs::with(|_| {
+            self.max_len
+                .store(self.len.load(Ordering::Relaxed), Ordering::Relaxed)
+        });
+    }
+
+    // All length updates happen inside of the list's critical sections, so plain loads and
+    // stores are enough.
+    #[inline(always)]
+    fn increment_len(&self) {
+        #[cfg(feature = "stats")]
+        {
+            let len = self.len.load(Ordering::Relaxed) + 1;
+            self.len.store(len, Ordering::Relaxed);
+            if len > self.max_len.load(Ordering::Relaxed) {
+                self.max_len.store(len, Ordering::Relaxed);
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn decrement_len(&self) {
+        #[cfg(feature = "stats")]
+        self.len
+            .store(self.len.load(Ordering::Relaxed) - 1, Ordering::Relaxed);
+    }
 }
 
 impl<T: PartialOrd + Clone> LinkedList<T> {
@@ -35,6 +87,7 @@ impl<T: PartialOrd + Clone> LinkedList<T> {
                     // Move head to the next element
                     self.head
                         .store(head.next.load(Ordering::Relaxed), Ordering::Relaxed);
+                    self.decrement_len();
 
                     // We read the value at head
                     let head_val = head.val.clone();
@@ -66,6 +119,7 @@ impl<T: PartialOrd + Clone> LinkedList<T> {
                 // 2. Replace head with head.next
                 self.head
                     .store(head_ref.next.load(Ordering::Relaxed), Ordering::Relaxed);
+                self.decrement_len();
 
                 return;
             }
@@ -81,6 +135,7 @@ impl<T: PartialOrd + Clone> LinkedList<T> {
                 if next as *const _ as usize == addr {
                     curr.next
                         .store(next_link.next.load(Ordering::Relaxed), Ordering::Relaxed);
+                    self.decrement_len();
 
                     return;
                 }
@@ -106,6 +161,8 @@ impl<T: PartialOrd + Clone> LinkedList<T> {
             // Make sure all previous writes are visible
             core::sync::atomic::fence(Ordering::SeqCst);
 
+            self.increment_len();
+
             let head = self.head.load(Ordering::Relaxed);
 
             // 3 cases to handle
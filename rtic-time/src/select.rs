@@ -0,0 +1,139 @@
+//! Race multiple futures against each other, optionally with a deadline.
+//!
+//! [`select`] takes a tuple of up to 6 futures, or an array of futures of the same type, and
+//! completes as soon as one of them completes, reporting which one it was. The remaining futures
+//! are dropped. No heap and no macros are involved; the futures are stored inline in the returned
+//! [`SelectFuture`].
+//!
+//! Combined with a deadline this becomes [`Monotonic::select_timeout_at`] and
+//! [`Monotonic::select_timeout_after`], which use the regular timeout machinery of the monotonic.
+//!
+//! # Fairness
+//!
+//! The futures are polled in order, so if more than one is ready at the same time, the first one
+//! wins.
+//!
+//! # Example
+//!
+//! ```rust
+//! use rtic_time::select::{select, Either};
+//!
+//! async fn wait_for_either(a: impl core::future::Future<Output = u32>, b: impl core::future::Future<Output = bool>) {
+//!     match select((a, b)).await {
+//!         Either::First(value) => { /* `a` completed first */ }
+//!         Either::Second(flag) => { /* `b` completed first */ }
+//!     }
+//! }
+//! ```
+//!
+//! [`Monotonic::select_timeout_at`]: crate::Monotonic::select_timeout_at
+//! [`Monotonic::select_timeout_after`]: crate::Monotonic::select_timeout_after
+
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+
+/// A set of futures that can be raced against each other.
+pub trait Select {
+    /// The result of the race, identifying the future that completed.
+    type Output;
+
+    /// Poll all futures in order, returning the output of the first one that is ready.
+    fn poll_select(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output>;
+}
+
+/// Race the futures in `futures`, see the [module level documentation](crate::select).
+pub fn select<S: Select>(futures: S) -> SelectFuture<S> {
+    SelectFuture { futures }
+}
+
+/// Future returned by [`select`].
+pub struct SelectFuture<S> {
+    futures: S,
+}
+
+impl<S: Select> Future for SelectFuture<S> {
+    type Output = S::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // SAFETY: `futures` is structurally pinned, it is never moved out of `self`.
+        unsafe { self.map_unchecked_mut(|s| &mut s.futures) }.poll_select(cx)
+    }
+}
+
+impl<F: Future, const N: usize> Select for [F; N] {
+    /// The index of the future that completed, and its output.
+    type Output = (usize, F::Output);
+
+    fn poll_select(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // SAFETY: The array is pinned, so its elements are as well. Nothing is moved.
+        let this = unsafe { self.get_unchecked_mut() };
+
+        for (i, f) in this.iter_mut().enumerate() {
+            // SAFETY: See above.
+            if let Poll::Ready(v) = unsafe { Pin::new_unchecked(f) }.poll(cx) {
+                return Poll::Ready((i, v));
+            }
+        }
+
+        Poll::Pending
+    }
+}
+
+macro_rules! impl_select_tuple {
+    ($either:ident, $(($t:ident, $idx:tt, $variant:ident)),+ $(,)?) => {
+        /// Result of racing a tuple of futures with [`select`].
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        pub enum $either<$($t),+> {
+            $(
+                #[doc = concat!("Future `", stringify!($idx), "` completed first.")]
+                $variant($t),
+            )+
+        }
+
+        impl<$($t: Future),+> Select for ($($t,)+) {
+            type Output = $either<$($t::Output),+>;
+
+            fn poll_select(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+                // SAFETY: The tuple is pinned, so its fields are as well. Nothing is moved.
+                let this = unsafe { self.get_unchecked_mut() };
+
+                $(
+                    // SAFETY: See above.
+                    if let Poll::Ready(v) = unsafe { Pin::new_unchecked(&mut this.$idx) }.poll(cx) {
+                        return Poll::Ready($either::$variant(v));
+                    }
+                )+
+
+                Poll::Pending
+            }
+        }
+    };
+}
+
+impl_select_tuple!(Either, (A, 0, First), (B, 1, Second));
+impl_select_tuple!(Either3, (A, 0, First), (B, 1, Second), (C, 2, Third));
+impl_select_tuple!(
+    Either4,
+    (A, 0, First),
+    (B, 1, Second),
+    (C, 2, Third),
+    (D, 3, Fourth)
+);
+impl_select_tuple!(
+    Either5,
+    (A, 0, First),
+    (B, 1, Second),
+    (C, 2, Third),
+    (D, 3, Fourth),
+    (E, 4, Fifth)
+);
+impl_select_tuple!(
+    Either6,
+    (A, 0, First),
+    (B, 1, Second),
+    (C, 2, Third),
+    (D, 3, Fourth),
+    (E, 4, Fifth),
+    (F, 5, Sixth)
+);
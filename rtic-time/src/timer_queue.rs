@@ -9,8 +9,12 @@ use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use core::task::{Poll, Waker};
 
 mod backend;
+#[cfg(feature = "stats")]
+mod stats;
 mod tick_type;
 pub use backend::TimerQueueBackend;
+#[cfg(feature = "stats")]
+pub use stats::TimerQueueStats;
 pub use tick_type::TimerQueueTicks;
 
 /// Holds a waker and at which time instant this waker shall be awoken.
@@ -57,9 +61,16 @@ impl<Backend: TimerQueueBackend> PartialOrd for WaitingWaker<Backend> {
 /// complete.
 ///
 /// Do not call `mem::forget` on an awaited future, or there will be dragons!
+///
+/// # Statistics
+///
+/// With the `stats` feature enabled, the queue keeps track of its length and of how late
+/// waiters are released, see [`TimerQueue::stats`].
 pub struct TimerQueue<Backend: TimerQueueBackend> {
     queue: LinkedList<WaitingWaker<Backend>>,
     initialized: AtomicBool,
+    #[cfg(feature = "stats")]
+    wakeup_stats: stats::WakeupStats<Backend::Ticks>,
 }
 
 impl<Backend: TimerQueueBackend> Default for TimerQueue<Backend> {
@@ -74,9 +85,33 @@ impl<Backend: TimerQueueBackend> TimerQueue<Backend> {
         Self {
             queue: LinkedList::new(),
             initialized: AtomicBool::new(false),
+            #[cfg(feature = "stats")]
+            wakeup_stats: stats::WakeupStats::new(),
         }
     }
 
+    /// Take a snapshot of the statistics of this queue.
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> TimerQueueStats<Backend::Ticks> {
+        let (late_wakeups, max_lateness) = self.wakeup_stats.get();
+
+        TimerQueueStats {
+            len: self.queue.len(),
+            max_len: self.queue.max_len(),
+            late_wakeups,
+            max_lateness,
+        }
+    }
+
+    /// Reset the statistics of this queue.
+    ///
+    /// The high-water mark of the queue length is reset to the current length.
+    #[cfg(feature = "stats")]
+    pub fn reset_stats(&self) {
+        self.queue.reset_max_len();
+        self.wakeup_stats.reset();
+    }
+
     /// Forwards the `Monotonic::now()` method.
     #[inline(always)]
     pub fn now(&self) -> Backend::Ticks {
@@ -103,10 +138,18 @@ impl<Backend: TimerQueueBackend> TimerQueue<Backend> {
 
         loop {
             let mut release_at = None;
+            #[cfg(feature = "stats")]
+            let mut now = None;
             let head = self.queue.pop_if(|head| {
                 release_at = Some(head.release_at);
 
-                let should_pop = Backend::now().is_at_least(head.release_at);
+                let current = Backend::now();
+                #[cfg(feature = "stats")]
+                {
+                    now = Some(current);
+                }
+
+                let should_pop = current.is_at_least(head.release_at);
                 head.was_popped.store(should_pop, Ordering::Relaxed);
 
                 should_pop
@@ -114,6 +157,11 @@ impl<Backend: TimerQueueBackend> TimerQueue<Backend> {
 
             match (head, release_at) {
                 (Some(link), _) => {
+                    #[cfg(feature = "stats")]
+                    if let Some(now) = now {
+                        self.wakeup_stats.record(link.release_at, now);
+                    }
+
                     link.waker.wake();
                 }
                 (None, Some(instant)) => {
@@ -0,0 +1,83 @@
+use core::cell::Cell;
+use critical_section::Mutex;
+
+use super::TimerQueueTicks;
+
+/// A snapshot of the statistics of a [`TimerQueue`](super::TimerQueue).
+///
+/// Lateness is the time between the instant a waiter wanted to be woken up at and the
+/// instant the timer interrupt actually released it, in ticks of the monotonic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimerQueueStats<Ticks> {
+    /// The number of waiters currently in the queue.
+    pub len: usize,
+    /// The highest number of waiters that were in the queue at the same time.
+    pub max_len: usize,
+    /// The number of waiters that were released at least one tick late.
+    pub late_wakeups: u32,
+    /// The highest lateness observed, `None` if no waiter was released yet or if the ticks do
+    /// not implement [`elapsed_since`](super::TimerQueueTicks::elapsed_since).
+    pub max_lateness: Option<Ticks>,
+}
+
+#[derive(Clone, Copy)]
+struct Lateness<Ticks> {
+    late_wakeups: u32,
+    max_lateness: Option<Ticks>,
+}
+
+/// Wakeup statistics, recorded by the timer interrupt.
+pub(crate) struct WakeupStats<Ticks> {
+    lateness: Mutex<Cell<Lateness<Ticks>>>,
+}
+
+impl<Ticks: TimerQueueTicks> WakeupStats<Ticks> {
+    pub const fn new() -> Self {
+        Self {
+            lateness: Mutex::new(Cell::new(Lateness {
+                late_wakeups: 0,
+                max_lateness: None,
+            })),
+        }
+    }
+
+    /// Record that a waiter for `release_at` was released at `now`.
+    pub fn record(&self, release_at: Ticks, now: Ticks) {
+        let lateness = now.elapsed_since(release_at);
+
+        critical_section::with(|cs| {
+            let cell = self.lateness.borrow(cs);
+            let mut stats = cell.get();
+
+            if now != release_at {
+                stats.late_wakeups = stats.late_wakeups.saturating_add(1);
+            }
+
+            if let Some(lateness) = lateness {
+                stats.max_lateness = match stats.max_lateness {
+                    Some(max) if max.is_at_least(lateness) => Some(max),
+                    _ => Some(lateness),
+                };
+            }
+
+            cell.set(stats);
+        });
+    }
+
+    /// Returns `(late_wakeups, max_lateness)`.
+    pub fn get(&self) -> (u32, Option<Ticks>) {
+        critical_section::with(|cs| {
+            let stats = self.lateness.borrow(cs).get();
+            (stats.late_wakeups, stats.max_lateness)
+        })
+    }
+
+    pub fn reset(&self) {
+        critical_section::with(|cs| {
+            self.lateness.borrow(cs).set(Lateness {
+                late_wakeups: 0,
+                max_lateness: None,
+            })
+        });
+    }
+}
@@ -25,6 +25,15 @@ pub trait TimerQueueTicks: Copy + PartialEq + Eq {
 
     /// Wrapping addition.
     fn wrapping_add(self, other: Self) -> Self;
+
+    /// Optional. The ticks elapsed from `earlier` to `self`, with wrapping.
+    ///
+    /// Used to measure wakeup latencies with the `stats` feature. The default returns `None`,
+    /// in which case the latencies are not measured.
+    fn elapsed_since(self, earlier: Self) -> Option<Self> {
+        let _ = earlier;
+        None
+    }
 }
 
 impl TimerQueueTicks for u32 {
@@ -36,6 +45,9 @@ impl TimerQueueTicks for u32 {
     fn wrapping_add(self, other: Self) -> Self {
         u32::wrapping_add(self, other)
     }
+    fn elapsed_since(self, earlier: Self) -> Option<Self> {
+        Some(u32::wrapping_sub(self, earlier))
+    }
 }
 impl TimerQueueTicks for u64 {
     const ONE_TICK: Self = 1;
@@ -46,4 +58,7 @@ impl TimerQueueTicks for u64 {
     fn wrapping_add(self, other: Self) -> Self {
         u64::wrapping_add(self, other)
     }
+    fn elapsed_since(self, earlier: Self) -> Option<Self> {
+        Some(u64::wrapping_sub(self, earlier))
+    }
 }
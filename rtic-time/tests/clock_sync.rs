@@ -0,0 +1,162 @@
+//! A test that verifies that [`ClockSync`] converges towards a drifting reference.
+//!
+//! To run this test, you need to activate the `critical-section/std` feature.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use rtic_time::{
+    clock_sync::{ClockSync, ClockSyncConfig, ClockSyncSource, SynchronizedMonotonic},
+    Monotonic, TimeoutError,
+};
+
+static NOW: AtomicU64 = AtomicU64::new(0);
+
+/// A base monotonic whose time is set manually by the test.
+struct TestMono;
+
+impl Monotonic for TestMono {
+    type Instant = fugit::Instant<u64, 1, 1_000_000>;
+    type Duration = fugit::Duration<u64, 1, 1_000_000>;
+
+    fn now() -> Self::Instant {
+        Self::Instant::from_ticks(NOW.load(Ordering::Relaxed))
+    }
+
+    async fn delay(_: Self::Duration) {
+        unimplemented!()
+    }
+
+    async fn delay_until(_: Self::Instant) {
+        unimplemented!()
+    }
+
+    async fn timeout_at<F: core::future::Future>(
+        _: Self::Instant,
+        _: F,
+    ) -> Result<F::Output, TimeoutError> {
+        unimplemented!()
+    }
+
+    async fn timeout_after<F: core::future::Future>(
+        _: Self::Duration,
+        _: F,
+    ) -> Result<F::Output, TimeoutError> {
+        unimplemented!()
+    }
+}
+
+type Instant = <TestMono as Monotonic>::Instant;
+
+static SYNC: ClockSync<TestMono> = ClockSync::new(ClockSyncConfig::new());
+
+struct TestSync;
+
+impl ClockSyncSource for TestSync {
+    type Base = TestMono;
+
+    fn clock_sync() -> &'static ClockSync<TestMono> {
+        &SYNC
+    }
+}
+
+/// The local clock runs 80 ppm fast and started 12345 ticks ahead of the reference.
+fn local_at(reference: u64) -> u64 {
+    12_345 + reference + reference * 80 / 1_000_000
+}
+
+#[test]
+fn clock_sync() {
+    let sync = ClockSync::<TestMono>::new(ClockSyncConfig::new());
+
+    // Unsynchronized: identity.
+    assert!(!sync.is_synchronized());
+    assert_eq!(sync.to_reference(Instant::from_ticks(1000)).ticks(), 1000);
+
+    // The first measurement steps to the reference.
+    let offset = sync.update(Instant::from_ticks(local_at(0)), Instant::from_ticks(0));
+    assert_eq!(offset, -12_345);
+    assert!(sync.is_synchronized());
+    assert_eq!(
+        sync.to_reference(Instant::from_ticks(local_at(0))).ticks(),
+        0
+    );
+
+    // One measurement per second (PPS).
+    let mut previous = 0;
+    let mut offset = 0;
+    for second in 1..=30u64 {
+        let reference = second * 1_000_000;
+        let local = local_at(reference);
+
+        // The corrected time must never go backwards.
+        let corrected = sync.to_reference(Instant::from_ticks(local)).ticks();
+        assert!(corrected >= previous);
+        previous = corrected;
+
+        offset = sync.update(Instant::from_ticks(local), Instant::from_ticks(reference));
+    }
+
+    // Converged to within a couple of ticks and learned the drift.
+    assert!(offset.abs() <= 2, "offset did not converge: {offset}");
+    let drift = sync.drift_ppb();
+    assert!(
+        (-81_000..=-79_000).contains(&drift),
+        "drift estimate is off: {drift}"
+    );
+
+    // Converting back and forth is consistent, rounding the local instant up.
+    let reference = Instant::from_ticks(31_500_000);
+    let local = sync.to_local(reference);
+    assert!(sync.to_reference(local) >= reference);
+    assert!(local.ticks().abs_diff(local_at(reference.ticks())) <= 2);
+
+    sync.reset();
+    assert!(!sync.is_synchronized());
+    assert_eq!(sync.rate_ppb(), 0);
+}
+
+#[test]
+fn synchronized_monotonic() {
+    NOW.store(local_at(0), Ordering::Relaxed);
+    SYNC.update(TestMono::now(), Instant::from_ticks(0));
+
+    NOW.store(local_at(500_000), Ordering::Relaxed);
+    assert_eq!(
+        SynchronizedMonotonic::<TestSync>::now().ticks(),
+        SYNC.to_reference(TestMono::now()).ticks()
+    );
+    assert_eq!(
+        SynchronizedMonotonic::<TestSync>::now().ticks(),
+        500_000 + 40
+    );
+
+    // The monotonic is in use, neither a reset nor the next first measurement steps the time.
+    SYNC.reset();
+    assert_eq!(SynchronizedMonotonic::<TestSync>::now().ticks(), 500_040);
+
+    let offset = SYNC.update(TestMono::now(), Instant::from_ticks(0));
+    assert_eq!(offset, -500_040);
+    assert!(SYNC.is_synchronized());
+    assert_eq!(SynchronizedMonotonic::<TestSync>::now().ticks(), 500_040);
+
+    // The offset is slewed away at the maximum rate.
+    let mut previous = 500_040;
+    for second in 1..=5u64 {
+        NOW.store(local_at(500_000) + second * 1_000_000, Ordering::Relaxed);
+        SYNC.update(TestMono::now(), Instant::from_ticks(second * 1_000_000));
+
+        let now = SynchronizedMonotonic::<TestSync>::now().ticks();
+        assert!(now > previous);
+        assert!(now - previous >= 1_000_000 - 500);
+        previous = now;
+    }
+}
+
+#[test]
+#[should_panic(expected = "`max_rate_ppb` must be less than 1_000_000_000")]
+fn rejects_rate_stopping_the_clock() {
+    let _ = ClockSync::<TestMono>::new(ClockSyncConfig {
+        max_rate_ppb: 1_000_000_000,
+        ..ClockSyncConfig::new()
+    });
+}
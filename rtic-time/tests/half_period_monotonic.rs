@@ -0,0 +1,277 @@
+//! A test that verifies [`HalfPeriodMonotonic`] with a simulated 8-bit timer.
+//!
+//! To run this test, you need to activate the `critical-section/std` feature.
+
+use std::{
+    future::Future,
+    pin::pin,
+    sync::atomic::{AtomicBool, AtomicU64, Ordering},
+    task::{Context, Poll, Waker},
+};
+
+use futures_util::future::join4;
+use rtic_time::{
+    half_period_monotonic::{
+        CompareChannel, HalfPeriodChannel, HalfPeriodMonotonic, HalfPeriodState, HalfPeriodTimer,
+    },
+    monotonic::TimerQueueBasedMonotonic,
+    timer_queue::{TimerQueue, TimerQueueBackend},
+    Monotonic,
+};
+
+/// Simulated registers of an 8-bit timer.
+struct Registers {
+    ticks: AtomicU64,
+    compare: AtomicU64,
+    overflow_flag: AtomicBool,
+    half_period_flag: AtomicBool,
+    compare_flag: AtomicBool,
+    compare1: AtomicU64,
+    compare1_flag: AtomicBool,
+}
+
+impl Registers {
+    const fn new() -> Self {
+        Self {
+            ticks: AtomicU64::new(0),
+            compare: AtomicU64::new(0),
+            overflow_flag: AtomicBool::new(false),
+            half_period_flag: AtomicBool::new(false),
+            compare_flag: AtomicBool::new(false),
+            compare1: AtomicU64::new(0),
+            compare1_flag: AtomicBool::new(false),
+        }
+    }
+
+    /// Advance the timer by one tick and raise the corresponding flags.
+    fn tick(&self) {
+        let counter = (self.ticks.fetch_add(1, Ordering::Relaxed) + 1) as u8;
+
+        if counter == 0 {
+            self.overflow_flag.store(true, Ordering::Relaxed);
+        }
+        if counter == 0x80 {
+            self.half_period_flag.store(true, Ordering::Relaxed);
+        }
+        if u64::from(counter) == self.compare.load(Ordering::Relaxed) {
+            self.compare_flag.store(true, Ordering::Relaxed);
+        }
+        if u64::from(counter) == self.compare1.load(Ordering::Relaxed) {
+            self.compare1_flag.store(true, Ordering::Relaxed);
+        }
+    }
+
+    fn interrupt_pending(&self) -> bool {
+        self.overflow_flag.load(Ordering::Relaxed)
+            || self.half_period_flag.load(Ordering::Relaxed)
+            || self.compare_flag.load(Ordering::Relaxed)
+    }
+}
+
+macro_rules! sim_timer {
+    ($name:ident, $regs:ident) => {
+        static $regs: Registers = Registers::new();
+
+        struct $name;
+
+        impl HalfPeriodTimer for $name {
+            type Counter = u8;
+
+            fn read_counter() -> u8 {
+                $regs.ticks.load(Ordering::Relaxed) as u8
+            }
+
+            fn set_compare(value: u64) {
+                assert!(value <= u64::from(u8::MAX));
+                $regs.compare.store(value, Ordering::Relaxed);
+            }
+
+            fn clear_compare_flag() {
+                $regs.compare_flag.store(false, Ordering::Relaxed);
+            }
+
+            fn take_overflow_flag() -> bool {
+                $regs.overflow_flag.swap(false, Ordering::Relaxed)
+            }
+
+            fn take_half_period_flag() -> bool {
+                $regs.half_period_flag.swap(false, Ordering::Relaxed)
+            }
+
+            fn start() {
+                $regs.ticks.store(0, Ordering::Relaxed);
+                $regs.overflow_flag.store(false, Ordering::Relaxed);
+                $regs.half_period_flag.store(false, Ordering::Relaxed);
+                $regs.compare_flag.store(false, Ordering::Relaxed);
+            }
+
+            fn pend_interrupt() {
+                $regs.compare_flag.store(true, Ordering::Relaxed);
+            }
+
+            fn state() -> &'static HalfPeriodState<Self> {
+                static STATE: HalfPeriodState<$name> = HalfPeriodState::new();
+                &STATE
+            }
+        }
+
+        impl $name {
+            /// Run the interrupt handler if an interrupt is pending.
+            fn service() {
+                if $regs.interrupt_pending() {
+                    unsafe {
+                        HalfPeriodMonotonic::<Self>::timer_queue().on_monotonic_interrupt();
+                    }
+                }
+            }
+        }
+    };
+}
+
+sim_timer!(SimTimer, SIM_REGS);
+sim_timer!(SkippingTimer, SKIPPING_REGS);
+
+impl SimTimer {
+    /// Advance the timer and run the interrupt handlers if needed.
+    fn tick() {
+        SIM_REGS.tick();
+        Self::service();
+        SimChannel::service();
+    }
+}
+
+/// The second compare channel of `SimTimer`, with its own interrupt.
+struct SimChannel;
+
+impl CompareChannel for SimChannel {
+    type Timer = SimTimer;
+
+    fn set_compare(value: u64) {
+        assert!(value <= u64::from(u8::MAX));
+        SIM_REGS.compare1.store(value, Ordering::Relaxed);
+    }
+
+    fn clear_compare_flag() {
+        SIM_REGS.compare1_flag.store(false, Ordering::Relaxed);
+    }
+
+    fn pend_interrupt() {
+        SIM_REGS.compare1_flag.store(true, Ordering::Relaxed);
+    }
+
+    fn timer_queue() -> &'static TimerQueue<HalfPeriodChannel<Self>> {
+        static QUEUE: TimerQueue<HalfPeriodChannel<SimChannel>> = TimerQueue::new();
+        &QUEUE
+    }
+}
+
+impl SimChannel {
+    /// Run the channel's interrupt handler if its interrupt is pending.
+    fn service() {
+        if SIM_REGS.compare1_flag.load(Ordering::Relaxed) {
+            unsafe { Self::timer_queue().on_monotonic_interrupt() };
+        }
+    }
+}
+
+struct TestMono;
+
+impl TimerQueueBasedMonotonic for TestMono {
+    type Backend = HalfPeriodMonotonic<SimTimer>;
+    type Instant = fugit::Instant<u64, 1, 1_000>;
+    type Duration = fugit::Duration<u64, 1, 1_000>;
+}
+
+/// A monotonic on the second compare channel, sharing the time base of `TestMono`.
+struct BackgroundMono;
+
+impl TimerQueueBasedMonotonic for BackgroundMono {
+    type Backend = HalfPeriodChannel<SimChannel>;
+    type Instant = fugit::Instant<u64, 1, 1_000>;
+    type Duration = fugit::Duration<u64, 1, 1_000>;
+}
+
+type Instant = <TestMono as Monotonic>::Instant;
+type Duration = <TestMono as Monotonic>::Duration;
+
+/// Await `delay` and return the number of ticks since `start`.
+async fn released_after(start: Instant, delay: impl Future<Output = ()>) -> u64 {
+    delay.await;
+    (TestMono::now() - start).ticks()
+}
+
+/// Poll `future` once per tick until it completes.
+fn run<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let mut cx = Context::from_waker(Waker::noop());
+
+    loop {
+        SimTimer::service();
+        SimChannel::service();
+
+        if let Poll::Ready(v) = future.as_mut().poll(&mut cx) {
+            return v;
+        }
+
+        SimTimer::tick();
+    }
+}
+
+#[test]
+fn half_period_monotonic() {
+    HalfPeriodMonotonic::<SimTimer>::start();
+
+    // The 8-bit timer is extended far beyond its period.
+    for _ in 0..5_000 {
+        assert_eq!(
+            TestMono::now().ticks(),
+            SIM_REGS.ticks.load(Ordering::Relaxed)
+        );
+        SimTimer::tick();
+    }
+
+    // Delays shorter and longer than a timer period complete exactly on time.
+    for delay in [1, 10, 127, 128, 200, 255, 256, 257, 1_000, 3_333] {
+        let start = TestMono::now();
+        run(TestMono::delay_until(start + Duration::from_ticks(delay)));
+        assert_eq!((TestMono::now() - start).ticks(), delay);
+    }
+
+    // Both queues run independently on the same time base.
+    HalfPeriodChannel::<SimChannel>::start();
+    assert_eq!(BackgroundMono::now(), TestMono::now());
+
+    let start = TestMono::now();
+    let released = run(join4(
+        released_after(
+            start,
+            BackgroundMono::delay_until(start + Duration::from_ticks(300)),
+        ),
+        released_after(
+            start,
+            TestMono::delay_until(start + Duration::from_ticks(100)),
+        ),
+        released_after(
+            start,
+            BackgroundMono::delay_until(start + Duration::from_ticks(100)),
+        ),
+        released_after(
+            start,
+            TestMono::delay_until(start + Duration::from_ticks(700)),
+        ),
+    ));
+    assert_eq!(released, (300, 100, 100, 700));
+}
+
+#[test]
+#[should_panic(expected = "Monotonic must have skipped an interrupt!")]
+fn skipped_interrupt() {
+    HalfPeriodMonotonic::<SkippingTimer>::start();
+
+    // Run past the half-period and the overflow without servicing the interrupt.
+    for _ in 0..=0x100 {
+        SKIPPING_REGS.tick();
+    }
+
+    SkippingTimer::service();
+}
@@ -0,0 +1,136 @@
+//! A test that verifies [`select`] and [`Monotonic::select_timeout_at`].
+//!
+//! To run this test, you need to activate the `critical-section/std` feature.
+
+use std::{
+    future::{pending, poll_fn, Future},
+    pin::pin,
+    sync::atomic::{AtomicU64, Ordering},
+    task::{Context, Poll, Waker},
+};
+
+use parking_lot::Mutex;
+use rtic_time::{
+    monotonic::TimerQueueBasedMonotonic,
+    select::{select, Either, Either3},
+    timer_queue::{TimerQueue, TimerQueueBackend},
+    Monotonic, TimeoutError,
+};
+
+static NOW: AtomicU64 = AtomicU64::new(0);
+static COMPARE: Mutex<Option<u64>> = Mutex::new(None);
+static TIMER_QUEUE: TimerQueue<TestMonoBackend> = TimerQueue::new();
+
+struct TestMonoBackend;
+
+impl TestMonoBackend {
+    fn tick() {
+        let now = NOW.fetch_add(1, Ordering::Relaxed) + 1;
+
+        if Some(now) == *COMPARE.lock() {
+            unsafe { TIMER_QUEUE.on_monotonic_interrupt() };
+        }
+    }
+}
+
+impl TimerQueueBackend for TestMonoBackend {
+    type Ticks = u64;
+
+    fn now() -> Self::Ticks {
+        NOW.load(Ordering::Relaxed)
+    }
+
+    fn set_compare(instant: Self::Ticks) {
+        *COMPARE.lock() = Some(instant);
+    }
+
+    fn clear_compare_flag() {}
+
+    fn pend_interrupt() {}
+
+    fn timer_queue() -> &'static TimerQueue<Self> {
+        &TIMER_QUEUE
+    }
+}
+
+struct TestMono;
+
+impl TimerQueueBasedMonotonic for TestMono {
+    type Backend = TestMonoBackend;
+    type Instant = fugit::Instant<u64, 1, 1_000>;
+    type Duration = fugit::Duration<u64, 1, 1_000>;
+}
+
+/// A future that becomes ready with `value` once the time has reached `at`.
+fn ready_at<T: Copy>(at: u64, value: T) -> impl Future<Output = T> {
+    poll_fn(move |_| {
+        if NOW.load(Ordering::Relaxed) >= at {
+            Poll::Ready(value)
+        } else {
+            Poll::Pending
+        }
+    })
+}
+
+/// Poll `future` once per tick until it completes.
+fn run<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let mut cx = Context::from_waker(Waker::noop());
+
+    loop {
+        if let Poll::Ready(v) = future.as_mut().poll(&mut cx) {
+            return v;
+        }
+
+        TestMonoBackend::tick();
+    }
+}
+
+#[test]
+fn select_futures() {
+    TIMER_QUEUE.initialize(TestMonoBackend);
+
+    // First one to complete wins.
+    let start = NOW.load(Ordering::Relaxed);
+    let r = run(select((
+        ready_at(start + 5, 1u8),
+        ready_at(start + 3, true),
+    )));
+    assert_eq!(r, Either::Second(true));
+
+    // Ties are resolved in order.
+    let start = NOW.load(Ordering::Relaxed);
+    let r = run(select((
+        pending::<()>(),
+        ready_at(start + 2, 'a'),
+        ready_at(start + 2, 2u32),
+    )));
+    assert_eq!(r, Either3::Second('a'));
+
+    // Arrays report the index.
+    let start = NOW.load(Ordering::Relaxed);
+    let r = run(select([
+        ready_at(start + 9, 0),
+        ready_at(start + 4, 1),
+        ready_at(start + 6, 2),
+    ]));
+    assert_eq!(r, (1, 1));
+
+    // The deadline is reached before any of the futures.
+    let start = NOW.load(Ordering::Relaxed);
+    let deadline = <TestMono as Monotonic>::Instant::from_ticks(start + 10);
+    let r = run(TestMono::select_timeout_at(
+        deadline,
+        (ready_at(start + 20, ()), pending::<()>()),
+    ));
+    assert!(matches!(r, Err(TimeoutError)));
+    assert_eq!(NOW.load(Ordering::Relaxed), start + 10);
+
+    // One of the futures completes before the deadline.
+    let start = NOW.load(Ordering::Relaxed);
+    let r = run(TestMono::select_timeout_after(
+        <TestMono as Monotonic>::Duration::from_ticks(10),
+        (pending::<()>(), ready_at(start + 7, 7u64)),
+    ));
+    assert!(matches!(r, Ok(Either::Second(7))));
+}
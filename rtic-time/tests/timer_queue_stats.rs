@@ -0,0 +1,119 @@
+//! A test that verifies the statistics of [`TimerQueue`].
+//!
+//! To run this test, you need to activate the `critical-section/std` and `stats` features.
+
+#![cfg(feature = "stats")]
+
+use std::{
+    future::Future,
+    pin::pin,
+    sync::atomic::{AtomicU64, Ordering},
+    task::{Context, Poll, Waker},
+};
+
+use parking_lot::Mutex;
+use rtic_time::timer_queue::{TimerQueue, TimerQueueBackend, TimerQueueStats};
+
+static NOW: AtomicU64 = AtomicU64::new(0);
+static COMPARE: Mutex<Option<u64>> = Mutex::new(None);
+static TIMER_QUEUE: TimerQueue<TestMonoBackend> = TimerQueue::new();
+
+struct TestMonoBackend;
+
+impl TestMonoBackend {
+    /// Advance the time, running the interrupt `latency` ticks after the compare match.
+    fn tick(latency: u64) {
+        let now = NOW.fetch_add(1, Ordering::Relaxed) + 1;
+
+        let compare = *COMPARE.lock();
+        if compare.is_some_and(|compare| now == compare + latency) {
+            unsafe { TIMER_QUEUE.on_monotonic_interrupt() };
+        }
+    }
+}
+
+impl TimerQueueBackend for TestMonoBackend {
+    type Ticks = u64;
+
+    fn now() -> Self::Ticks {
+        NOW.load(Ordering::Relaxed)
+    }
+
+    fn set_compare(instant: Self::Ticks) {
+        *COMPARE.lock() = Some(instant);
+    }
+
+    fn clear_compare_flag() {}
+
+    fn pend_interrupt() {}
+
+    fn timer_queue() -> &'static TimerQueue<Self> {
+        &TIMER_QUEUE
+    }
+}
+
+fn poll<F: Future>(future: core::pin::Pin<&mut F>) -> Poll<F::Output> {
+    future.poll(&mut Context::from_waker(Waker::noop()))
+}
+
+#[test]
+fn timer_queue_stats() {
+    TIMER_QUEUE.initialize(TestMonoBackend);
+
+    let empty = TimerQueueStats {
+        len: 0,
+        max_len: 0,
+        late_wakeups: 0,
+        max_lateness: None,
+    };
+    assert_eq!(TIMER_QUEUE.stats(), empty);
+
+    let mut d1 = pin!(TIMER_QUEUE.delay_until(10));
+    let mut d2 = pin!(TIMER_QUEUE.delay_until(20));
+    let mut d3 = Box::pin(TIMER_QUEUE.delay_until(30));
+    assert!(poll(d1.as_mut()).is_pending());
+    assert!(poll(d2.as_mut()).is_pending());
+    assert!(poll(d3.as_mut()).is_pending());
+
+    // The interrupt arms the compare for the first waiter.
+    unsafe { TIMER_QUEUE.on_monotonic_interrupt() };
+    assert_eq!(TIMER_QUEUE.stats().len, 3);
+    assert_eq!(TIMER_QUEUE.stats().max_len, 3);
+
+    // Released on time.
+    while NOW.load(Ordering::Relaxed) < 10 {
+        TestMonoBackend::tick(0);
+    }
+    assert!(poll(d1.as_mut()).is_ready());
+    let stats = TIMER_QUEUE.stats();
+    assert_eq!(stats.len, 2);
+    assert_eq!(stats.max_len, 3);
+    assert_eq!(stats.late_wakeups, 0);
+    assert_eq!(stats.max_lateness, Some(0));
+
+    // Released 3 ticks late.
+    while NOW.load(Ordering::Relaxed) < 23 {
+        TestMonoBackend::tick(3);
+    }
+    assert!(poll(d2.as_mut()).is_ready());
+    let stats = TIMER_QUEUE.stats();
+    assert_eq!(stats.len, 1);
+    assert_eq!(stats.late_wakeups, 1);
+    assert_eq!(stats.max_lateness, Some(3));
+
+    // Resetting keeps the current length as the high-water mark.
+    TIMER_QUEUE.reset_stats();
+    assert_eq!(
+        TIMER_QUEUE.stats(),
+        TimerQueueStats {
+            len: 1,
+            max_len: 1,
+            late_wakeups: 0,
+            max_lateness: None,
+        }
+    );
+
+    // Dropping a waiter removes it from the queue.
+    drop(d3);
+    assert_eq!(TIMER_QUEUE.stats().len, 0);
+}
@@ -30,6 +30,15 @@ fn main() {
         "riscv-clint-backend" => {
             println!("cargo:rustc-cfg=feature=\"riscv-slic\"");
         }
+        "riscv-clic-backend" => {
+            println!("cargo:rustc-cfg=feature=\"riscv-clic\"");
+        }
+        "riscv-plic-backend" => {
+            println!("cargo:rustc-cfg=feature=\"riscv-plic\"");
+        }
+        "external-backend" => {
+            // The runtime of the backend lives in a crate of its own
+        }
         _ => {
             panic!("Unknown backend feature: {:?}", backend);
         }
@@ -0,0 +1,30 @@
+//! Memory used by the executors of the software tasks.
+//!
+//! The application module has an `executors` module with a `TASKS` table describing the
+//! executors of each software task, to find out which task uses the memory reported by the
+//! `executors_size` field of the `init::Context`.
+//!
+//! The size of the future of a task can be bounded with `#[task(max_size = N)]`, the
+//! application then fails to compile when the future is larger than `N` bytes.
+
+/// The executors of a software task
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExecutorInfo {
+    /// Name of the task.
+    pub name: &'static str,
+    /// Priority of the task.
+    pub priority: u8,
+    /// Number of executors, the `capacity` of the task.
+    pub capacity: u8,
+    /// Size of the future of the task in bytes.
+    pub future_size: usize,
+    /// Size of one executor in bytes, the future and the state of the executor.
+    pub executor_size: usize,
+}
+
+impl ExecutorInfo {
+    /// Size of all the executors of the task in bytes.
+    pub const fn total_size(&self) -> usize {
+        self.executor_size * self.capacity as usize
+    }
+}
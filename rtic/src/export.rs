@@ -2,6 +2,13 @@ pub use bare_metal::CriticalSection;
 pub use portable_atomic as atomic;
 
 pub mod executor;
+pub mod mailbox;
+pub mod queue;
+pub mod spawn_queue;
+#[cfg(feature = "stack-usage")]
+pub mod stack;
+#[cfg(feature = "measure-timing")]
+pub mod timing;
 
 // Cortex-M target (any)
 #[cfg(feature = "cortex-m")]
@@ -40,8 +47,21 @@ mod slic;
 #[cfg(feature = "riscv-slic")]
 pub use slic::*;
 
+#[cfg(feature = "riscv-clic")]
+mod riscv_clic;
+#[cfg(feature = "riscv-clic")]
+pub use riscv_clic::*;
+
+#[cfg(feature = "riscv-plic")]
+mod riscv_plic;
+#[cfg(feature = "riscv-plic")]
+pub use riscv_plic::*;
+
 #[inline(always)]
 pub fn assert_send<T: Send>() {}
 
 #[inline(always)]
 pub fn assert_sync<T: Sync>() {}
+
+#[inline(always)]
+pub fn assert_cross_core<T: crate::CrossCore>() {}
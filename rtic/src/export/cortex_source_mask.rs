@@ -19,17 +19,47 @@ compile_error!(
 #[derive(Copy, Clone)]
 pub struct Mask<const M: usize>([u32; M]);
 
-pub const fn create_mask<const N: usize, const M: usize>(list_of_shifts: [u32; N]) -> Mask<M> {
-    let mut mask = Mask([0; M]);
+/// Number of `ISER`/`ICER` registers of the NVIC, each covering 32 interrupts
+const NVIC_CHUNKS: usize = if cfg!(feature = "thumbv6-backend") {
+    1
+} else {
+    16
+};
+
+/// Number of masks needed for a device with `nvic_prio_bits` priority bits, one for each priority
+/// below the highest one, which is locked with a global critical section.
+pub const fn mask_levels(nvic_prio_bits: u8) -> usize {
+    (1 << nvic_prio_bits) - 1
+}
+
+/// Creates the mask of each priority level from a list of `(priority, interrupt number)` pairs.
+///
+/// The mask at index `i` holds the interrupts at priority `i + 1`. Interrupts at the highest
+/// priority, `L + 1`, have no mask as a lock up to it is a global critical section.
+pub const fn create_masks<const N: usize, const M: usize, const L: usize>(
+    interrupts: [(u8, u32); N],
+) -> [Mask<M>; L] {
+    let mut masks = [Mask([0; M]); L];
     let mut i = 0;
 
     while i < N {
-        let shift = list_of_shifts[i];
+        let (priority, id) = interrupts[i];
         i += 1;
-        mask = mask.set_bit(shift);
+
+        if priority == 0 {
+            panic!("Generating masks for source masking failed! An interrupt can not run at priority 0.");
+        }
+
+        // The priorities are checked against `NVIC_PRIO_BITS` by the `#[app]`
+        if priority as usize > L {
+            continue;
+        }
+
+        let level = priority as usize - 1;
+        masks[level] = masks[level].set_bit(id);
     }
 
-    mask
+    masks
 }
 
 /// Compute the number of u32 chunks needed to store the Mask value.
@@ -48,7 +78,14 @@ pub const fn compute_mask_chunks<const L: usize>(ids: [u32; L]) -> usize {
             max = id;
         }
     }
-    (max + 32) / 32
+
+    let chunks = (max + 32) / 32;
+
+    if chunks > NVIC_CHUNKS {
+        panic!("Generating masks for source masking failed! An interrupt number is more than supported by the NVIC.");
+    }
+
+    chunks
 }
 
 impl<const M: usize> Mask<M> {
@@ -98,7 +135,7 @@ where
 /// (Sub)-zero as:
 /// - Either zero OH (lock optimized out), or
 /// - Amounting to an optimal assembly implementation
-///   - if ceiling == (1 << nvic_prio_bits)
+///   - if ceiling == (1 << nvic_prio_bits), the highest priority without a mask
 ///     - we execute the closure in a global critical section (interrupt free)
 ///     - CS entry cost, single write to core register
 ///     - CS exit cost, single write to core register
@@ -119,13 +156,13 @@ where
 ///
 /// These possible solutions are set goals for future work
 #[inline(always)]
-pub unsafe fn lock<T, R, const M: usize>(
+pub unsafe fn lock<T, R, const M: usize, const L: usize>(
     ptr: *mut T,
     ceiling: u8,
-    masks: &[Mask<M>; 3],
+    masks: &[Mask<M>; L],
     f: impl FnOnce(&mut T) -> R,
 ) -> R {
-    if ceiling >= 4 {
+    if ceiling as usize > L {
         // safe to manipulate outside critical section
         // execute closure under protection of raised system ceiling
 
@@ -148,10 +185,10 @@ pub unsafe fn lock<T, R, const M: usize>(
 }
 
 #[inline(always)]
-pub const fn compute_mask<const M: usize>(
+pub const fn compute_mask<const M: usize, const L: usize>(
     from_prio: u8,
     to_prio: u8,
-    masks: &[Mask<M>; 3],
+    masks: &[Mask<M>; L],
 ) -> Mask<M> {
     let mut res = Mask([0; M]);
 
@@ -213,3 +250,51 @@ unsafe fn clear_enable_mask<const M: usize>(mask: Mask<M>) {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn masks_cover_all_priority_levels() {
+        const LEVELS: usize = mask_levels(3);
+        const MASKS: [Mask<1>; LEVELS] = create_masks([(1, 0), (5, 3), (7, 31), (5, 4)]);
+
+        assert_eq!(LEVELS, 7);
+        assert_eq!(MASKS[0].0, [1]);
+        assert_eq!(MASKS[4].0, [1 << 3 | 1 << 4]);
+        assert_eq!(MASKS[6].0, [1 << 31]);
+        assert_eq!(compute_mask(0, 5, &MASKS).0, [1 | 1 << 3 | 1 << 4]);
+        assert_eq!(
+            compute_mask(0, 7, &MASKS).0,
+            [1 | 1 << 3 | 1 << 4 | 1 << 31]
+        );
+    }
+
+    #[test]
+    fn highest_priority_has_no_mask() {
+        const LEVELS: usize = mask_levels(2);
+        const MASKS: [Mask<1>; LEVELS] = create_masks([(1, 0), (4, 1), (3, 2)]);
+
+        assert_eq!(LEVELS, 3);
+        assert_eq!(MASKS[0].0, [1]);
+        assert_eq!(MASKS[1].0, [0]);
+        assert_eq!(MASKS[2].0, [1 << 2]);
+        assert_eq!(compute_mask(0, 3, &MASKS).0, [1 | 1 << 2]);
+    }
+
+    // ARMv6-M has a single `ISER` register
+    #[cfg(not(feature = "thumbv6-backend"))]
+    #[test]
+    fn masks_cover_more_than_32_interrupts() {
+        const IDS: [u32; 3] = [2, 40, 100];
+        const CHUNKS: usize = compute_mask_chunks(IDS);
+        const MASKS: [Mask<CHUNKS>; 3] = create_masks([(1, 2), (2, 40), (3, 100)]);
+
+        assert_eq!(CHUNKS, 4);
+        assert_eq!(MASKS[0].0, [1 << 2, 0, 0, 0]);
+        assert_eq!(MASKS[1].0, [0, 1 << 8, 0, 0]);
+        assert_eq!(MASKS[2].0, [0, 0, 0, 1 << 4]);
+        assert_eq!(compute_mask(1, 3, &MASKS).0, [0, 1 << 8, 0, 1 << 4]);
+    }
+}
@@ -1,11 +1,15 @@
-use super::atomic::{AtomicBool, AtomicPtr, Ordering};
+use super::atomic::{AtomicBool, AtomicPtr, AtomicU8, Ordering};
 use core::{
+    alloc::Layout,
     cell::UnsafeCell,
     future::Future,
+    marker::PhantomData,
     mem::{self, ManuallyDrop, MaybeUninit},
     pin::Pin,
+    ptr,
     task::{Context, Poll, RawWaker, RawWakerVTable, Waker},
 };
+use rtic_common::waker_registration::CriticalSectionWakerRegistration;
 
 static WAKER_VTABLE: RawWakerVTable =
     RawWakerVTable::new(waker_clone, waker_wake, waker_wake, waker_drop);
@@ -58,12 +62,111 @@ impl Default for AsyncTaskExecutorPtr {
     }
 }
 
+/// Alignment `N`, implemented for the powers of two up to 4096.
+pub struct Align<const N: usize>;
+
+/// A type of the alignment of `Align<N>`.
+pub trait Alignment {
+    type Archetype;
+}
+
+macro_rules! alignment {
+    ($($name:ident = $n:literal),*) => {
+        $(
+            #[repr(align($n))]
+            pub struct $name;
+
+            impl Alignment for Align<$n> {
+                type Archetype = $name;
+            }
+        )*
+    };
+}
+
+alignment!(
+    Align1 = 1,
+    Align2 = 2,
+    Align4 = 4,
+    Align8 = 8,
+    Align16 = 16,
+    Align32 = 32,
+    Align64 = 64,
+    Align128 = 128,
+    Align256 = 256,
+    Align512 = 512,
+    Align1024 = 1024,
+    Align2048 = 2048,
+    Align4096 = 4096
+);
+
+/// Static memory of an executor, placed in a link section by `#[app(executors_section = ..)]`.
+///
+/// The memory is left uninitialized, so that it can be in a section that is not initialized by
+/// the runtime, the executor is constructed in place by `main`.
+#[repr(C)]
+pub struct ExecutorStorage<const SIZE: usize, const ALIGN: usize>
+where
+    Align<ALIGN>: Alignment,
+{
+    _align: [<Align<ALIGN> as Alignment>::Archetype; 0],
+    memory: UnsafeCell<MaybeUninit<[u8; SIZE]>>,
+}
+
+unsafe impl<const SIZE: usize, const ALIGN: usize> Sync for ExecutorStorage<SIZE, ALIGN> where
+    Align<ALIGN>: Alignment
+{
+}
+
+impl<const SIZE: usize, const ALIGN: usize> ExecutorStorage<SIZE, ALIGN>
+where
+    Align<ALIGN>: Alignment,
+{
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> Self {
+        Self {
+            _align: [],
+            memory: UnsafeCell::new(MaybeUninit::uninit()),
+        }
+    }
+
+    /// Constructs an executor in the storage, without a temporary on the stack.
+    ///
+    /// # Safety
+    ///
+    /// Must be called once, before any use of the executor, by `main`.
+    #[inline(always)]
+    unsafe fn init<F: Future>(&'static self) -> &'static ManuallyDrop<AsyncTaskExecutor<F>> {
+        const {
+            let layout = Layout::new::<ManuallyDrop<AsyncTaskExecutor<F>>>();
+            assert!(layout.size() <= SIZE && layout.align() <= ALIGN);
+        }
+
+        // `ManuallyDrop` is `repr(transparent)`.
+        let ptr = self.memory.get() as *mut AsyncTaskExecutor<F>;
+        AsyncTaskExecutor::init_in_place(ptr);
+        &*(ptr as *const ManuallyDrop<AsyncTaskExecutor<F>>)
+    }
+}
+
+// Bits of `AsyncTaskExecutor::state`.
+/// The executor holds a future.
+const RUNNING: u8 = 1 << 0;
+/// A `TaskHandle` refers to the executor, the executor can not be reused until it is dropped.
+const HANDLE: u8 = 1 << 1;
+/// Cancellation was requested by the handle.
+const CANCEL: u8 = 1 << 2;
+/// The future was dropped before completion, there is no output.
+const CANCELLED: u8 = 1 << 3;
+
 /// Executor for an async task.
 pub struct AsyncTaskExecutor<F: Future> {
-    // `task` is protected by the `running` flag.
+    // `task` is protected by the `RUNNING` bit.
     task: UnsafeCell<MaybeUninit<F>>,
-    running: AtomicBool,
+    // `output` is written before clearing `RUNNING`, and owned by the `HANDLE` afterwards.
+    output: UnsafeCell<MaybeUninit<F::Output>>,
+    state: AtomicU8,
     pending: AtomicBool,
+    joiner: CriticalSectionWakerRegistration,
 }
 
 unsafe impl<F: Future> Sync for AsyncTaskExecutor<F> {}
@@ -86,6 +189,40 @@ macro_rules! from_ptr_n_args {
     };
 }
 
+macro_rules! in_storage_n_args {
+    ($name:ident, $($t:ident),*) => {
+        /// Constructs an executor of the future returned by `_f` in `storage`.
+        ///
+        /// # Safety
+        ///
+        /// Must be called once, before any use of the executor, by `main`.
+        #[inline(always)]
+        pub unsafe fn $name<$($t,)* Fun: Fn($($t,)*) -> F, const SIZE: usize, const ALIGN: usize>(
+            _f: Fun,
+            storage: &'static ExecutorStorage<SIZE, ALIGN>,
+        ) -> &'static ManuallyDrop<Self>
+        where
+            Align<ALIGN>: Alignment,
+        {
+            storage.init()
+        }
+    };
+}
+
+macro_rules! size_n_args {
+    ($future:ident, $executor:ident, $($t:ident),*) => {
+        /// Size of the future returned by `_f`.
+        pub const fn $future<$($t,)* Fun: Fn($($t,)*) -> F>(_f: &Fun) -> usize {
+            mem::size_of::<F>()
+        }
+
+        /// Layout of an executor of the future returned by `_f`.
+        pub const fn $executor<$($t,)* Fun: Fn($($t,)*) -> F>(_f: &Fun) -> Layout {
+            Layout::new::<Self>()
+        }
+    };
+}
+
 impl<F: Future> Default for AsyncTaskExecutor<F> {
     fn default() -> Self {
         Self::new()
@@ -98,11 +235,26 @@ impl<F: Future> AsyncTaskExecutor<F> {
     pub const fn new() -> Self {
         Self {
             task: UnsafeCell::new(MaybeUninit::uninit()),
-            running: AtomicBool::new(false),
+            output: UnsafeCell::new(MaybeUninit::uninit()),
+            state: AtomicU8::new(0),
             pending: AtomicBool::new(false),
+            joiner: CriticalSectionWakerRegistration::new(),
         }
     }
 
+    /// Initializes the executor at `ptr`, which is only written to.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be valid for writes and aligned.
+    #[inline(always)]
+    unsafe fn init_in_place(ptr: *mut Self) {
+        // `task` and `output` are `MaybeUninit`, they need no initialization.
+        ptr::addr_of_mut!((*ptr).state).write(AtomicU8::new(0));
+        ptr::addr_of_mut!((*ptr).pending).write(AtomicBool::new(false));
+        ptr::addr_of_mut!((*ptr).joiner).write(CriticalSectionWakerRegistration::new());
+    }
+
     // Support for up to 16 arguments on async functions. Should be
     // enough for now, else extend this list.
     new_n_args!(new_0_args,);
@@ -152,10 +304,64 @@ impl<F: Future> AsyncTaskExecutor<F> {
     #[rustfmt::skip]
     from_ptr_n_args!(from_ptr_16_args, A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11, A12, A13, A14, A15, A16);
 
+    in_storage_n_args!(in_storage_0_args,);
+    in_storage_n_args!(in_storage_1_args, A1);
+    in_storage_n_args!(in_storage_2_args, A1, A2);
+    in_storage_n_args!(in_storage_3_args, A1, A2, A3);
+    in_storage_n_args!(in_storage_4_args, A1, A2, A3, A4);
+    in_storage_n_args!(in_storage_5_args, A1, A2, A3, A4, A5);
+    in_storage_n_args!(in_storage_6_args, A1, A2, A3, A4, A5, A6);
+    in_storage_n_args!(in_storage_7_args, A1, A2, A3, A4, A5, A6, A7);
+    in_storage_n_args!(in_storage_8_args, A1, A2, A3, A4, A5, A6, A7, A8);
+    in_storage_n_args!(in_storage_9_args, A1, A2, A3, A4, A5, A6, A7, A8, A9);
+    in_storage_n_args!(in_storage_10_args, A1, A2, A3, A4, A5, A6, A7, A8, A9, A10);
+    #[rustfmt::skip]
+    in_storage_n_args!(in_storage_11_args, A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11);
+    #[rustfmt::skip]
+    in_storage_n_args!(in_storage_12_args, A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11, A12);
+    #[rustfmt::skip]
+    in_storage_n_args!(in_storage_13_args, A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11, A12, A13);
+    #[rustfmt::skip]
+    in_storage_n_args!(in_storage_14_args, A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11, A12, A13, A14);
+    #[rustfmt::skip]
+    in_storage_n_args!(in_storage_15_args, A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11, A12, A13, A14, A15);
+    #[rustfmt::skip]
+    in_storage_n_args!(in_storage_16_args, A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11, A12, A13, A14, A15, A16);
+
+    size_n_args!(future_size_0_args, executor_layout_0_args,);
+    size_n_args!(future_size_1_args, executor_layout_1_args, A1);
+    size_n_args!(future_size_2_args, executor_layout_2_args, A1, A2);
+    size_n_args!(future_size_3_args, executor_layout_3_args, A1, A2, A3);
+    size_n_args!(future_size_4_args, executor_layout_4_args, A1, A2, A3, A4);
+    #[rustfmt::skip]
+    size_n_args!(future_size_5_args, executor_layout_5_args, A1, A2, A3, A4, A5);
+    #[rustfmt::skip]
+    size_n_args!(future_size_6_args, executor_layout_6_args, A1, A2, A3, A4, A5, A6);
+    #[rustfmt::skip]
+    size_n_args!(future_size_7_args, executor_layout_7_args, A1, A2, A3, A4, A5, A6, A7);
+    #[rustfmt::skip]
+    size_n_args!(future_size_8_args, executor_layout_8_args, A1, A2, A3, A4, A5, A6, A7, A8);
+    #[rustfmt::skip]
+    size_n_args!(future_size_9_args, executor_layout_9_args, A1, A2, A3, A4, A5, A6, A7, A8, A9);
+    #[rustfmt::skip]
+    size_n_args!(future_size_10_args, executor_layout_10_args, A1, A2, A3, A4, A5, A6, A7, A8, A9, A10);
+    #[rustfmt::skip]
+    size_n_args!(future_size_11_args, executor_layout_11_args, A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11);
+    #[rustfmt::skip]
+    size_n_args!(future_size_12_args, executor_layout_12_args, A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11, A12);
+    #[rustfmt::skip]
+    size_n_args!(future_size_13_args, executor_layout_13_args, A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11, A12, A13);
+    #[rustfmt::skip]
+    size_n_args!(future_size_14_args, executor_layout_14_args, A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11, A12, A13, A14);
+    #[rustfmt::skip]
+    size_n_args!(future_size_15_args, executor_layout_15_args, A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11, A12, A13, A14, A15);
+    #[rustfmt::skip]
+    size_n_args!(future_size_16_args, executor_layout_16_args, A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11, A12, A13, A14, A15, A16);
+
     /// Check if there is an active task in the executor.
     #[inline(always)]
     pub fn is_running(&self) -> bool {
-        self.running.load(Ordering::Relaxed)
+        self.state.load(Ordering::Relaxed) & RUNNING != 0
     }
 
     /// Checks if a waker has pended the executor and simultaneously clears the flag.
@@ -176,36 +382,297 @@ impl<F: Future> AsyncTaskExecutor<F> {
     /// Allocate the executor. To use with `spawn`.
     #[inline(always)]
     pub unsafe fn try_allocate(&self) -> bool {
-        // Try to reserve the executor for a future.
-        self.running
-            .compare_exchange(false, true, Ordering::AcqRel, Ordering::Relaxed)
+        // Try to reserve the executor for a future, the executor is free if it neither runs
+        // a future nor is referred to by a handle.
+        self.state
+            .fetch_update(Ordering::AcqRel, Ordering::Relaxed, |state| {
+                if state & (RUNNING | HANDLE) == 0 {
+                    Some(RUNNING | HANDLE)
+                } else {
+                    None
+                }
+            })
             .is_ok()
     }
 
     /// Spawn a future
     #[inline(always)]
     pub unsafe fn spawn(&self, future: F) {
-        // This unsafe is protected by `running` being false and the atomic setting it to true.
+        // This unsafe is protected by `RUNNING` being cleared and the atomic setting it.
         unsafe {
             self.task.get().write(MaybeUninit::new(future));
         }
         self.set_pending();
     }
 
+    /// Create the handle of the future that was just spawned. To use after `spawn`.
+    ///
+    /// `pend` must pend the dispatcher of the task.
+    #[inline(always)]
+    pub unsafe fn handle(&'static self, pend: fn()) -> TaskHandle<F::Output> {
+        TaskHandle {
+            exec: self,
+            pend,
+            _output: PhantomData,
+        }
+    }
+
     /// Poll the future in the executor.
     #[inline(always)]
     pub fn poll(&self, wake: fn()) {
         if self.is_running() && self.check_and_clear_pending() {
+            if self.state.load(Ordering::Acquire) & CANCEL != 0 {
+                // SAFETY: The future is live as `RUNNING` is set, and it is never polled again.
+                unsafe { ptr::drop_in_place(self.task.get() as *mut F) };
+                self.finish(CANCELLED);
+                return;
+            }
+
             let waker = unsafe { Waker::from_raw(RawWaker::new(wake as *const (), &WAKER_VTABLE)) };
             let mut cx = Context::from_waker(&waker);
             let future = unsafe { Pin::new_unchecked(&mut *(self.task.get() as *mut F)) };
 
             match future.poll(&mut cx) {
-                Poll::Ready(_) => {
-                    self.running.store(false, Ordering::Release);
+                Poll::Ready(output) => {
+                    // SAFETY: The output is only accessed by the handle after `RUNNING` is
+                    // cleared in `finish`.
+                    unsafe { self.output.get().write(MaybeUninit::new(output)) };
+                    self.finish(0);
                 }
                 Poll::Pending => {}
             }
         }
     }
+
+    /// Mark the executor as free and notify the handle.
+    #[inline(always)]
+    fn finish(&self, flags: u8) {
+        // Ordering::AcqRel so that the handle sees the output.
+        let prev = self
+            .state
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |state| {
+                Some((state & !(RUNNING | CANCELLED)) | flags)
+            })
+            .unwrap_or_else(|state| state);
+
+        if prev & HANDLE == 0 {
+            // Nobody is interested in the output.
+            if flags & CANCELLED == 0 {
+                // SAFETY: The output was just written, and there is no handle to read it. A
+                // new future spawned in the meantime only writes the output when polled, which
+                // happens in this dispatcher.
+                unsafe { ptr::drop_in_place(self.output.get() as *mut F::Output) };
+            }
+        } else {
+            self.joiner.wake();
+        }
+    }
+
+    /// Release the handle, dropping the output if it was not taken.
+    fn release(&self) {
+        // Detach from a running future, `finish` then drops the output.
+        let detached = self
+            .state
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |state| {
+                if state & RUNNING != 0 {
+                    Some(state & !HANDLE)
+                } else {
+                    None
+                }
+            });
+
+        if let Err(state) = detached {
+            if state & CANCELLED == 0 {
+                // SAFETY: The future completed and the output is owned by the handle. The
+                // executor can not be reused before `HANDLE` is cleared below.
+                unsafe { ptr::drop_in_place(self.output.get() as *mut F::Output) };
+            }
+
+            self.state.fetch_and(!HANDLE, Ordering::Release);
+        }
+    }
+
+    /// Take the output after the future finished, the handle is released.
+    fn take_output(&self) -> Result<F::Output, Cancelled> {
+        let state = self.state.load(Ordering::Acquire);
+        debug_assert!(state & RUNNING == 0);
+
+        let output = if state & CANCELLED == 0 {
+            // SAFETY: The future completed and the output is owned by the handle. The
+            // executor can not be reused before `HANDLE` is cleared below.
+            Ok(unsafe { self.output.get().read().assume_init() })
+        } else {
+            Err(Cancelled)
+        };
+
+        self.state.fetch_and(!HANDLE, Ordering::Release);
+
+        output
+    }
+}
+
+/// The type erased part of an executor used by [`TaskHandle`].
+trait Task<T>: Sync {
+    fn is_running(&self) -> bool;
+    fn cancel(&self);
+    fn poll_join(&self, cx: &mut Context<'_>) -> Poll<Result<T, Cancelled>>;
+    fn release(&self);
+}
+
+impl<F: Future> Task<F::Output> for AsyncTaskExecutor<F> {
+    fn is_running(&self) -> bool {
+        AsyncTaskExecutor::is_running(self)
+    }
+
+    fn cancel(&self) {
+        self.state.fetch_or(CANCEL, Ordering::Release);
+        self.set_pending();
+    }
+
+    fn poll_join(&self, cx: &mut Context<'_>) -> Poll<Result<F::Output, Cancelled>> {
+        // Register before checking, so that the completion is not missed.
+        self.joiner.register(cx.waker());
+
+        if AsyncTaskExecutor::is_running(self) {
+            Poll::Pending
+        } else {
+            Poll::Ready(self.take_output())
+        }
+    }
+
+    fn release(&self) {
+        AsyncTaskExecutor::release(self)
+    }
+}
+
+/// Returns `Pending` once, so the dispatcher polls its other tasks before the task continues.
+pub async fn yield_now() {
+    let mut yielded = false;
+
+    core::future::poll_fn(|cx| {
+        if yielded {
+            Poll::Ready(())
+        } else {
+            yielded = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    })
+    .await
+}
+
+/// The task was cancelled before it completed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cancelled;
+
+/// Handle to a spawned instance of a software task, returned by `spawn`.
+///
+/// The handle can await the completion of the instance with [`join`](TaskHandle::join),
+/// request its cancellation and check if it is still running. While the handle exists, the
+/// instance it refers to is not reused by a later `spawn`, so drop it as soon as it is not
+/// needed anymore. Dropping the handle does not affect the running task.
+pub struct TaskHandle<T: 'static> {
+    exec: &'static dyn Task<T>,
+    pend: fn(),
+    // The handle hands out the output, so it is only `Send` / `Sync` if the output is.
+    _output: PhantomData<T>,
+}
+
+impl<T> TaskHandle<T> {
+    /// Check if the task is still running.
+    #[inline]
+    pub fn is_running(&self) -> bool {
+        self.exec.is_running()
+    }
+
+    /// Request cancellation of the task.
+    ///
+    /// The future of the task is dropped instead of being polled the next time the dispatcher
+    /// runs, await [`join`](TaskHandle::join) to know when that happened. A task that already
+    /// completed is not affected.
+    pub fn cancel(&self) {
+        if self.exec.is_running() {
+            self.exec.cancel();
+            (self.pend)();
+        }
+    }
+
+    /// Wait for the task to finish and return its output, or [`Cancelled`] if it was cancelled
+    /// before completion.
+    pub async fn join(self) -> Result<T, Cancelled> {
+        let result = core::future::poll_fn(|cx| self.exec.poll_join(cx)).await;
+
+        // The handle was released by taking the output.
+        mem::forget(self);
+
+        result
+    }
+}
+
+impl<T> Drop for TaskHandle<T> {
+    fn drop(&mut self) {
+        self.exec.release();
+    }
+}
+
+/// Error message of a task whose future is larger than its `max_size`, built at compile time.
+pub struct MaxSizeExceeded {
+    buf: [u8; 256],
+    len: usize,
+}
+
+impl MaxSizeExceeded {
+    pub const fn new(task: &str, size: usize, max_size: usize) -> Self {
+        Self {
+            buf: [0; 256],
+            len: 0,
+        }
+        .push_str("the future of task `")
+        .push_str(task)
+        .push_str("` is ")
+        .push_usize(size)
+        .push_str(" bytes, ")
+        .push_usize(size - max_size)
+        .push_str(" more than its `max_size` of ")
+        .push_usize(max_size)
+    }
+
+    const fn push_str(mut self, s: &str) -> Self {
+        let bytes = s.as_bytes();
+        let mut i = 0;
+        while i < bytes.len() && self.len < self.buf.len() {
+            self.buf[self.len] = bytes[i];
+            self.len += 1;
+            i += 1;
+        }
+        self
+    }
+
+    const fn push_usize(mut self, n: usize) -> Self {
+        let mut digits = [0; 20];
+        let mut n_digits = 0;
+        let mut n = n;
+        loop {
+            digits[n_digits] = b'0' + (n % 10) as u8;
+            n_digits += 1;
+            n /= 10;
+            if n == 0 {
+                break;
+            }
+        }
+        while n_digits > 0 && self.len < self.buf.len() {
+            n_digits -= 1;
+            self.buf[self.len] = digits[n_digits];
+            self.len += 1;
+        }
+        self
+    }
+
+    pub const fn as_str(&self) -> &str {
+        match core::str::from_utf8(self.buf.split_at(self.len).0) {
+            Ok(s) => s,
+            // A task name cut in the middle of a character
+            Err(_) => "the future of a task is larger than its `max_size`",
+        }
+    }
 }
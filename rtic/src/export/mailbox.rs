@@ -0,0 +1,12 @@
+//============
+// Mailbox
+
+/// Bounded queue of cross-core spawn requests for a software task.
+///
+/// Any core can push requests, only the core running the task drains them, in the handler of its
+/// inter-processor interrupt. The queue is lock-free with the `lock-free-queues` feature, see
+/// [`Queue`](super::queue::Queue).
+///
+/// Without the feature, or on targets without compare-and-swap instructions, the queue uses
+/// critical sections, which must then be implemented for all cores.
+pub type Mailbox<T, const N: usize> = super::queue::Queue<T, N>;
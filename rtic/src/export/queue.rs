@@ -0,0 +1,127 @@
+use core::{cell::UnsafeCell, mem::MaybeUninit};
+
+//============
+// Queue
+
+/// Bounded FIFO queue of the spawn queues and mailboxes.
+///
+/// The queue is lock-free with the `lock-free-queues` feature, see
+/// [`LockFreeQueue`](rtic_common::lock_free_queue::LockFreeQueue), and protected by critical
+/// sections otherwise.
+#[cfg(feature = "lock-free-queues")]
+pub type Queue<T, const N: usize> = rtic_common::lock_free_queue::LockFreeQueue<T, N>;
+
+/// Bounded FIFO queue of the spawn queues and mailboxes.
+///
+/// The queue is protected by critical sections, or lock-free with the `lock-free-queues` feature.
+#[cfg(not(feature = "lock-free-queues"))]
+pub type Queue<T, const N: usize> = CsQueue<T, N>;
+
+/// Bounded FIFO queue protected by critical sections, with the API of `LockFreeQueue`.
+pub struct CsQueue<T, const N: usize> {
+    // `buf`, `head` and `len` are only accessed in critical sections.
+    buf: UnsafeCell<[MaybeUninit<T>; N]>,
+    head: UnsafeCell<usize>,
+    len: UnsafeCell<usize>,
+}
+
+unsafe impl<T: Send, const N: usize> Sync for CsQueue<T, N> {}
+
+impl<T, const N: usize> Default for CsQueue<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> CsQueue<T, N> {
+    /// Create a new, empty queue.
+    pub const fn new() -> Self {
+        Self {
+            // SAFETY: An array of `MaybeUninit` does not need initialization.
+            buf: UnsafeCell::new(unsafe { MaybeUninit::uninit().assume_init() }),
+            head: UnsafeCell::new(0),
+            len: UnsafeCell::new(0),
+        }
+    }
+
+    /// Push a value to the back of the queue. Returns the value if the queue is full.
+    pub fn push(&self, value: T) -> Result<(), T> {
+        critical_section::with(|_| {
+            // SAFETY: Access is protected by the critical section.
+            unsafe {
+                let len = *self.len.get();
+                if len == N {
+                    return Err(value);
+                }
+
+                let idx = (*self.head.get() + len) % N;
+                (*self.buf.get())[idx].write(value);
+                *self.len.get() = len + 1;
+            }
+
+            Ok(())
+        })
+    }
+
+    /// Pop the value at the front of the queue.
+    pub fn pop(&self) -> Option<T> {
+        // SAFETY: Access is protected by the critical section.
+        critical_section::with(|_| unsafe { self.pop_front() })
+    }
+
+    /// Hand the values to `f` in order, stopping at the first one `f` returns, which is kept at
+    /// the front of the queue. The queue is drained in a single critical section.
+    ///
+    /// # Safety
+    ///
+    /// Same contract as `LockFreeQueue::drain`, there must be a single consumer.
+    pub unsafe fn drain(&self, mut f: impl FnMut(T) -> Result<(), T>) {
+        critical_section::with(|_| {
+            while let Some(value) = self.pop_front() {
+                if let Err(value) = f(value) {
+                    // There is space, the slot was just freed and nothing was pushed since.
+                    let idx = (*self.head.get() + N - 1) % N;
+                    (*self.buf.get())[idx].write(value);
+                    *self.head.get() = idx;
+                    *self.len.get() += 1;
+                    break;
+                }
+            }
+        })
+    }
+
+    /// Check if the queue is empty.
+    pub fn is_empty(&self) -> bool {
+        // SAFETY: Access is protected by the critical section.
+        critical_section::with(|_| unsafe { *self.len.get() == 0 })
+    }
+
+    /// Check if the queue is full.
+    pub fn is_full(&self) -> bool {
+        // SAFETY: Access is protected by the critical section.
+        critical_section::with(|_| unsafe { *self.len.get() == N })
+    }
+
+    /// # Safety
+    ///
+    /// Must be called in a critical section.
+    unsafe fn pop_front(&self) -> Option<T> {
+        let len = *self.len.get();
+        if len == 0 {
+            return None;
+        }
+
+        // The slot at `head` is initialized as the queue is not empty.
+        let idx = *self.head.get();
+        let value = (*self.buf.get())[idx].assume_init_read();
+        *self.head.get() = (idx + 1) % N;
+        *self.len.get() = len - 1;
+        Some(value)
+    }
+}
+
+impl<T, const N: usize> Drop for CsQueue<T, N> {
+    fn drop(&mut self) {
+        while self.pop().is_some() {}
+    }
+}
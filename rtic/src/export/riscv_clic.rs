@@ -0,0 +1,155 @@
+use portable_atomic::{AtomicUsize, Ordering};
+pub use riscv::interrupt;
+
+#[cfg(all(feature = "riscv-clic", not(feature = "riscv-clic-backend")))]
+compile_error!("Building for the CLIC, but 'riscv-clic-backend' not selected");
+
+/// Base address of the memory mapped CLIC registers, set by `clic_init`
+static CLIC_BASE: AtomicUsize = AtomicUsize::new(0);
+
+/// Offset of the per interrupt registers: `clicintip`, `clicintie`, `clicintattr` and
+/// `clicintctl`, one byte each
+const CLICINT: usize = 0x1000;
+const CLICINTIP: usize = 0;
+const CLICINTIE: usize = 1;
+const CLICINTATTR: usize = 2;
+const CLICINTCTL: usize = 3;
+
+/// Machine mode interrupt level threshold
+mod mintthresh {
+    #[inline(always)]
+    pub fn read() -> u8 {
+        let r: usize;
+        unsafe { core::arch::asm!("csrr {}, 0x347", out(reg) r, options(nomem, nostack)) };
+        r as u8
+    }
+
+    #[inline(always)]
+    pub unsafe fn write(level: u8) {
+        core::arch::asm!("csrw 0x347, {}", in(reg) level as usize, options(nostack));
+    }
+}
+
+/// Trap state which a nested interrupt overwrites
+mod trap {
+    #[inline(always)]
+    pub fn read() -> (usize, usize) {
+        let (mepc, mcause): (usize, usize);
+        unsafe {
+            core::arch::asm!(
+                "csrr {}, mepc",
+                "csrr {}, mcause",
+                out(reg) mepc,
+                out(reg) mcause,
+                options(nomem, nostack),
+            )
+        };
+        (mepc, mcause)
+    }
+
+    #[inline(always)]
+    pub unsafe fn write((mepc, mcause): (usize, usize)) {
+        core::arch::asm!(
+            "csrw mepc, {}",
+            "csrw mcause, {}",
+            in(reg) mepc,
+            in(reg) mcause,
+            options(nostack),
+        );
+    }
+}
+
+#[inline(always)]
+fn clicint(int: u16, register: usize) -> *mut u8 {
+    (CLIC_BASE.load(Ordering::Relaxed) + CLICINT + 4 * int as usize + register) as *mut u8
+}
+
+/// Configures the CLIC at `base` to use the upper `level_bits` bits of `clicintctl` for the
+/// interrupt level, and lets all levels through
+///
+/// # Safety
+///
+/// `base` must be the address of the CLIC, and interrupts must be disabled.
+pub unsafe fn clic_init(base: usize, level_bits: u8) {
+    CLIC_BASE.store(base, Ordering::Relaxed);
+
+    // `cliccfg.nlbits`
+    let cliccfg = base as *mut u8;
+    cliccfg.write_volatile((cliccfg.read_volatile() & !(0b1111 << 1)) | (level_bits << 1));
+
+    mintthresh::write(0);
+}
+
+/// Sets the level of the interrupt `int` and enables it
+///
+/// Interrupts pended by software are configured as positive edge triggered, as a write to the
+/// pending bit of a level triggered interrupt is ignored.
+///
+/// # Safety
+///
+/// Unmasking an interrupt can break critical sections.
+pub unsafe fn enable(int: u16, level: u8, software: bool) {
+    if software {
+        // `clicintattr.trig`
+        let attr = clicint(int, CLICINTATTR);
+        attr.write_volatile((attr.read_volatile() & !(0b11 << 1)) | (0b01 << 1));
+    }
+
+    clicint(int, CLICINTCTL).write_volatile(level);
+    clicint(int, CLICINTIE).write_volatile(1);
+}
+
+/// Sets the given interrupt as pending
+#[inline(always)]
+pub fn pend(int: u16) {
+    unsafe { clicint(int, CLICINTIP).write_volatile(1) }
+}
+
+/// Sets the given interrupt as not pending
+#[inline(always)]
+pub fn unpend(int: u16) {
+    unsafe { clicint(int, CLICINTIP).write_volatile(0) }
+}
+
+/// Runs a task at the level of its interrupt
+///
+/// The CLIC raised the interrupt level of the hart to the level of the interrupt on entry, so
+/// enabling interrupts lets only higher levels preempt the task. The trap state is restored
+/// before returning, as a preempting interrupt overwrites it.
+#[inline(always)]
+pub fn run<F>(_priority: u8, f: F)
+where
+    F: FnOnce(),
+{
+    let trap = trap::read();
+
+    unsafe { core::arch::asm!("csrsi mstatus, 8", options(nostack)) }; // set MIE
+    f();
+    unsafe { core::arch::asm!("csrci mstatus, 8", options(nostack)) }; // clear MIE
+
+    unsafe { trap::write(trap) };
+}
+
+/// Lock implementation using the interrupt level threshold
+///
+/// # Safety
+///
+/// The system ceiling is raised from current to `ceiling`, the CLIC level of the priority
+/// ceiling, by raising `mintthresh`. Interrupts at levels up to the threshold are not taken, so
+/// no task sharing the resource can preempt the closure.
+///
+/// The threshold is never lowered, a nested lock with a lower ceiling than the current one runs
+/// the closure directly.
+#[inline(always)]
+pub unsafe fn lock<T, R>(ptr: *mut T, ceiling: u8, f: impl FnOnce(&mut T) -> R) -> R {
+    let current = mintthresh::read();
+
+    if ceiling <= current {
+        f(&mut *ptr)
+    } else {
+        mintthresh::write(ceiling);
+        let r = f(&mut *ptr);
+        mintthresh::write(current);
+        r
+    }
+}
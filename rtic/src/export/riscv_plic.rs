@@ -0,0 +1,381 @@
+use portable_atomic::{AtomicUsize, Ordering};
+pub use riscv::interrupt;
+
+#[cfg(all(feature = "riscv-plic", not(feature = "riscv-plic-backend")))]
+compile_error!("Building for the PLIC, but 'riscv-plic-backend' not selected");
+
+/// The registers of a PLIC used by one context, i.e. one privilege mode of a hart
+///
+/// Priorities and thresholds are PLIC priorities: a source is only signaled to the context if its
+/// priority is higher than the threshold, and priority 0 never interrupts.
+pub trait PlicContext {
+    /// Reads the priority threshold of the context
+    fn threshold(&self) -> u8;
+
+    /// Writes the priority threshold of the context
+    fn set_threshold(&self, threshold: u8);
+
+    /// Writes the priority of `source`
+    fn set_priority(&self, source: u16, priority: u8);
+
+    /// Enables `source` for the context
+    fn enable(&self, source: u16);
+
+    /// Claims the highest priority pending source, 0 if there is none
+    fn claim(&self) -> u16;
+
+    /// Signals the end of the handler of a claimed `source`
+    fn complete(&self, source: u16);
+}
+
+/// Memory mapped PLIC registers, following the RISC-V PLIC specification
+struct Plic {
+    base: usize,
+    context: usize,
+}
+
+impl Plic {
+    const ENABLE: usize = 0x2000;
+    const ENABLE_STRIDE: usize = 0x80;
+    const CONTEXT: usize = 0x20_0000;
+    const CONTEXT_STRIDE: usize = 0x1000;
+
+    fn reg(&self, offset: usize) -> *mut u32 {
+        (self.base + offset) as *mut u32
+    }
+
+    fn threshold_reg(&self) -> *mut u32 {
+        self.reg(Self::CONTEXT + Self::CONTEXT_STRIDE * self.context)
+    }
+
+    fn claim_reg(&self) -> *mut u32 {
+        self.reg(Self::CONTEXT + Self::CONTEXT_STRIDE * self.context + 4)
+    }
+}
+
+impl PlicContext for Plic {
+    fn threshold(&self) -> u8 {
+        unsafe { self.threshold_reg().read_volatile() as u8 }
+    }
+
+    fn set_threshold(&self, threshold: u8) {
+        unsafe { self.threshold_reg().write_volatile(threshold as u32) }
+    }
+
+    fn set_priority(&self, source: u16, priority: u8) {
+        unsafe {
+            self.reg(4 * source as usize)
+                .write_volatile(priority as u32)
+        }
+    }
+
+    fn enable(&self, source: u16) {
+        let word = 4 * (source as usize / 32);
+        let reg = self.reg(Self::ENABLE + Self::ENABLE_STRIDE * self.context + word);
+        unsafe { reg.write_volatile(reg.read_volatile() | (1 << (source % 32))) }
+    }
+
+    fn claim(&self) -> u16 {
+        unsafe { self.claim_reg().read_volatile() as u16 }
+    }
+
+    fn complete(&self, source: u16) {
+        unsafe { self.claim_reg().write_volatile(source as u32) }
+    }
+}
+
+/// Base address of the PLIC and the context of the hart, set by `plic_init`
+static PLIC_BASE: AtomicUsize = AtomicUsize::new(0);
+static PLIC_CONTEXT: AtomicUsize = AtomicUsize::new(0);
+
+#[inline(always)]
+fn plic() -> Plic {
+    Plic {
+        base: PLIC_BASE.load(Ordering::Relaxed),
+        context: PLIC_CONTEXT.load(Ordering::Relaxed),
+    }
+}
+
+/// Trap state which a nested interrupt overwrites
+mod trap {
+    #[inline(always)]
+    pub fn read() -> (usize, usize) {
+        let (mepc, mcause): (usize, usize);
+        unsafe {
+            core::arch::asm!(
+                "csrr {}, mepc",
+                "csrr {}, mcause",
+                out(reg) mepc,
+                out(reg) mcause,
+                options(nomem, nostack),
+            )
+        };
+        (mepc, mcause)
+    }
+
+    #[inline(always)]
+    pub unsafe fn write((mepc, mcause): (usize, usize)) {
+        core::arch::asm!(
+            "csrw mepc, {}",
+            "csrw mcause, {}",
+            in(reg) mepc,
+            in(reg) mcause,
+            options(nostack),
+        );
+    }
+}
+
+/// Uses the `context` of the PLIC at `base`, lets all priorities through and enables the machine
+/// external interrupt
+///
+/// # Safety
+///
+/// `base` must be the address of the PLIC, and interrupts must be disabled.
+pub unsafe fn plic_init(base: usize, context: usize) {
+    PLIC_BASE.store(base, Ordering::Relaxed);
+    PLIC_CONTEXT.store(context, Ordering::Relaxed);
+
+    plic().set_threshold(0);
+
+    // `mie.MEIE`
+    core::arch::asm!("csrs mie, {}", in(reg) 1usize << 11, options(nostack));
+}
+
+/// Sets the priority of `source` and enables it
+///
+/// # Safety
+///
+/// Unmasking an interrupt can break critical sections.
+pub unsafe fn enable(source: u16, priority: u8) {
+    let plic = plic();
+    plic.set_priority(source, priority);
+    plic.enable(source);
+}
+
+/// Claims the pending source, runs its handler with `f` and completes it
+#[inline(always)]
+pub fn dispatch(f: impl FnOnce(u16)) {
+    dispatch_with(&plic(), f);
+}
+
+fn dispatch_with<P: PlicContext>(plic: &P, f: impl FnOnce(u16)) {
+    let source = plic.claim();
+
+    if source != 0 {
+        f(source);
+        plic.complete(source);
+    }
+}
+
+/// Runs a task at its priority
+///
+/// The threshold is raised to the priority of the task, so only higher priorities are signaled
+/// once interrupts are enabled again. The trap state is restored before returning, as a
+/// preempting interrupt overwrites it.
+#[inline(always)]
+pub fn run<F>(priority: u8, f: F)
+where
+    F: FnOnce(),
+{
+    let trap = trap::read();
+
+    run_with(&plic(), priority, || {
+        unsafe { core::arch::asm!("csrsi mstatus, 8", options(nostack)) }; // set MIE
+        f();
+        unsafe { core::arch::asm!("csrci mstatus, 8", options(nostack)) }; // clear MIE
+    });
+
+    unsafe { trap::write(trap) };
+}
+
+fn run_with<P: PlicContext>(plic: &P, priority: u8, f: impl FnOnce()) {
+    let current = plic.threshold();
+    plic.set_threshold(priority);
+    f();
+    plic.set_threshold(current);
+}
+
+/// Lock implementation using the threshold of the PLIC context
+///
+/// # Safety
+///
+/// The system ceiling is raised from current to `ceiling` by raising the threshold. Sources with
+/// a priority up to the threshold are not signaled, so no task sharing the resource can preempt
+/// the closure.
+///
+/// The threshold is never lowered, a nested lock with a lower ceiling than the current one runs
+/// the closure directly.
+#[inline(always)]
+pub unsafe fn lock<T, R>(ptr: *mut T, ceiling: u8, f: impl FnOnce(&mut T) -> R) -> R {
+    lock_with(&plic(), ceiling, || f(&mut *ptr))
+}
+
+fn lock_with<P: PlicContext, R>(plic: &P, ceiling: u8, f: impl FnOnce() -> R) -> R {
+    let current = plic.threshold();
+
+    if ceiling <= current {
+        f()
+    } else {
+        plic.set_threshold(ceiling);
+        let r = f();
+        plic.set_threshold(current);
+        r
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::cell::Cell;
+
+    /// A PLIC context with 32 sources
+    #[derive(Default)]
+    struct Model {
+        threshold: Cell<u8>,
+        priorities: [Cell<u8>; 32],
+        enabled: Cell<u32>,
+        pending: Cell<u32>,
+        claimed: Cell<u32>,
+    }
+
+    impl Model {
+        /// The source the PLIC signals to the context, if any
+        fn signaled(&self) -> Option<u16> {
+            (1..32u16)
+                .filter(|&s| self.pending.get() & self.enabled.get() & (1 << s) != 0)
+                .filter(|&s| self.priorities[s as usize].get() > self.threshold.get())
+                .max_by_key(|&s| self.priorities[s as usize].get())
+        }
+
+        /// A request of `source` through its gateway
+        fn pend(&self, source: u16) {
+            self.pending.set(self.pending.get() | 1 << source);
+        }
+    }
+
+    impl PlicContext for Model {
+        fn threshold(&self) -> u8 {
+            self.threshold.get()
+        }
+
+        fn set_threshold(&self, threshold: u8) {
+            self.threshold.set(threshold);
+        }
+
+        fn set_priority(&self, source: u16, priority: u8) {
+            self.priorities[source as usize].set(priority);
+        }
+
+        fn enable(&self, source: u16) {
+            self.enabled.set(self.enabled.get() | 1 << source);
+        }
+
+        fn claim(&self) -> u16 {
+            // The claim is independent of the threshold
+            let source = (1..32u16)
+                .filter(|&s| self.pending.get() & self.enabled.get() & (1 << s) != 0)
+                .max_by_key(|&s| self.priorities[s as usize].get())
+                .unwrap_or(0);
+
+            if source != 0 {
+                self.pending.set(self.pending.get() & !(1 << source));
+                self.claimed.set(self.claimed.get() | 1 << source);
+            }
+            source
+        }
+
+        fn complete(&self, source: u16) {
+            self.claimed.set(self.claimed.get() & !(1 << source));
+        }
+    }
+
+    fn model(sources: &[(u16, u8)]) -> Model {
+        let plic = Model::default();
+        for &(source, priority) in sources {
+            plic.set_priority(source, priority);
+            plic.enable(source);
+        }
+        plic
+    }
+
+    #[test]
+    fn lock_masks_up_to_the_ceiling() {
+        let plic = model(&[(1, 1), (2, 2), (3, 3)]);
+
+        lock_with(&plic, 2, || {
+            plic.pend(2);
+            assert_eq!(plic.signaled(), None);
+
+            plic.pend(3);
+            assert_eq!(plic.signaled(), Some(3));
+        });
+
+        assert_eq!(plic.threshold(), 0);
+        assert_eq!(plic.signaled(), Some(3));
+    }
+
+    #[test]
+    fn nested_lock_never_lowers_the_threshold() {
+        let plic = model(&[(1, 1), (2, 2), (3, 3)]);
+
+        lock_with(&plic, 3, || {
+            lock_with(&plic, 1, || assert_eq!(plic.threshold(), 3));
+            assert_eq!(plic.threshold(), 3);
+        });
+
+        assert_eq!(plic.threshold(), 0);
+    }
+
+    #[test]
+    fn lock_returns_the_closure_value() {
+        let plic = model(&[]);
+        assert_eq!(lock_with(&plic, 2, || 42), 42);
+    }
+
+    #[test]
+    fn run_lets_only_higher_priorities_preempt() {
+        let plic = model(&[(1, 1), (2, 2), (3, 3)]);
+
+        run_with(&plic, 2, || {
+            assert_eq!(plic.threshold(), 2);
+
+            plic.pend(1);
+            plic.pend(2);
+            assert_eq!(plic.signaled(), None);
+
+            // A lock in the task raises the threshold further and restores the task priority
+            lock_with(&plic, 3, || assert_eq!(plic.threshold(), 3));
+            assert_eq!(plic.threshold(), 2);
+
+            plic.pend(3);
+            assert_eq!(plic.signaled(), Some(3));
+        });
+
+        assert_eq!(plic.threshold(), 0);
+    }
+
+    #[test]
+    fn dispatch_claims_and_completes() {
+        let plic = model(&[(4, 1), (5, 2)]);
+        plic.pend(4);
+        plic.pend(5);
+
+        let mut handled = None;
+        dispatch_with(&plic, |source| {
+            assert_eq!(plic.claimed.get(), 1 << 5);
+            handled = Some(source);
+        });
+
+        assert_eq!(handled, Some(5));
+        assert_eq!(plic.claimed.get(), 0);
+        assert_eq!(plic.signaled(), Some(4));
+    }
+
+    #[test]
+    fn dispatch_ignores_spurious_claims() {
+        let plic = model(&[(4, 1)]);
+
+        dispatch_with(&plic, |_| panic!("no source is pending"));
+        assert_eq!(plic.claimed.get(), 0);
+    }
+}
@@ -0,0 +1,154 @@
+use core::{
+    future::poll_fn,
+    pin::Pin,
+    task::{Poll, Waker},
+};
+use rtic_common::{
+    dropper::OnDrop,
+    wait_queue::{Link, WaitQueue},
+};
+
+use super::queue::Queue;
+
+//============
+// SpawnQueue
+
+/// Bounded queue of spawn requests for a software task, used when all instances of the
+/// task are busy.
+///
+/// The queue is protected by critical sections, or lock-free with the `lock-free-queues`
+/// feature, see [`Queue`]. The inputs are moved into the task's executors by the dispatcher once
+/// an instance becomes available.
+pub struct SpawnQueue<T, const N: usize> {
+    queue: Queue<T, N>,
+    waiters: WaitQueue,
+}
+
+impl<T, const N: usize> Default for SpawnQueue<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> SpawnQueue<T, N> {
+    /// Create a new, empty spawn queue.
+    pub const fn new() -> Self {
+        Self {
+            queue: Queue::new(),
+            waiters: WaitQueue::new(),
+        }
+    }
+
+    /// Spawn directly with `spawn` if there are no queued requests, otherwise or if `spawn`
+    /// fails, enqueue `input`. Returns the input if the queue is full.
+    ///
+    /// An instance can finish between the failed `spawn` and the push, after the dispatcher
+    /// looked at the queue, so `pend` pends the dispatcher to look again.
+    pub fn spawn_or_enqueue(
+        &self,
+        input: T,
+        spawn: fn(T) -> Result<(), T>,
+        pend: fn(),
+    ) -> Result<(), T> {
+        // Keep the order of the requests, queued requests go first.
+        let input = if self.queue.is_empty() {
+            match spawn(input) {
+                Ok(()) => return Ok(()),
+                Err(input) => input,
+            }
+        } else {
+            input
+        };
+
+        self.queue.push(input)?;
+        pend();
+
+        Ok(())
+    }
+
+    /// Move queued requests into free instances of the task using `spawn`. Called by the
+    /// dispatcher after polling the task.
+    ///
+    /// Waiters of `wait_for` are woken for each request moved out of the queue.
+    pub fn refill(&self, spawn: fn(T) -> Result<(), T>) {
+        let mut moved = 0;
+
+        // SAFETY: Only the dispatcher of the task, which does not preempt itself, drains the queue.
+        unsafe {
+            self.queue.drain(|input| {
+                spawn(input)?;
+                moved += 1;
+                Ok(())
+            })
+        };
+
+        for _ in 0..moved {
+            match self.waiters.pop() {
+                Some(waker) => waker.wake(),
+                None => break,
+            }
+        }
+    }
+
+    /// Wait until `f` returns `Some`, `f` is retried each time there is space in the queue.
+    pub async fn wait_for<R>(&self, mut f: impl FnMut() -> Option<R>) -> R {
+        let mut link_ptr: Option<Link<Waker>> = None;
+
+        // SAFETY(link_ptr): Shadow the original definition of `link_ptr` so we can't abuse it.
+        let link_ptr = LinkPtr(&mut link_ptr as *mut Option<Link<Waker>>);
+
+        let dropper = OnDrop::new(|| {
+            // SAFETY: We only run this closure and dereference the pointer if we have
+            // exited the `poll_fn` below in the `drop(dropper)` call. The other dereference
+            // of this pointer is in the `poll_fn`.
+            if let Some(link) = unsafe { link_ptr.get() } {
+                link.remove_from_list(&self.waiters);
+            }
+        });
+
+        let r = poll_fn(|cx| {
+            // SAFETY: See the `dropper`.
+            let link = unsafe { link_ptr.get() };
+
+            // Register before trying, so that space freed in between is not missed.
+            if link.as_ref().is_none_or(|link| link.is_popped()) {
+                let link_ref = link.insert(Link::new(cx.waker().clone()));
+
+                // SAFETY(new_unchecked): The address to the link is stable as it is defined
+                // outside this stack frame.
+                // SAFETY(push): `link_ref` lifetime comes from `link_ptr` that is shadowed, and
+                // we make sure in `dropper` that the link is removed from the queue before
+                // dropping `link_ptr` AND `dropper` makes sure that the shadowed `link_ptr`
+                // lives until the end of the stack frame.
+                unsafe { self.waiters.push(Pin::new_unchecked(link_ref)) };
+            }
+
+            match f() {
+                Some(r) => Poll::Ready(r),
+                None => Poll::Pending,
+            }
+        })
+        .await;
+
+        // Make sure the link is removed from the queue.
+        drop(dropper);
+
+        r
+    }
+}
+
+/// Convenience for the pointer to the link of a waiter.
+#[derive(Clone, Copy)]
+struct LinkPtr(*mut Option<Link<Waker>>);
+
+impl LinkPtr {
+    /// This will dereference the pointer stored within and give out an `&mut`.
+    #[allow(clippy::mut_from_ref)]
+    unsafe fn get(&self) -> &mut Option<Link<Waker>> {
+        &mut *self.0
+    }
+}
+
+unsafe impl Send for LinkPtr {}
+
+unsafe impl Sync for LinkPtr {}
@@ -0,0 +1,160 @@
+use super::msp;
+use crate::stack::{Level, PAINT};
+use portable_atomic::{AtomicUsize, Ordering};
+
+#[cfg(not(feature = "cortex-m"))]
+compile_error!(
+    "'stack-usage' needs the memory layout of `cortex-m-rt`, only the Cortex-M backends have it"
+);
+
+/// Words below the stack pointer of the painting function that are left unpainted, for its
+/// own frame
+const MARGIN: usize = 8;
+
+/// Consecutive painted words below the entry of a task that end its part of the stack, with
+/// `stack-usage-bounded-scan`
+#[cfg(feature = "stack-usage-bounded-scan")]
+const RUN: usize = 8;
+
+/// The bottom and top of the stack, `0` if it was not painted
+static BOTTOM: AtomicUsize = AtomicUsize::new(0);
+static TOP: AtomicUsize = AtomicUsize::new(0);
+
+/// The lowest used address seen by the tasks, before they repainted their part of the stack
+static DEEPEST: AtomicUsize = AtomicUsize::new(usize::MAX);
+
+/// Paints the free stack, before `init`
+///
+/// # Safety
+///
+/// Must run once, in `main` with interrupts disabled.
+pub unsafe fn paint() {
+    extern "C" {
+        static _stack_start: u32;
+        static __ebss: u32;
+    }
+
+    let top = &_stack_start as *const _ as usize;
+    let bottom = &__ebss as *const _ as usize;
+
+    // With `flip-link` the stack is below `.bss` and its bottom is not known
+    if top <= bottom {
+        return;
+    }
+
+    paint_from(bottom);
+    BOTTOM.store(bottom, Ordering::Relaxed);
+    TOP.store(top, Ordering::Relaxed);
+}
+
+/// Paints the stack from `from` up to the frame of this function
+#[inline(never)]
+unsafe fn paint_from(from: usize) {
+    let end = msp::read() as usize - MARGIN * 4;
+    let mut word = from as *mut u32;
+
+    while (word as usize) < end {
+        word.write_volatile(PAINT);
+        word = word.add(1);
+    }
+}
+
+/// The lowest address between `bottom` and `top` that does not hold the paint
+fn lowest_used(bottom: usize, top: usize) -> usize {
+    let mut word = bottom as *const u32;
+
+    // SAFETY: The words between the bottom and the top of the stack are readable.
+    while (word as usize) < top && unsafe { word.read_volatile() } == PAINT {
+        word = unsafe { word.add(1) };
+    }
+
+    word as usize
+}
+
+/// The lowest address below `entry`, down to `bottom`, that does not hold the paint
+///
+/// Scans from `bottom`, so that unwritten parts of the frames do not end the scan early.
+#[cfg(not(feature = "stack-usage-bounded-scan"))]
+fn lowest_used_below(bottom: usize, entry: usize) -> usize {
+    lowest_used(bottom, entry)
+}
+
+/// The lowest address below `entry`, down to `bottom`, that does not hold the paint, stopping at
+/// the first `RUN` painted words
+///
+/// The stack below `entry` was painted when the task started, so the scan only covers the stack
+/// used by this run. Unwritten parts of a frame longer than `RUN` words end it early: the run is
+/// measured short, and the words used below the gap are not repainted, so they are counted by
+/// the next runs and by `thread_mode_usage`.
+#[cfg(feature = "stack-usage-bounded-scan")]
+fn lowest_used_below(bottom: usize, entry: usize) -> usize {
+    let mut lowest = entry;
+    let mut word = entry as *const u32;
+    let mut painted = 0;
+
+    while painted < RUN && word as usize > bottom {
+        // SAFETY: The words between the bottom of the stack and `entry` are readable.
+        word = unsafe { word.sub(1) };
+
+        if unsafe { word.read_volatile() } == PAINT {
+            painted += 1;
+        } else {
+            painted = 0;
+            lowest = word as usize;
+        }
+    }
+
+    lowest
+}
+
+/// The stack pointer at the start of a task
+#[inline(always)]
+pub fn enter() -> usize {
+    msp::read() as usize
+}
+
+/// Records the stack used since `enter` returned `entry` for the priority `level`
+///
+/// The tasks preempting this one already repainted their part of the stack, the lowest used
+/// word is the deepest point of this run. It is repainted for the next runs and the tasks of
+/// the lower levels. The scan starts at the bottom of the stack, with `stack-usage-bounded-scan`
+/// both the scan and the repaint are bounded by the stack used by this run instead.
+#[inline(never)]
+pub fn exit(level: &Level, entry: usize) {
+    let bottom = BOTTOM.load(Ordering::Relaxed);
+    if bottom == 0 {
+        return;
+    }
+
+    let lowest = lowest_used_below(bottom, entry);
+    level.record(entry.saturating_sub(lowest));
+    DEEPEST.fetch_min(lowest, Ordering::Relaxed);
+
+    // SAFETY: The stack below this frame is not in use.
+    unsafe { paint_from(lowest) };
+}
+
+/// The deepest stack usage so far, in bytes
+///
+/// Scans the whole stack from its bottom, see [`crate::stack::high_water`].
+pub fn high_water() -> usize {
+    let (bottom, top) = (BOTTOM.load(Ordering::Relaxed), TOP.load(Ordering::Relaxed));
+    if bottom == 0 {
+        return 0;
+    }
+
+    top - lowest_used(bottom, top).min(DEEPEST.load(Ordering::Relaxed))
+}
+
+/// The stack usage of thread mode, in bytes
+///
+/// The tasks repaint their part of the stack, so the lowest used word from the bottom belongs to
+/// thread mode. Scans the whole stack, see [`crate::stack::thread_mode_usage`].
+pub fn thread_mode_usage() -> usize {
+    let (bottom, top) = (BOTTOM.load(Ordering::Relaxed), TOP.load(Ordering::Relaxed));
+    if bottom == 0 {
+        return 0;
+    }
+
+    top - lowest_used(bottom, top)
+}
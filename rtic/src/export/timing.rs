@@ -0,0 +1,58 @@
+use crate::timing::Measurement;
+use cortex_m::peripheral::{DCB, DWT};
+use portable_atomic::{AtomicU32, Ordering};
+
+#[cfg(not(feature = "cortex-m-basepri"))]
+compile_error!(
+    "'measure-timing' needs the DWT cycle counter, only 'thumbv7-backend' and 'thumbv8main-backend' have it"
+);
+
+/// Cycles spent in the tasks that preempted the innermost measurement
+static PREEMPTED: AtomicU32 = AtomicU32::new(0);
+
+/// Starts the cycle counter, before `init`
+///
+/// # Safety
+///
+/// Writes the DCB and DWT registers, must run before the core peripherals are used.
+pub unsafe fn enable() {
+    const DEMCR_TRCENA: u32 = 1 << 24;
+    const DWT_CTRL_CYCCNTENA: u32 = 1 << 0;
+
+    (*DCB::PTR).demcr.modify(|w| w | DEMCR_TRCENA);
+    DWT::unlock();
+    (*DWT::PTR).ctrl.modify(|w| w | DWT_CTRL_CYCCNTENA);
+}
+
+/// Measures the run of a hardware task or the poll of a software task
+#[inline(always)]
+pub fn measure_task<R>(measurement: &Measurement, f: impl FnOnce() -> R) -> R {
+    measure(measurement, true, f)
+}
+
+/// Measures the time a resource is locked
+#[inline(always)]
+pub fn measure_lock<R>(measurement: &Measurement, f: impl FnOnce() -> R) -> R {
+    measure(measurement, false, f)
+}
+
+#[inline(always)]
+fn measure<R>(measurement: &Measurement, preempts: bool, f: impl FnOnce() -> R) -> R {
+    // A preemption between the reads of the counter and of `PREEMPTED` is counted towards
+    // the measurement, which errs on the side of overestimating it.
+    let start = DWT::cycle_count();
+    let outer = PREEMPTED.swap(0, Ordering::Relaxed);
+
+    let r = f();
+
+    let inner = PREEMPTED.load(Ordering::Relaxed);
+    let elapsed = DWT::cycle_count().wrapping_sub(start);
+    measurement.record(elapsed.saturating_sub(inner));
+
+    // A task preempts the code it interrupted for all its cycles, while a lock is part of the
+    // code it is taken in and only passes on the preemptions.
+    let preempted = if preempts { elapsed } else { inner };
+    PREEMPTED.store(outer.wrapping_add(preempted), Ordering::Relaxed);
+
+    r
+}
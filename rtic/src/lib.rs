@@ -34,7 +34,7 @@
 #![allow(unexpected_cfgs)]
 
 pub use rtic_core::{prelude as mutex_prelude, Exclusive, Mutex};
-pub use rtic_macros::app;
+pub use rtic_macros::{app, task_module};
 
 /// module `mutex::prelude` provides `Mutex` and multi-lock variants. Recommended over `mutex_prelude`
 pub mod mutex {
@@ -45,8 +45,25 @@ pub mod mutex {
 #[doc(hidden)]
 pub mod export;
 
+pub use executors::ExecutorInfo;
+pub use export::executor::{Cancelled, TaskHandle};
+// The pending bits of the PLIC are read-only, sources are pended through the device. An external
+// backend provides `pend` in its own runtime crate.
+#[cfg(not(any(feature = "riscv-plic", feature = "external-backend")))]
 pub use export::pend;
 
+pub mod executors;
+
+pub mod multicore;
+
+pub use multicore::{CrossCore, Doorbell};
+
+#[cfg(feature = "measure-timing")]
+pub mod timing;
+
+#[cfg(feature = "stack-usage")]
+pub mod stack;
+
 use core::cell::UnsafeCell;
 
 /// Internal replacement for `static mut T`
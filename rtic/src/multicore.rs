@@ -0,0 +1,57 @@
+//! Support for applications running one `#[app]` per core
+
+/// Types that can be shared between the cores of a multi-core application
+///
+/// `#[shared]` resources marked `#[cross_core]` must implement this trait. They are accessed by
+/// shared reference from the tasks of their application and from the other cores, without locks.
+///
+/// # Safety
+///
+/// Every operation through `&Self` must be sound when it runs concurrently on several cores. This
+/// is stronger than `Sync`: types that synchronize with interrupt-free critical sections are
+/// `Sync` on a single core, but not across cores.
+pub unsafe trait CrossCore: Sync {}
+
+macro_rules! cross_core_atomics {
+    ($($ty:ty),*) => {
+        $(
+            // SAFETY: Atomic loads and stores are atomic across cores.
+            unsafe impl CrossCore for $ty {}
+        )*
+    };
+}
+
+cross_core_atomics!(
+    core::sync::atomic::AtomicBool,
+    core::sync::atomic::AtomicU8,
+    core::sync::atomic::AtomicU16,
+    core::sync::atomic::AtomicU32,
+    core::sync::atomic::AtomicUsize,
+    core::sync::atomic::AtomicI8,
+    core::sync::atomic::AtomicI16,
+    core::sync::atomic::AtomicI32,
+    core::sync::atomic::AtomicIsize
+);
+
+// SAFETY: Atomic loads and stores are atomic across cores.
+unsafe impl<T> CrossCore for core::sync::atomic::AtomicPtr<T> {}
+
+// SAFETY: Only shared references can be obtained from a shared reference.
+unsafe impl<T: CrossCore, const N: usize> CrossCore for [T; N] {}
+
+/// The inter-processor interrupt of a core, which delivers the spawns of the tasks of its
+/// application with a `mailbox` from the other cores
+///
+/// Passed to `#[app(ipi = .., doorbell = ..)]`, where `ipi` is the interrupt the doorbell raises.
+pub trait Doorbell {
+    /// Raises the inter-processor interrupt on the core of the application, called by the
+    /// spawning core after the request is in the mailbox
+    fn ring();
+
+    /// Clears the inter-processor interrupt, called by the core of the application at the start
+    /// of its handler
+    ///
+    /// RTIC also runs the handler itself to deliver requests which did not find a free instance
+    /// of their task, so this must handle being called when the doorbell did not ring.
+    fn acknowledge();
+}
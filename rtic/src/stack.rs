@@ -0,0 +1,71 @@
+//! Stack usage measurements, enabled by the `stack-usage` feature of the Cortex-M backends.
+//!
+//! The stack is painted with a known pattern before `init`, and the used part is found again
+//! by looking for the first word that no longer holds the pattern. With the stack resource
+//! policy all tasks share this stack, a task preempts the tasks of lower priority on top of
+//! their stack, so the worst case usage is the sum of the worst case of each priority level.
+//!
+//! The application module gets a `stack` module with the table `LEVELS`, the deepest run of the
+//! tasks of each priority level, and `worst_case()`, their sum plus the thread mode usage.
+//!
+//! Only the default memory layout of `cortex-m-rt`, with the stack above `.bss`, is supported.
+//! With `flip-link` the stack is not painted and all measurements are `0`. Words of the stack
+//! that happen to hold the pattern, e.g. in arrays that are never written, are not counted.
+//!
+//! At the end of each task the stack is scanned from its bottom. The `stack-usage-bounded-scan`
+//! feature only scans down from the stack pointer at the start of the task, until 8 words in a
+//! row hold the pattern, which is faster for large stacks. A task whose frame has more unwritten
+//! words in a row is then measured short, and the words it used below them are not painted
+//! again, so `worst_case()` and [`thread_mode_usage`] are no longer upper bounds.
+
+use portable_atomic::{AtomicUsize, Ordering};
+
+/// The pattern the free stack is painted with
+pub(crate) const PAINT: u32 = 0xCCCC_CCCC;
+
+/// The deepest measured stack usage of the tasks of a priority level
+pub struct Level {
+    priority: u8,
+    max: AtomicUsize,
+}
+
+impl Level {
+    #[doc(hidden)]
+    pub const fn new(priority: u8) -> Self {
+        Self {
+            priority,
+            max: AtomicUsize::new(0),
+        }
+    }
+
+    /// The priority level.
+    pub fn priority(&self) -> u8 {
+        self.priority
+    }
+
+    /// The deepest measured stack usage in bytes, including the exception frames of the
+    /// higher priority tasks preempting this level.
+    pub fn max_bytes(&self) -> usize {
+        self.max.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn record(&self, bytes: usize) {
+        self.max.fetch_max(bytes, Ordering::Relaxed);
+    }
+}
+
+/// The deepest stack usage so far in bytes, of all priority levels together.
+///
+/// Scans the stack from its bottom, which takes time proportional to the stack size.
+pub fn high_water() -> usize {
+    crate::export::stack::high_water()
+}
+
+/// The deepest stack usage of thread mode, i.e. `init`, `idle` and the priority 0 tasks, in
+/// bytes.
+///
+/// Only meaningful when called from thread mode, as the running tasks are counted otherwise.
+/// Scans the stack from its bottom, which takes time proportional to the stack size.
+pub fn thread_mode_usage() -> usize {
+    crate::export::stack::thread_mode_usage()
+}
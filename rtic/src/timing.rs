@@ -0,0 +1,48 @@
+//! Execution time measurements with the DWT cycle counter, enabled by the `measure-timing`
+//! feature of the `thumbv7-backend` and `thumbv8main-backend`.
+//!
+//! The application module gets a `timing` module with two tables of [`Measurement`]s:
+//!
+//! - `TASKS`, the longest run of each hardware task and the longest poll of each software task
+//! - `RESOURCES`, the longest time each shared resource was locked
+//!
+//! Times are in CPU cycles and exclude the time spent in tasks preempting the measured code, so
+//! they can be used as the execution and blocking times of a schedulability analysis. `init`
+//! and `idle` are not measured.
+
+use portable_atomic::{AtomicU32, Ordering};
+
+/// The longest measured duration of a task or lock
+pub struct Measurement {
+    name: &'static str,
+    max: AtomicU32,
+}
+
+impl Measurement {
+    #[doc(hidden)]
+    pub const fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            max: AtomicU32::new(0),
+        }
+    }
+
+    /// Name of the task or resource.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// The longest measured duration in cycles, `0` until the task or lock has run.
+    pub fn max_cycles(&self) -> u32 {
+        self.max.load(Ordering::Relaxed)
+    }
+
+    /// Forget the measurements so far, e.g. to leave out the start-up of the application.
+    pub fn reset(&self) {
+        self.max.store(0, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record(&self, cycles: u32) {
+        self.max.fetch_max(cycles, Ordering::Relaxed);
+    }
+}
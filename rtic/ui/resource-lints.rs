@@ -0,0 +1,41 @@
+#![no_main]
+#![deny(deprecated)]
+
+#[rtic::app(device = lm3s6965, dispatchers = [SSI0, QEI0], resource_lints = true)]
+mod app {
+    #[shared]
+    struct Shared {
+        unused: u32,
+        single: u32,
+        config: u32,
+        listed: u32,
+    }
+
+    #[local]
+    struct Local {}
+
+    #[init]
+    fn init(_: init::Context) -> (Shared, Local) {
+        (
+            Shared {
+                unused: 0,
+                single: 0,
+                config: 0,
+                listed: 0,
+            },
+            Local {},
+        )
+    }
+
+    #[task(priority = 1, shared = [single, config, listed])]
+    async fn low(mut cx: low::Context) {
+        let config = cx.shared.config.lock(|config| *config);
+        cx.shared.single.lock(|single| *single += config);
+    }
+
+    #[task(priority = 2, shared = [config, listed])]
+    async fn high(mut cx: high::Context) {
+        let _ = cx.shared.config.lock(|config| *config + 1);
+        cx.shared.listed.lock(|listed| *listed += 1);
+    }
+}
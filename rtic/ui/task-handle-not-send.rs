@@ -0,0 +1,20 @@
+#![no_main]
+
+fn assert_send<T: Send>() {}
+
+#[rtic::app(device = lm3s6965, dispatchers = [SSI0])]
+mod app {
+    #[shared]
+    struct Shared {}
+
+    #[local]
+    struct Local {}
+
+    #[init]
+    fn init(_: init::Context) -> (Shared, Local) {
+        // The handle of a task with a non-`Send` output can not be sent to another task
+        super::assert_send::<rtic::TaskHandle<*const u8>>();
+
+        (Shared {}, Local {})
+    }
+}
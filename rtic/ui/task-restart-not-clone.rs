@@ -0,0 +1,24 @@
+#![no_main]
+
+struct NotClone;
+
+#[rtic::app(device = lm3s6965, dispatchers = [SSI0])]
+mod app {
+    use super::NotClone;
+
+    #[shared]
+    struct Shared {}
+
+    #[local]
+    struct Local {}
+
+    #[init]
+    fn init(_: init::Context) -> (Shared, Local) {
+        (Shared {}, Local {})
+    }
+
+    #[task(priority = 1, restart = always)]
+    async fn foo(_: foo::Context, _arg: NotClone) -> Result<(), ()> {
+        Err(())
+    }
+}
@@ -58,9 +58,27 @@ impl Package {
         partial: bool,
     ) -> Vec<Option<String>> {
         match self {
-            Package::Rtic => vec![Some(target.and_features(backend.to_rtic_feature()))],
+            Package::Rtic => {
+                let features = backend.to_rtic_feature();
+                std::iter::once(features.to_string())
+                    .chain(
+                        backend
+                            .to_measurement_features()
+                            .map(|m| format!("{features},{m}")),
+                    )
+                    .map(|f| Some(target.and_features(&f)))
+                    .collect()
+            }
             Package::RticMacros => {
-                vec![Some(backend.to_rtic_macros_feature().to_string())]
+                let features = backend.to_rtic_macros_feature();
+                std::iter::once(features.to_string())
+                    .chain(
+                        backend
+                            .to_measurement_features()
+                            .map(|m| format!("{features},{m}")),
+                    )
+                    .map(Some)
+                    .collect()
             }
             Package::RticMonotonics => {
                 let features = if partial {
@@ -139,7 +157,7 @@ impl TestMetadata {
             },
             Package::RticCommon => CargoCommand::Test {
                 package: Some(package.name()),
-                features: Some("testing".to_owned()),
+                features: Some("testing,lock-free".to_owned()),
                 test: None,
                 deny_warnings: true,
             },
@@ -151,7 +169,7 @@ impl TestMetadata {
             },
             Package::RticTime => CargoCommand::Test {
                 package: Some(package.name()),
-                features: Some("critical-section/std".into()),
+                features: Some("critical-section/std,stats".into()),
                 test: None,
                 deny_warnings: true,
             },
@@ -169,6 +187,8 @@ pub enum Backends {
     RiscvEsp32C3,
     Riscv32ImcClint, // not working yet (issues with portable-atomic features...)
     Riscv32ImacClint,
+    Riscv32ImacClic,
+    Riscv32ImacPlic,
 }
 
 impl Backends {
@@ -180,7 +200,9 @@ impl Backends {
             Backends::Thumbv8Base => ARMV8MBASE,
             Backends::Thumbv8Main => ARMV8MMAIN,
             Backends::Riscv32ImcClint | Backends::RiscvEsp32C3 => RISCV32IMC,
-            Backends::Riscv32ImacClint => RISCV32IMAC,
+            Backends::Riscv32ImacClint | Backends::Riscv32ImacClic | Backends::Riscv32ImacPlic => {
+                RISCV32IMAC
+            }
         }
     }
 
@@ -193,6 +215,18 @@ impl Backends {
             Backends::Thumbv8Main => "thumbv8main-backend",
             Backends::RiscvEsp32C3 => "riscv-esp32c3-backend",
             Backends::Riscv32ImcClint | Backends::Riscv32ImacClint => "riscv-clint-backend",
+            Backends::Riscv32ImacClic => "riscv-clic-backend",
+            Backends::Riscv32ImacPlic => "riscv-plic-backend",
+        }
+    }
+    /// The `stack-usage` and `measure-timing` features supported by the backend, which are
+    /// checked in a build of their own
+    #[allow(clippy::wrong_self_convention)]
+    pub fn to_measurement_features(&self) -> Option<&'static str> {
+        match self {
+            Backends::Thumbv7 | Backends::Thumbv8Main => Some("stack-usage,measure-timing"),
+            Backends::Thumbv6 | Backends::Thumbv8Base => Some("stack-usage"),
+            _ => None,
         }
     }
     #[allow(clippy::wrong_self_convention)]
@@ -202,6 +236,8 @@ impl Backends {
             Backends::Thumbv7 | Backends::Thumbv8Main => "cortex-m-basepri",
             Backends::RiscvEsp32C3 => "riscv-esp32c3",
             Backends::Riscv32ImcClint | Backends::Riscv32ImacClint => "riscv-clint",
+            Backends::Riscv32ImacClic => "riscv-clic",
+            Backends::Riscv32ImacPlic => "riscv-plic",
         }
     }
 }
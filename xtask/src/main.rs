@@ -113,7 +113,8 @@ fn main() -> anyhow::Result<()> {
     let examples: Vec<_> = std::fs::read_dir(examples_path)?
         .filter_map(|p| p.ok())
         .map(|p| p.path())
-        .filter(|p| p.display().to_string().ends_with(".rs"))
+        // Single file examples, or directories with a `main.rs` for examples split into modules
+        .filter(|p| p.display().to_string().ends_with(".rs") || p.join("main.rs").exists())
         .map(|path| path.file_stem().unwrap().to_str().unwrap().to_string())
         .collect();
 